@@ -0,0 +1,482 @@
+//! Ancestor chains (method resolution order) of every class and module in a `Workspace`, built
+//! from superclasses and `include`/`prepend`/`extend` calls.
+//!
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+    constants::{
+        defined_constants, definition_path, enclosing_namespace, lexical_namespace, ConstPath,
+    },
+    node::{in_singleton_class, index_by_id},
+    parser::parse,
+    properties::Properties,
+    scope_gate,
+    workspace::Workspace,
+    Node,
+};
+
+/// An entry in an ancestor chain: where to look for a method.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Ancestor {
+    /// The instance methods of a class or module.
+    Instance(Vec<String>),
+
+    /// The singleton methods (ex. `def self.foo`) of a class or module.
+    Singleton(Vec<String>),
+}
+
+/// The ancestor chains of a class or module, nearest first.
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Ancestors {
+    pub(crate) instance: Vec<Ancestor>,
+    pub(crate) singleton: Vec<Ancestor>,
+}
+
+impl Ancestors {
+    /// Where methods called on instances are looked up, ex. `[Foo, Bar, Object, Kernel,
+    /// BasicObject]` for `class Foo; include Bar; end`.
+    ///
+    pub fn instance(&self) -> &[Ancestor] {
+        self.instance.as_ref()
+    }
+
+    /// Where methods called on the class or module itself are looked up.
+    ///
+    pub fn singleton(&self) -> &[Ancestor] {
+        self.singleton.as_ref()
+    }
+}
+
+/// Ancestor chains of every class and module in the workspace, keyed by fully qualified name.
+/// Superclass and mixin constants are resolved lexically; ones that aren't defined in the
+/// workspace are kept as-is, as the end of their branch of the chain.
+///
+#[salsa::tracked]
+pub fn ancestor_chains(
+    db: &dyn crate::db::Db,
+    workspace: Workspace,
+) -> BTreeMap<Vec<String>, Ancestors> {
+    let defined = defined_constants(db, workspace);
+    let mut declarations: BTreeMap<Vec<String>, Declaration> = BTreeMap::new();
+
+    for &file_source in workspace.file_sources(db) {
+        let nodes = parse(db, file_source);
+        let by_id = index_by_id(&nodes);
+
+        for node in nodes.iter() {
+            match node.properties() {
+                Properties::Class(class) => {
+                    let Some(path) = definition_path(&nodes, &by_id, node) else {
+                        continue;
+                    };
+
+                    // The superclass is looked up from outside the class.
+                    let namespace = lexical_namespace(&nodes, &by_id, node);
+                    let superclass = class.superclass_id.and_then(|id| {
+                        resolve_const(&by_id, by_id.get(&id)?, &namespace, &defined)
+                    });
+
+                    let declaration = declarations.entry(path).or_default();
+                    declaration.is_class = true;
+
+                    if declaration.superclass.is_none() {
+                        declaration.superclass = superclass;
+                    }
+                }
+                Properties::Module(_) => {
+                    if let Some(path) = definition_path(&nodes, &by_id, node) {
+                        declarations.entry(path).or_default();
+                    }
+                }
+                Properties::Send(send) => {
+                    let Some(mut kind) = MixinKind::from_method_name(&send.method_name) else {
+                        continue;
+                    };
+
+                    let receiverless = send.recv_id.map_or(true, |id| {
+                        by_id.get(&id).map_or(false, |recv| {
+                            matches!(recv.properties(), Properties::Self_(_))
+                        })
+                    });
+
+                    // Only calls made directly in a class or module body.
+                    if !receiverless
+                        || !matches!(
+                            node.scope_gate().last(),
+                            Some(scope_gate::Node::Class(_) | scope_gate::Node::Module(_))
+                        )
+                    {
+                        continue;
+                    }
+
+                    let Some(owner) = enclosing_namespace(&nodes, &by_id, node) else {
+                        continue;
+                    };
+
+                    // `include` in a `class << self` is an `extend` for the class itself.
+                    if in_singleton_class(&nodes, node) {
+                        match kind {
+                            MixinKind::Include | MixinKind::Prepend => kind = MixinKind::Extend,
+                            MixinKind::Extend => continue,
+                        }
+                    }
+
+                    // `include A, B` works like `include B; include A`.
+                    let modules: Vec<Vec<String>> = send
+                        .arg_ids
+                        .iter()
+                        .rev()
+                        .filter_map(|id| by_id.get(id))
+                        .filter_map(|arg| match arg.properties() {
+                            Properties::Self_(_) => Some(owner.clone()),
+                            _ => resolve_const(&by_id, arg, &owner, &defined),
+                        })
+                        .collect();
+
+                    declarations
+                        .entry(owner)
+                        .or_default()
+                        .mixins
+                        .extend(modules.into_iter().map(|module| Mixin { kind, module }));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let linearizer = Linearizer {
+        declarations: &declarations,
+    };
+
+    declarations
+        .iter()
+        .map(|(path, declaration)| {
+            let ancestors = Ancestors {
+                instance: linearizer
+                    .instance_chain(path, declaration.is_class, &mut Vec::new())
+                    .into_iter()
+                    .map(Ancestor::Instance)
+                    .collect(),
+                singleton: linearizer.singleton_chain(path, declaration.is_class, &mut Vec::new()),
+            };
+
+            (path.clone(), ancestors)
+        })
+        .collect()
+}
+
+fn resolve_const(
+    by_id: &HashMap<usize, &Node>,
+    node: &Node,
+    namespace: &[String],
+    defined: &BTreeSet<Vec<String>>,
+) -> Option<Vec<String>> {
+    match node.properties() {
+        Properties::Const(const_) => {
+            Some(ConstPath::new(by_id, &const_.name, const_.scope_id)?.resolve(namespace, defined))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MixinKind {
+    Include,
+    Prepend,
+    Extend,
+}
+
+impl MixinKind {
+    fn from_method_name(method_name: &str) -> Option<Self> {
+        match method_name {
+            "include" => Some(Self::Include),
+            "prepend" => Some(Self::Prepend),
+            "extend" => Some(Self::Extend),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Mixin {
+    kind: MixinKind,
+    module: Vec<String>,
+}
+
+/// Everything the workspace says about a class or module, possibly across several files.
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct Declaration {
+    is_class: bool,
+    superclass: Option<Vec<String>>,
+
+    /// In the order they're called.
+    mixins: Vec<Mixin>,
+}
+
+struct Linearizer<'a> {
+    declarations: &'a BTreeMap<Vec<String>, Declaration>,
+}
+
+impl<'a> Linearizer<'a> {
+    /// Modules mixed in with `kind`, most recently mixed in first (which is the order they appear
+    /// in the ancestor chain).
+    ///
+    fn mixins(&self, path: &[String], kind: MixinKind) -> Vec<&'a [String]> {
+        self.declarations
+            .get(path)
+            .map(|declaration| {
+                declaration
+                    .mixins
+                    .iter()
+                    .rev()
+                    .filter(|mixin| mixin.kind == kind)
+                    .map(|mixin| mixin.module.as_slice())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// `is_class` is only used when `path` isn't defined in the workspace, since then we can't
+    /// otherwise know.
+    ///
+    fn superclass(&self, path: &[String], is_class: bool) -> Option<Vec<String>> {
+        let declaration = self.declarations.get(path);
+
+        if !declaration.map_or(is_class, |d| d.is_class) {
+            return None;
+        }
+
+        if let Some(superclass) = declaration.and_then(|d| d.superclass.clone()) {
+            return Some(superclass);
+        }
+
+        match path {
+            [name] if name == "BasicObject" => None,
+            [name] if name == "Object" => Some(vec!["BasicObject".to_string()]),
+            [name] if name == "Class" => Some(vec!["Module".to_string()]),
+            _ => Some(vec!["Object".to_string()]),
+        }
+    }
+
+    fn instance_chain(
+        &self,
+        path: &[String],
+        is_class: bool,
+        visiting: &mut Vec<Vec<String>>,
+    ) -> Vec<Vec<String>> {
+        if visiting.iter().any(|v| v == path) {
+            return Vec::new();
+        }
+
+        visiting.push(path.to_vec());
+
+        let mut chain = Vec::new();
+
+        for module in self.mixins(path, MixinKind::Prepend) {
+            chain.extend(self.instance_chain(module, false, visiting));
+        }
+
+        chain.push(path.to_vec());
+
+        for module in self.mixins(path, MixinKind::Include) {
+            chain.extend(self.instance_chain(module, false, visiting));
+        }
+
+        if matches!(path, [name] if name == "Object") {
+            chain.push(vec!["Kernel".to_string()]);
+        }
+
+        if let Some(superclass) = self.superclass(path, is_class) {
+            chain.extend(self.instance_chain(&superclass, true, visiting));
+        }
+
+        visiting.pop();
+
+        dedup_keeping_last(chain)
+    }
+
+    fn singleton_chain(
+        &self,
+        path: &[String],
+        is_class: bool,
+        visiting: &mut Vec<Vec<String>>,
+    ) -> Vec<Ancestor> {
+        if visiting.iter().any(|v| v == path) {
+            return Vec::new();
+        }
+
+        visiting.push(path.to_vec());
+
+        let mut chain = vec![Ancestor::Singleton(path.to_vec())];
+
+        for module in self.mixins(path, MixinKind::Extend) {
+            chain.extend(
+                self.instance_chain(module, false, &mut Vec::new())
+                    .into_iter()
+                    .map(Ancestor::Instance),
+            );
+        }
+
+        let is_class = self.declarations.get(path).map_or(is_class, |d| d.is_class);
+
+        match self.superclass(path, is_class) {
+            Some(superclass) => chain.extend(self.singleton_chain(&superclass, true, visiting)),
+            None => {
+                // The end of the line: the class or module itself is an instance of `Class` or
+                // `Module`.
+                let meta = if is_class { "Class" } else { "Module" };
+
+                chain.extend(
+                    self.instance_chain(&[meta.to_string()], true, &mut Vec::new())
+                        .into_iter()
+                        .map(Ancestor::Instance),
+                );
+            }
+        }
+
+        visiting.pop();
+
+        dedup_keeping_last(chain)
+    }
+}
+
+/// When a module shows up more than once, Ruby only keeps it in the furthest spot (ex. a module
+/// that's included by both a class and its superclass is only the superclass's ancestor).
+///
+fn dedup_keeping_last<T: PartialEq>(chain: Vec<T>) -> Vec<T> {
+    let mut deduped: Vec<T> = Vec::with_capacity(chain.len());
+
+    for item in chain.into_iter().rev() {
+        if !deduped.contains(&item) {
+            deduped.push(item);
+        }
+    }
+
+    deduped.reverse();
+
+    deduped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ropey::Rope;
+
+    use super::*;
+    use crate::{db::Database, parser::FileSource};
+
+    const MODULES: &str = r#"module Greeting; end
+module Loud; end
+module Tracing; end
+module ClassMethods; end
+
+module Admin
+  module Helpers; end
+
+  class Panel
+    include Helpers
+  end
+end"#;
+
+    const CLASSES: &str = r#"class Base
+  include Greeting
+end
+
+class Child < Base
+  include Loud
+  prepend Tracing
+  extend ClassMethods
+  include Greeting
+end"#;
+
+    fn path(path: &str) -> Vec<String> {
+        path.split("::").map(ToString::to_string).collect()
+    }
+
+    fn instance(paths: &[&str]) -> Vec<Ancestor> {
+        paths.iter().map(|p| Ancestor::Instance(path(p))).collect()
+    }
+
+    fn setup(db: &Database) -> BTreeMap<Vec<String>, Ancestors> {
+        let modules = FileSource::new(
+            db,
+            PathBuf::from("/tmp/modules.rb"),
+            Rope::from_str(MODULES),
+        );
+        let classes = FileSource::new(
+            db,
+            PathBuf::from("/tmp/classes.rb"),
+            Rope::from_str(CLASSES),
+        );
+        let workspace = Workspace::new(db, vec![modules, classes]);
+
+        ancestor_chains(db, workspace)
+    }
+
+    #[test]
+    fn instance_chain_test() {
+        let db = Database::default();
+        let chains = setup(&db);
+
+        // `Greeting` is already included by `Base`, so `Child` doesn't get it again.
+        assert_eq!(
+            instance(&[
+                "Tracing",
+                "Child",
+                "Loud",
+                "Base",
+                "Greeting",
+                "Object",
+                "Kernel",
+                "BasicObject"
+            ]),
+            chains[&path("Child")].instance()
+        );
+
+        // `Helpers` resolves lexically to `Admin::Helpers`.
+        assert_eq!(
+            instance(&[
+                "Admin::Panel",
+                "Admin::Helpers",
+                "Object",
+                "Kernel",
+                "BasicObject"
+            ]),
+            chains[&path("Admin::Panel")].instance()
+        );
+
+        assert_eq!(instance(&["Loud"]), chains[&path("Loud")].instance());
+    }
+
+    #[test]
+    fn singleton_chain_test() {
+        let db = Database::default();
+        let chains = setup(&db);
+
+        let mut expected = vec![
+            Ancestor::Singleton(path("Child")),
+            Ancestor::Instance(path("ClassMethods")),
+            Ancestor::Singleton(path("Base")),
+            Ancestor::Singleton(path("Object")),
+            Ancestor::Singleton(path("BasicObject")),
+        ];
+        expected.extend(instance(&[
+            "Class",
+            "Module",
+            "Object",
+            "Kernel",
+            "BasicObject",
+        ]));
+
+        assert_eq!(expected, chains[&path("Child")].singleton());
+
+        let mut expected = vec![Ancestor::Singleton(path("Loud"))];
+        expected.extend(instance(&["Module", "Object", "Kernel", "BasicObject"]));
+
+        assert_eq!(expected, chains[&path("Loud")].singleton());
+    }
+}
@@ -0,0 +1,396 @@
+//! Comments and magic comments. lib-ruby-parser reports these separately from the AST, so
+//! `crate::parser::parse` accumulates them (see `crate::parser::Comments` and
+//! `crate::parser::MagicCommentEntries`) and the queries here make sense of them.
+//!
+use std::collections::BTreeMap;
+
+use ropey::Rope;
+
+use crate::{
+    node::{Contains, Loc},
+    parser::{parse, Comments, FileSource, MagicCommentEntries},
+    properties::Properties,
+    sorbet::signatures,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommentKind {
+    /// `# ...`
+    Inline,
+
+    /// `=begin ... =end`
+    Document,
+}
+
+/// A single comment, as written in the source.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Comment {
+    pub(crate) kind: CommentKind,
+    pub(crate) loc: Loc,
+
+    /// The source text of the comment, including `#` (or `=begin`/`=end`).
+    pub(crate) text: String,
+}
+
+impl Comment {
+    pub(crate) fn new(comment: &lib_ruby_parser::source::Comment, code: &Rope) -> Self {
+        let loc = Loc::from(comment.location);
+
+        let kind = match comment.kind {
+            lib_ruby_parser::source::CommentType::Document => CommentKind::Document,
+            lib_ruby_parser::source::CommentType::Inline
+            | lib_ruby_parser::source::CommentType::Unknown => CommentKind::Inline,
+        };
+
+        Self {
+            kind,
+            loc,
+            text: code
+                .byte_slice(loc.begin..loc.end)
+                .to_string()
+                .trim_end()
+                .to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> CommentKind {
+        self.kind
+    }
+
+    pub fn loc(&self) -> Loc {
+        self.loc
+    }
+
+    pub fn text(&self) -> &str {
+        self.text.as_ref()
+    }
+
+    /// The text of the comment without its markers, ex. `foo` for `# foo`.
+    ///
+    pub fn body(&self) -> String {
+        match self.kind {
+            CommentKind::Inline => {
+                let body = self.text.strip_prefix('#').unwrap_or(&self.text);
+                body.strip_prefix(' ').unwrap_or(body).to_string()
+            }
+            CommentKind::Document => {
+                let lines: Vec<&str> = self.text.lines().collect();
+                let inner = lines
+                    .get(1..lines.len().saturating_sub(1))
+                    .unwrap_or_default();
+
+                inner.join("\n")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MagicCommentKind {
+    Encoding,
+    FrozenStringLiteral,
+    WarnIndent,
+    ShareableConstantValue,
+}
+
+/// A single magic comment, ex. `# frozen_string_literal: true`.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MagicComment {
+    pub(crate) kind: MagicCommentKind,
+    pub(crate) key_l: Loc,
+    pub(crate) value_l: Loc,
+    pub(crate) value: String,
+}
+
+impl MagicComment {
+    pub(crate) fn new(magic_comment: &lib_ruby_parser::source::MagicComment, code: &Rope) -> Self {
+        let kind = match magic_comment.kind {
+            lib_ruby_parser::source::MagicCommentKind::Encoding => MagicCommentKind::Encoding,
+            lib_ruby_parser::source::MagicCommentKind::FrozenStringLiteral => {
+                MagicCommentKind::FrozenStringLiteral
+            }
+            lib_ruby_parser::source::MagicCommentKind::WarnIndent => MagicCommentKind::WarnIndent,
+            lib_ruby_parser::source::MagicCommentKind::ShareableConstantValue => {
+                MagicCommentKind::ShareableConstantValue
+            }
+        };
+
+        let value_l = Loc::from(magic_comment.value_l);
+
+        Self {
+            kind,
+            key_l: Loc::from(magic_comment.key_l),
+            value_l,
+            value: code.byte_slice(value_l.begin..value_l.end).to_string(),
+        }
+    }
+
+    pub fn kind(&self) -> MagicCommentKind {
+        self.kind
+    }
+
+    pub fn key_l(&self) -> Loc {
+        self.key_l
+    }
+
+    pub fn value_l(&self) -> Loc {
+        self.value_l
+    }
+
+    pub fn value(&self) -> &str {
+        self.value.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShareableConstantValue {
+    None,
+    Literal,
+    ExperimentalEverything,
+    ExperimentalCopy,
+}
+
+impl ShareableConstantValue {
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "none" => Some(Self::None),
+            "literal" => Some(Self::Literal),
+            "experimental_everything" => Some(Self::ExperimentalEverything),
+            "experimental_copy" => Some(Self::ExperimentalCopy),
+            _ => None,
+        }
+    }
+}
+
+/// The magic comments of a file, parsed. Values that Ruby wouldn't accept (ex.
+/// `# frozen_string_literal: yes`) are left as `None`. If a magic comment is given more than
+/// once, the first one wins.
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct MagicComments {
+    pub(crate) encoding: Option<String>,
+    pub(crate) frozen_string_literal: Option<bool>,
+    pub(crate) warn_indent: Option<bool>,
+    pub(crate) shareable_constant_value: Option<ShareableConstantValue>,
+}
+
+impl MagicComments {
+    pub fn encoding(&self) -> Option<&str> {
+        self.encoding.as_deref()
+    }
+
+    pub fn frozen_string_literal(&self) -> Option<bool> {
+        self.frozen_string_literal
+    }
+
+    pub fn warn_indent(&self) -> Option<bool> {
+        self.warn_indent
+    }
+
+    pub fn shareable_constant_value(&self) -> Option<ShareableConstantValue> {
+        self.shareable_constant_value
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// All comments in the file, in source order.
+///
+#[salsa::tracked]
+pub fn comments(db: &dyn crate::db::Db, file_source: FileSource) -> Vec<Comment> {
+    let mut comments = parse::accumulated::<Comments>(db, file_source);
+    comments.sort_by_key(|comment| comment.loc.begin);
+
+    comments
+}
+
+#[salsa::tracked]
+pub fn magic_comments(db: &dyn crate::db::Db, file_source: FileSource) -> MagicComments {
+    let mut magic_comments = MagicComments::default();
+
+    for entry in parse::accumulated::<MagicCommentEntries>(db, file_source) {
+        match entry.kind {
+            MagicCommentKind::Encoding => {
+                magic_comments.encoding.get_or_insert(entry.value);
+            }
+            MagicCommentKind::FrozenStringLiteral => {
+                if magic_comments.frozen_string_literal.is_none() {
+                    magic_comments.frozen_string_literal = parse_bool(&entry.value);
+                }
+            }
+            MagicCommentKind::WarnIndent => {
+                if magic_comments.warn_indent.is_none() {
+                    magic_comments.warn_indent = parse_bool(&entry.value);
+                }
+            }
+            MagicCommentKind::ShareableConstantValue => {
+                if magic_comments.shareable_constant_value.is_none() {
+                    magic_comments.shareable_constant_value =
+                        ShareableConstantValue::parse(&entry.value);
+                }
+            }
+        }
+    }
+
+    magic_comments
+}
+
+/// Doc comments, keyed by the ID of the node they document. A doc comment is the block of
+/// comments on the lines directly above a class, module, constant, method (or its Sorbet `sig`) or
+/// receiverless call (ex. `attr_reader`), where each comment is on a line of its own. Magic
+/// comments are skipped.
+///
+#[salsa::tracked]
+pub fn doc_comments(db: &dyn crate::db::Db, file_source: FileSource) -> BTreeMap<usize, String> {
+    let code = file_source.code(db);
+    let nodes = parse(db, file_source);
+    let signatures = signatures(db, file_source);
+
+    let magic_key_ls: Vec<Loc> = parse::accumulated::<MagicCommentEntries>(db, file_source)
+        .into_iter()
+        .map(|entry| entry.key_l)
+        .collect();
+
+    let comments: Vec<Comment> = comments(db, file_source)
+        .into_iter()
+        .filter(|comment| on_own_line(code, comment))
+        .filter(|comment| !magic_key_ls.iter().any(|key_l| comment.loc.contains(key_l)))
+        .collect();
+
+    nodes
+        .iter()
+        .filter(|node| match node.properties() {
+            Properties::Class(_)
+            | Properties::Module(_)
+            | Properties::Casgn(_)
+            | Properties::Def(_)
+            | Properties::Defs(_) => true,
+            Properties::Send(send) => send.recv_id.is_none(),
+            _ => false,
+        })
+        .filter_map(|node| {
+            // A method's doc comment goes above its Sorbet `sig`, if it has one.
+            let begin = signatures
+                .get(&node.id())
+                .map_or(node.expression_l().begin, |sig| sig.loc.begin);
+            let mut next_line = code.byte_to_line(begin);
+
+            let mut block: Vec<String> = comments
+                .iter()
+                .rev()
+                .skip_while(|comment| comment.loc.begin >= begin)
+                .map_while(|comment| {
+                    (last_line(code, comment) + 1 == next_line).then(|| {
+                        next_line = code.byte_to_line(comment.loc.begin);
+                        comment.body()
+                    })
+                })
+                .collect();
+
+            if block.is_empty() {
+                return None;
+            }
+
+            block.reverse();
+
+            Some((node.id(), block.join("\n")))
+        })
+        .collect()
+}
+
+/// Is `comment` the only thing on its line(s)? (As opposed to trailing some code.)
+///
+pub(crate) fn on_own_line(code: &Rope, comment: &Comment) -> bool {
+    let line_start = code.line_to_byte(code.byte_to_line(comment.loc.begin));
+
+    code.byte_slice(line_start..comment.loc.begin)
+        .chars()
+        .all(char::is_whitespace)
+}
+
+pub(crate) fn last_line(code: &Rope, comment: &Comment) -> usize {
+    code.byte_to_line(comment.loc.end.saturating_sub(1).max(comment.loc.begin))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::db::Database;
+
+    const CODE: &str = r#"# frozen_string_literal: true
+# shareable_constant_value: literal
+class Foo
+  # Not attached; there's a blank line after it.
+
+  # The bar.
+  #
+  # More about the bar.
+  def bar
+    1 # Not a doc comment.
+  end
+
+  def baz; end # Trailing.
+  def qux; end
+
+=begin
+Documented
+the old way.
+=end
+  QUUX = 1
+end"#;
+
+    #[test]
+    fn magic_comments_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let magic_comments = magic_comments(&db, file_source);
+        assert_eq!(Some(true), magic_comments.frozen_string_literal());
+        assert_eq!(
+            Some(ShareableConstantValue::Literal),
+            magic_comments.shareable_constant_value()
+        );
+        assert_eq!(None, magic_comments.encoding());
+        assert_eq!(None, magic_comments.warn_indent());
+
+        assert_eq!(9, comments(&db, file_source).len());
+    }
+
+    #[test]
+    fn doc_comments_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let nodes = parse(&db, file_source);
+        let doc_comments = doc_comments(&db, file_source);
+
+        let doc_for = |name: &str| {
+            nodes
+                .iter()
+                .find(|node| match node.properties() {
+                    Properties::Class(class) => class.name == name,
+                    Properties::Def(def) => def.name == name,
+                    Properties::Casgn(casgn) => casgn.name == name,
+                    _ => false,
+                })
+                .and_then(|node| doc_comments.get(&node.id()))
+                .map(String::as_str)
+        };
+
+        // Only magic comments are above it.
+        assert_eq!(None, doc_for("Foo"));
+        assert_eq!(Some("The bar.\n\nMore about the bar."), doc_for("bar"));
+        assert_eq!(None, doc_for("qux"));
+        assert_eq!(Some("Documented\nthe old way."), doc_for("QUUX"));
+    }
+}
@@ -0,0 +1,653 @@
+//! Method completion after `foo.` and `foo&.`. The receiver's type is inferred (see
+//! `crate::types`), and the methods of each class and module in its ancestor chain are offered,
+//! nearest first; private ones are left out, since they can't be called with an explicit receiver
+//! (other than `self`). When the receiver's type can't be inferred, every method name that's
+//! called in the project is offered instead, most used first.
+//!
+//! Inside a call's arguments, the called method's keyword parameters are offered as `name:`.
+//!
+use std::collections::{BTreeMap, BTreeSet};
+
+use lsp_types::{CompletionItem, CompletionItemKind, CompletionItemLabelDetails};
+use ropey::Rope;
+
+use crate::{
+    ancestors::Ancestor,
+    constants::method_owner,
+    hover::{parameter_labels, parameters},
+    node::{in_singleton_class, index_by_id},
+    nodes::Visibility,
+    parser::{parse, FileSource},
+    properties::Properties,
+    signature_help::{call_at, pair_keyword, parameter_kinds, resolve_call, ParameterKind},
+    sorbet::signatures,
+    types::{declared_return_types, Inference, Type, TypeEnvironment},
+    workspace::Workspace,
+    yard::{yard_directives, DirectiveKind},
+    Node,
+};
+
+#[salsa::input]
+pub struct CompletionQuery {
+    pub environment: TypeEnvironment,
+    pub file_source: FileSource,
+
+    /// Where the cursor is, ex. right after the `.` of `user.`, or after `user.na`.
+    pub offset: usize,
+}
+
+/// A method defined in the workspace, as completion shows it.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodEntry {
+    pub(crate) name: String,
+    pub(crate) visibility: Visibility,
+
+    /// ex. `def greet(name, greeting = "Hi")`, preceded by its `sig` if it has one.
+    pub(crate) signature: String,
+}
+
+impl MethodEntry {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    pub fn callable_with_explicit_receiver(&self) -> bool {
+        self.visibility.callable_with_explicit_receiver()
+    }
+}
+
+/// The methods defined in the workspace, by owner: the class or module path, and whether they're
+/// singleton methods. Methods defined by YARD `@!method`/`@!attribute` directives are included.
+///
+#[salsa::tracked]
+pub fn workspace_methods(
+    db: &dyn crate::db::Db,
+    workspace: Workspace,
+) -> BTreeMap<(Vec<String>, bool), Vec<MethodEntry>> {
+    let mut methods: BTreeMap<(Vec<String>, bool), Vec<MethodEntry>> = BTreeMap::new();
+
+    for &file_source in workspace.file_sources(db) {
+        let code = file_source.code(db);
+        let nodes = parse(db, file_source);
+        let by_id = index_by_id(&nodes);
+        let signatures = signatures(db, file_source);
+
+        for node in nodes.iter() {
+            let (name, args_id, visibility, singleton, prefix) = match node.properties() {
+                Properties::Def(def) => (
+                    &def.name,
+                    def.args_id,
+                    def.visibility,
+                    in_singleton_class(&nodes, node),
+                    "",
+                ),
+                Properties::Defs(defs) => {
+                    (&defs.name, defs.args_id, defs.visibility, true, "self.")
+                }
+                _ => continue,
+            };
+
+            let owner = method_owner(&nodes, &by_id, node);
+
+            let mut signature = format!("def {prefix}{name}{}", parameters(&by_id, code, args_id));
+
+            if let Some(sig) = signatures.get(&node.id()) {
+                signature = format!("{}\n{signature}", sig.to_ruby());
+            }
+
+            let mut add = |singleton: bool, visibility: Visibility| {
+                methods
+                    .entry((owner.clone(), singleton))
+                    .or_default()
+                    .push(MethodEntry {
+                        name: name.clone(),
+                        visibility,
+                        signature: signature.clone(),
+                    });
+            };
+
+            match visibility {
+                // A private instance method, plus a public singleton method.
+                Visibility::ModuleFunction => {
+                    add(false, Visibility::Private);
+                    add(true, Visibility::Public);
+                }
+                _ => add(singleton, visibility),
+            }
+        }
+
+        for directive in yard_directives(db, file_source) {
+            let owner = (directive.namespace.clone(), directive.singleton);
+
+            for name in directive.method_names() {
+                let signature = match directive.kind {
+                    DirectiveKind::Method => format!("def {name}{}", directive.parameters),
+                    DirectiveKind::Attribute { .. } => format!("attribute {name}"),
+                };
+
+                methods.entry(owner.clone()).or_default().push(MethodEntry {
+                    name,
+                    visibility: Visibility::Public,
+                    signature,
+                });
+            }
+        }
+    }
+
+    methods
+}
+
+/// Every method name that's called in the workspace, with how many times, most called first.
+/// Operators (ex. `+` in `a + b`) aren't included, since they aren't called after a `.`.
+///
+#[salsa::tracked]
+pub fn called_method_names(db: &dyn crate::db::Db, workspace: Workspace) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+
+    for &file_source in workspace.file_sources(db) {
+        for node in parse(db, file_source).iter() {
+            let method_name = match node.properties() {
+                Properties::Send(send) => &send.method_name,
+                Properties::CSend(csend) => &csend.method_name,
+                _ => continue,
+            };
+
+            if method_name.starts_with(|char: char| char.is_alphabetic() || char == '_') {
+                *counts.entry(method_name.clone()).or_default() += 1;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|(a_name, a_count), (b_name, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_name.cmp(b_name))
+    });
+
+    ranked
+}
+
+/// Completion items for the method being typed after a `.` or `&.` at the query's `offset`. Empty
+/// if the cursor isn't after one.
+///
+#[salsa::tracked]
+pub fn method_completions(db: &dyn crate::db::Db, query: CompletionQuery) -> Vec<CompletionItem> {
+    let environment = query.environment(db);
+    let file_source = query.file_source(db);
+    let offset = query.offset(db);
+    let code = file_source.code(db);
+    let nodes = parse(db, file_source);
+
+    let Some((dot_begin, prefix)) = dot_before(code, offset) else {
+        return Vec::new();
+    };
+
+    let inference = Inference::new(db, environment, file_source, &nodes);
+    let receiver = receiver_node(&nodes, dot_begin);
+
+    let receiver_type = receiver.and_then(|receiver| inference.infer(receiver));
+
+    let Some(receiver_type) = receiver_type else {
+        // The call that's being typed (ex. `user.na`) doesn't count.
+        let typed = nodes.iter().find_map(|node| match node.properties() {
+            Properties::Send(send) if send.dot_l.map_or(false, |l| l.begin == dot_begin) => {
+                Some(send.method_name.as_str())
+            }
+            Properties::CSend(csend) if csend.dot_l.begin == dot_begin => {
+                Some(csend.method_name.as_str())
+            }
+            _ => None,
+        });
+
+        let mut called: Vec<(String, usize)> = called_method_names(db, environment.workspace(db))
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(&prefix))
+            .filter_map(|(name, count)| {
+                let count = if typed == Some(name.as_str()) {
+                    count - 1
+                } else {
+                    count
+                };
+
+                (count > 0).then_some((name, count))
+            })
+            .collect();
+        called.sort_by(|(_, a_count), (_, b_count)| b_count.cmp(a_count));
+
+        return called
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (name, count))| CompletionItem {
+                label: name,
+                kind: Some(CompletionItemKind::METHOD),
+                detail: Some(format!(
+                    "called {count} time{}",
+                    if count == 1 { "" } else { "s" }
+                )),
+                sort_text: Some(format!("{rank:05}")),
+                ..Default::default()
+            })
+            .collect();
+    };
+
+    // `self.foo` can call private methods.
+    let explicit_self = receiver.map_or(false, |receiver| {
+        matches!(receiver.properties(), Properties::Self_(_))
+    });
+
+    // Protected methods can only be called from within the class hierarchy that defines them.
+    let caller_owners = receiver
+        .map(|receiver| inference.method_owners(&inference.self_type(receiver)))
+        .unwrap_or_default();
+
+    let methods = workspace_methods(db, environment.workspace(db));
+    let declared = declared_return_types(db, environment);
+    let private_methods = environment.private_methods(db);
+
+    let mut seen = BTreeSet::new();
+    let mut items = Vec::new();
+
+    for member in receiver_type.members() {
+        for owner in inference.method_owners(member) {
+            let (path, singleton) = match &owner {
+                Ancestor::Instance(path) => (path, false),
+                Ancestor::Singleton(path) => (path, true),
+            };
+
+            let fully_qualified_prefix = if path.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", path.join("::"), if singleton { '.' } else { '#' })
+            };
+
+            let return_type = |name: &str| {
+                declared
+                    .get(&format!("{fully_qualified_prefix}{name}"))
+                    .filter(|annotation| Type::parse(annotation, member).is_some())
+                    .cloned()
+            };
+
+            for method in methods
+                .get(&(path.clone(), singleton))
+                .into_iter()
+                .flatten()
+            {
+                // Overridden methods, and private ones, aren't offered (but still hide the
+                // methods they override).
+                let callable = explicit_self
+                    || match method.visibility {
+                        Visibility::Protected => caller_owners.contains(&owner),
+                        _ => method.callable_with_explicit_receiver(),
+                    };
+
+                if !seen.insert(method.name.clone()) || !callable {
+                    continue;
+                }
+
+                items.push(item(
+                    &method.name,
+                    Some(method.signature.clone()),
+                    return_type(&method.name),
+                    items.len(),
+                ));
+            }
+
+            // Methods that are only declared (ex. in RBS), not defined in the workspace.
+            if fully_qualified_prefix.is_empty() {
+                continue;
+            }
+
+            for (fully_qualified_name, annotation) in
+                declared.range(fully_qualified_prefix.clone()..)
+            {
+                let Some(name) = fully_qualified_name.strip_prefix(&fully_qualified_prefix) else {
+                    break;
+                };
+
+                let callable = explicit_self || !private_methods.contains(fully_qualified_name);
+
+                // ex. `Foo::Bar#baz` after `Foo#`.
+                if name.contains(['#', '.', ':']) || !seen.insert(name.to_string()) || !callable {
+                    continue;
+                }
+
+                items.push(item(name, None, Some(annotation.clone()), items.len()));
+            }
+        }
+    }
+
+    items.retain(|item| item.label.starts_with(&prefix));
+    items
+}
+
+/// Completion items for the keyword arguments of the call whose arguments the query's `offset` is
+/// in, ex. `note:` in `deposit(5, n)`. Keywords that are already given are left out, and nothing
+/// is offered while typing a keyword argument's value.
+///
+#[salsa::tracked]
+pub fn keyword_completions(db: &dyn crate::db::Db, query: CompletionQuery) -> Vec<CompletionItem> {
+    let environment = query.environment(db);
+    let file_source = query.file_source(db);
+    let offset = query.offset(db);
+    let code = file_source.code(db);
+    let nodes = parse(db, file_source);
+    let by_id = index_by_id(&nodes);
+
+    if dot_before(code, offset).is_some() {
+        return Vec::new();
+    }
+
+    let prefix = identifier_before(code, offset);
+    let prefix_begin = offset - prefix.len();
+
+    let Some(call) = call_at(&nodes, prefix_begin) else {
+        return Vec::new();
+    };
+
+    let pairs: Vec<&Node> = call
+        .arg_ids
+        .iter()
+        .filter_map(|id| by_id.get(id))
+        .filter_map(|arg| match arg.properties() {
+            Properties::Kwargs(kwargs) => Some(&kwargs.pair_ids),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|id| by_id.get(id).copied())
+        .collect();
+
+    // In a value, ex. after `note: `.
+    let in_value = pairs.iter().any(|pair| match pair.properties() {
+        Properties::Pair(pair) => by_id.get(&pair.value_id).map_or(false, |value| {
+            value.expression_l().begin <= prefix_begin && offset <= value.expression_l().end
+        }),
+        _ => false,
+    });
+
+    if in_value {
+        return Vec::new();
+    }
+
+    let given: Vec<&str> = pairs
+        .iter()
+        .filter_map(|pair| pair_keyword(&by_id, pair))
+        .collect();
+
+    let inference = Inference::new(db, environment, file_source, &nodes);
+    let Some((definition_source, definition_id)) =
+        resolve_call(db, environment.workspace(db), &inference, &nodes, &call)
+    else {
+        return Vec::new();
+    };
+
+    let definition_nodes = parse(db, definition_source);
+    let definition_by_id = index_by_id(&definition_nodes);
+    let Some(definition) = definition_by_id.get(&definition_id) else {
+        return Vec::new();
+    };
+
+    let args_id = match definition.properties() {
+        Properties::Def(def) => def.args_id,
+        Properties::Defs(defs) => defs.args_id,
+        _ => None,
+    };
+
+    let labels = parameter_labels(&definition_by_id, definition_source.code(db), args_id);
+
+    labels
+        .unwrap_or_default()
+        .into_iter()
+        .zip(parameter_kinds(&definition_by_id, definition))
+        .filter_map(|(label, kind)| match kind {
+            ParameterKind::Keyword(name) => Some((label, name)),
+            _ => None,
+        })
+        .filter(|(_, name)| name.starts_with(&prefix) && !given.contains(&name.as_str()))
+        .enumerate()
+        .map(|(rank, (label, name))| CompletionItem {
+            label: format!("{name}:"),
+            kind: Some(CompletionItemKind::PROPERTY),
+            detail: Some(label),
+            sort_text: Some(format!("{rank:05}")),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn item(
+    name: &str,
+    signature: Option<String>,
+    return_type: Option<String>,
+    rank: usize,
+) -> CompletionItem {
+    CompletionItem {
+        label: name.to_string(),
+        kind: Some(CompletionItemKind::METHOD),
+        detail: signature,
+        label_details: return_type.map(|return_type| CompletionItemLabelDetails {
+            detail: None,
+            description: Some(return_type),
+        }),
+        sort_text: Some(format!("{rank:05}")),
+        ..Default::default()
+    }
+}
+
+/// The part of an identifier that's right before `offset`, ex. `no` in `deposit(5, no`.
+///
+fn identifier_before(code: &Rope, offset: usize) -> String {
+    let before = code.byte_slice(..offset).to_string();
+    let prefix_start = before
+        .char_indices()
+        .rev()
+        .find(|(_, char)| !(char.is_alphanumeric() || *char == '_'))
+        .map_or(0, |(index, char)| index + char.len_utf8());
+
+    before[prefix_start..].to_string()
+}
+
+/// If `offset` is right after a `.` or `&.` (and possibly part of a method name), where the dot
+/// begins and the part of the name that's typed.
+///
+fn dot_before(code: &Rope, offset: usize) -> Option<(usize, String)> {
+    let prefix = identifier_before(code, offset);
+    let prefix_start = offset - prefix.len();
+    let before_prefix = code.byte_slice(..prefix_start).to_string();
+
+    let dot_begin = if before_prefix.ends_with("&.") {
+        prefix_start - 2
+    } else if before_prefix.ends_with('.') && !before_prefix.ends_with("..") {
+        prefix_start - 1
+    } else {
+        return None;
+    };
+
+    Some((dot_begin, prefix))
+}
+
+/// The receiver of the call whose dot begins at `dot_begin`. When the call parsed (ex. `user.na`)
+/// that's its `recv`; otherwise (ex. `user.` with nothing after it yet) it's the outermost
+/// expression that ends where the dot begins.
+///
+fn receiver_node(nodes: &[Node], dot_begin: usize) -> Option<&Node> {
+    let by_id = index_by_id(nodes);
+
+    let recv_id = nodes.iter().find_map(|node| match node.properties() {
+        Properties::Send(send) if send.dot_l.map_or(false, |l| l.begin == dot_begin) => {
+            send.recv_id
+        }
+        Properties::CSend(csend) if csend.dot_l.begin == dot_begin => Some(csend.recv_id),
+        _ => None,
+    });
+
+    if let Some(receiver) = recv_id.and_then(|id| by_id.get(&id)) {
+        return Some(receiver);
+    }
+
+    nodes
+        .iter()
+        .filter(|node| node.expression_l().end == dot_begin)
+        .max_by_key(|node| node.expression_l().end - node.expression_l().begin)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::db::Database;
+
+    const CODE: &str = r#"class Account
+  def initialize(owner)
+    @owner = owner
+  end
+
+  # @return [Integer]
+  def balance; end
+
+  def deposit(amount, note: nil); end
+
+  def to_s
+    self.audit
+  end
+
+  protected def ledger; end
+
+  private
+
+  def audit; end
+end
+
+class Savings < Account
+  def balance; end
+
+  def rate
+    Account.new("bank").le
+  end
+end
+
+account = Savings.new("ada")
+account.balance
+"#;
+
+    fn labels(db: &Database, file_source: FileSource, offset: usize) -> Vec<String> {
+        let workspace = Workspace::new(db, vec![file_source]);
+        let environment = TypeEnvironment::new(
+            db,
+            workspace,
+            None,
+            BTreeMap::from([
+                ("Object#frozen?".to_string(), "bool".to_string()),
+                ("Kernel#puts".to_string(), "nil".to_string()),
+            ]),
+            BTreeSet::from(["Kernel#puts".to_string()]),
+        );
+        let query = CompletionQuery::new(db, environment, file_source, offset);
+
+        method_completions(db, query)
+            .into_iter()
+            .map(|item| item.label)
+            .collect()
+    }
+
+    #[test]
+    fn method_completions_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let offset = CODE.rfind("account.").unwrap() + "account.".len();
+
+        // Nearest first; `audit`, `initialize` and the declared `puts` are private, and `ledger`
+        // is protected.
+        assert_eq!(
+            vec!["balance", "rate", "deposit", "to_s", "frozen?"],
+            labels(&db, file_source, offset)
+        );
+
+        // `self.` can call private methods.
+        let offset = CODE.find("self.audit").unwrap() + "self.au".len();
+        assert_eq!(vec!["audit"], labels(&db, file_source, offset));
+
+        // Declared ones included.
+        let offset = CODE.find("self.audit").unwrap() + "self.".len();
+        assert!(labels(&db, file_source, offset).contains(&"puts".to_string()));
+
+        // Within the hierarchy, protected methods can be called on other instances.
+        let offset = CODE.find(".le").unwrap() + ".le".len();
+        assert_eq!(vec!["ledger"], labels(&db, file_source, offset));
+
+        // Not after a dot.
+        let offset = CODE.find("def rate").unwrap() + "def ra".len();
+        assert!(labels(&db, file_source, offset).is_empty());
+    }
+
+    #[test]
+    fn keyword_completions_test() {
+        let db = Database::default();
+        let code = r#"class Account
+  def deposit(amount, at:, note: nil); end
+end
+
+account = Account.new
+account.deposit(5, n, at: 1)
+account.deposit(5, a)
+account.deposit(5, at: n)
+"#;
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(code));
+        let workspace = Workspace::new(&db, vec![file_source]);
+        let environment =
+            TypeEnvironment::new(&db, workspace, None, BTreeMap::new(), BTreeSet::new());
+
+        let items = |needle: &str| {
+            let offset = code.find(needle).unwrap() + needle.len();
+            let query = CompletionQuery::new(&db, environment, file_source, offset);
+
+            keyword_completions(&db, query)
+                .into_iter()
+                .map(|item| (item.label, item.detail.unwrap_or_default()))
+                .collect::<Vec<_>>()
+        };
+
+        // `at:` is already given.
+        assert_eq!(
+            vec![("note:".to_string(), "note: nil".to_string())],
+            items("deposit(5, n")
+        );
+        assert_eq!(
+            vec![("at:".to_string(), "at:".to_string())],
+            items("deposit(5, a")
+        );
+
+        // Typing a value, not a keyword.
+        assert!(items("at: n").is_empty());
+
+        // Not in a call's arguments.
+        assert!(items("def deposit").is_empty());
+    }
+
+    #[test]
+    fn fallback_test() {
+        let db = Database::default();
+        let code = "items.each { |item| item.save }\nrecord.save\nunknown.s";
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(code));
+
+        let workspace = Workspace::new(&db, vec![file_source]);
+        let environment =
+            TypeEnvironment::new(&db, workspace, None, BTreeMap::new(), BTreeSet::new());
+        let query = CompletionQuery::new(&db, environment, file_source, code.len());
+
+        // `unknown.s` is still being typed, so isn't a call to `s`.
+        let items = method_completions(&db, query);
+        assert_eq!(
+            vec![("save", Some("called 2 times"))],
+            items
+                .iter()
+                .map(|item| (item.label.as_str(), item.detail.as_deref()))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Helpers for working out which constant a `Const`, `Casgn`, `Class` or `Module` node refers to.
+//!
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+    node::{index_by_id, Contains},
+    parser::{parse, FileSource},
+    properties::Properties,
+    workspace::Workspace,
+    Node,
+};
+
+/// A constant path as written in the source, ex. `Foo::Bar` or `::Foo`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ConstPath {
+    pub(crate) absolute: bool,
+    pub(crate) segments: Vec<String>,
+}
+
+impl ConstPath {
+    /// Builds the path for a `Const` or `Casgn` by following its chain of `scope_id`s. Returns
+    /// `None` if some part of the scope isn't a constant (ex. `foo::Bar`).
+    ///
+    pub(crate) fn new(
+        by_id: &HashMap<usize, &Node>,
+        name: &str,
+        scope_id: Option<usize>,
+    ) -> Option<Self> {
+        let mut segments = vec![name.to_string()];
+        let mut absolute = false;
+        let mut next_id = scope_id;
+
+        while let Some(id) = next_id {
+            match by_id.get(&id)?.properties() {
+                Properties::Const(const_) => {
+                    segments.push(const_.name.clone());
+                    next_id = const_.scope_id;
+                }
+                Properties::Cbase(_) => {
+                    absolute = true;
+                    next_id = None;
+                }
+                _ => return None,
+            }
+        }
+
+        segments.reverse();
+
+        Some(Self { absolute, segments })
+    }
+
+    /// Lexically resolves the path from within `namespace`: starting at the innermost namespace
+    /// and working outward, the first candidate that's in `defined` wins. If none are, the path is
+    /// treated as top-level.
+    ///
+    pub(crate) fn resolve(
+        &self,
+        namespace: &[String],
+        defined: &BTreeSet<Vec<String>>,
+    ) -> Vec<String> {
+        if self.absolute {
+            return self.segments.clone();
+        }
+
+        (0..=namespace.len())
+            .rev()
+            .map(|depth| {
+                namespace[..depth]
+                    .iter()
+                    .cloned()
+                    .chain(self.segments.iter().cloned())
+                    .collect::<Vec<_>>()
+            })
+            .find(|candidate| defined.contains(candidate))
+            .unwrap_or_else(|| self.segments.clone())
+    }
+
+    /// The fully qualified name of a constant that's defined (as opposed to referenced) with this
+    /// path from within `namespace`.
+    ///
+    pub(crate) fn definition(&self, namespace: &[String]) -> Vec<String> {
+        if self.absolute {
+            return self.segments.clone();
+        }
+
+        namespace
+            .iter()
+            .cloned()
+            .chain(self.segments.iter().cloned())
+            .collect()
+    }
+}
+
+/// The fully qualified name of the constant that `node` defines, if it's a `Class`, `Module` or
+/// `Casgn`.
+///
+pub(crate) fn definition_path(
+    nodes: &[Node],
+    by_id: &HashMap<usize, &Node>,
+    node: &Node,
+) -> Option<Vec<String>> {
+    let path = match node.properties() {
+        Properties::Class(class) => name_path(by_id, class.name_id)?,
+        Properties::Module(module) => name_path(by_id, module.name_id)?,
+        Properties::Casgn(casgn) => ConstPath::new(by_id, &casgn.name, casgn.scope_id)?,
+        _ => return None,
+    };
+
+    Some(path.definition(&lexical_namespace(nodes, by_id, node)))
+}
+
+/// The path of the `Const` that names a `Class` or `Module`.
+///
+pub(crate) fn name_path(by_id: &HashMap<usize, &Node>, name_id: usize) -> Option<ConstPath> {
+    match by_id.get(&name_id)?.properties() {
+        Properties::Const(const_) => ConstPath::new(by_id, &const_.name, const_.scope_id),
+        _ => None,
+    }
+}
+
+/// The fully qualified name of the innermost class or module whose body contains `node`.
+///
+pub(crate) fn enclosing_namespace(
+    nodes: &[Node],
+    by_id: &HashMap<usize, &Node>,
+    node: &Node,
+) -> Option<Vec<String>> {
+    // Nothing outside of a class or module body can be in one.
+    if node.scope_gate().namespace().is_empty() {
+        return None;
+    }
+
+    nodes
+        .iter()
+        .filter(|other| other.id() != node.id())
+        .filter(|other| body_contains(by_id, other, node))
+        .max_by_key(|other| other.expression_l().begin())
+        .and_then(|other| definition_path(nodes, by_id, other))
+}
+
+/// Is `node` in the body of `class_or_module`, as opposed to its name or superclass (which are
+/// looked up from the outer namespace)?
+///
+fn body_contains(by_id: &HashMap<usize, &Node>, class_or_module: &Node, node: &Node) -> bool {
+    let header_id = match class_or_module.properties() {
+        Properties::Class(class) => class.superclass_id.unwrap_or(class.name_id),
+        Properties::Module(module) => module.name_id,
+        _ => return false,
+    };
+    let body_begin = by_id
+        .get(&header_id)
+        .map_or(class_or_module.expression_l().begin(), |header| {
+            header.expression_l().end()
+        });
+
+    class_or_module.expression_l().contains(node.expression_l())
+        && node.expression_l().begin() >= body_begin
+}
+
+/// The namespace that constants at `node` are defined in and looked up from; empty at the top
+/// level. Unlike `ScopeGate::namespace`, this keeps every segment of a compact name, ex.
+/// `["Admin", "UsersController"]` inside `class Admin::UsersController`, along with whatever it's
+/// nested in.
+///
+pub(crate) fn lexical_namespace(
+    nodes: &[Node],
+    by_id: &HashMap<usize, &Node>,
+    node: &Node,
+) -> Vec<String> {
+    enclosing_namespace(nodes, by_id, node).unwrap_or_default()
+}
+
+/// The fully qualified name of the class or module that a method defined at `node` belongs to;
+/// empty at the top level.
+///
+pub(crate) fn method_owner(
+    nodes: &[Node],
+    by_id: &HashMap<usize, &Node>,
+    node: &Node,
+) -> Vec<String> {
+    lexical_namespace(nodes, by_id, node)
+}
+
+/// Fully qualified names of every constant that's defined in the workspace.
+///
+#[salsa::tracked]
+pub(crate) fn defined_constants(
+    db: &dyn crate::db::Db,
+    workspace: Workspace,
+) -> BTreeSet<Vec<String>> {
+    workspace
+        .file_sources(db)
+        .iter()
+        .flat_map(|&file_source| {
+            let nodes = parse(db, file_source);
+            let by_id = index_by_id(&nodes);
+
+            nodes
+                .iter()
+                .filter_map(|node| definition_path(&nodes, &by_id, node))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Where each constant in the workspace is defined: the `Class`, `Module` or `Casgn` node's file
+/// and ID. When a constant is defined more than once (ex. a reopened class), the first wins.
+///
+#[salsa::tracked]
+pub(crate) fn constant_definitions(
+    db: &dyn crate::db::Db,
+    workspace: Workspace,
+) -> BTreeMap<Vec<String>, (FileSource, usize)> {
+    let mut definitions = BTreeMap::new();
+
+    for &file_source in workspace.file_sources(db) {
+        let nodes = parse(db, file_source);
+        let by_id = index_by_id(&nodes);
+
+        for node in nodes.iter() {
+            if let Some(path) = definition_path(&nodes, &by_id, node) {
+                definitions.entry(path).or_insert((file_source, node.id()));
+            }
+        }
+    }
+
+    definitions
+}
@@ -0,0 +1,133 @@
+//! Fuzzy matching for symbol search, where `AUC` should match `Admin::UsersController` and rank it
+//! above names where those letters just happen to appear somewhere in the middle of words.
+//!
+
+const MATCH: i64 = 1;
+const CASE_MATCH: i64 = 3;
+const CONSECUTIVE: i64 = 5;
+const GAP: i64 = 1;
+const SEGMENT_START: i64 = 10;
+const CAMEL_HUMP: i64 = 8;
+
+/// Scores how well `pattern` matches `candidate`; higher is better. Returns `None` if `pattern`
+/// isn't a (case-insensitive) subsequence of `candidate`.
+///
+/// Matches on the first letter of a namespace segment (ex. after `::`, `#` or `.`), of a word
+/// (after `_`) or of a camel-case hump get bonuses, as do runs of consecutive matches.
+///
+/// ```
+/// use ruby_analyzer_basic_parser::fuzzy::fuzzy_score;
+///
+/// let hump = fuzzy_score("AUC", "Admin::UsersController").unwrap();
+/// let scattered = fuzzy_score("AUC", "Account::Authentic").unwrap();
+///
+/// assert!(hump > scattered);
+/// assert!(fuzzy_score("AUC", "Admin::Users").is_none());
+/// ```
+///
+pub fn fuzzy_score(pattern: &str, candidate: &str) -> Option<i64> {
+    let pattern: Vec<char> = pattern.chars().filter(|c| !c.is_whitespace()).collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+
+    if pattern.is_empty() {
+        return Some(0);
+    }
+
+    // `previous[j]` is the best score for matching the pattern so far, with the last pattern char
+    // matched at `candidate[j]`.
+    let mut previous: Vec<Option<i64>> = vec![None; candidate.len()];
+
+    for (i, &p) in pattern.iter().enumerate() {
+        let mut current = vec![None; candidate.len()];
+
+        // Best score for the previous pattern char matched at least two chars before `j`.
+        let mut best_with_gap: Option<i64> = None;
+
+        for (j, &c) in candidate.iter().enumerate() {
+            if i > 0 && j >= 2 {
+                best_with_gap = best_with_gap.max(previous[j - 2]);
+            }
+
+            if !p.eq_ignore_ascii_case(&c) {
+                continue;
+            }
+
+            let from = if i == 0 {
+                Some(0)
+            } else {
+                let consecutive = j
+                    .checked_sub(1)
+                    .and_then(|k| previous[k])
+                    .map(|score| score + CONSECUTIVE);
+
+                consecutive.max(best_with_gap.map(|score| score - GAP))
+            };
+
+            let case_bonus = if p == c { CASE_MATCH } else { 0 };
+
+            current[j] =
+                from.map(|score| score + MATCH + case_bonus + position_bonus(&candidate, j));
+        }
+
+        previous = current;
+    }
+
+    // Prefer shorter candidates when everything else is equal.
+    let length_penalty = candidate.len().saturating_sub(pattern.len()) as i64 / 8;
+
+    previous
+        .into_iter()
+        .flatten()
+        .max()
+        .map(|score| score - length_penalty)
+}
+
+fn position_bonus(candidate: &[char], index: usize) -> i64 {
+    let Some(&before) = index.checked_sub(1).and_then(|i| candidate.get(i)) else {
+        return SEGMENT_START;
+    };
+
+    let current = candidate[index];
+
+    if matches!(before, ':' | '#' | '.' | '_' | ' ') {
+        SEGMENT_START
+    } else if current.is_uppercase() && (before.is_lowercase() || before.is_ascii_digit()) {
+        CAMEL_HUMP
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segment_and_hump_matches_rank_higher_test() {
+        let mut candidates = vec![
+            "ApplicationController",
+            "Account::Authentic",
+            "Admin::UsersControllerHelper",
+            "Admin::UsersController",
+        ];
+
+        candidates.sort_by_key(|c| std::cmp::Reverse(fuzzy_score("AUC", c)));
+
+        assert_eq!(
+            vec![
+                "Admin::UsersController",
+                "Admin::UsersControllerHelper",
+                "Account::Authentic",
+                "ApplicationController",
+            ],
+            candidates
+        );
+    }
+
+    #[test]
+    fn case_insensitive_test() {
+        assert!(fuzzy_score("users", "Admin::UsersController").is_some());
+        assert!(fuzzy_score("xyz", "Admin::UsersController").is_none());
+        assert_eq!(Some(0), fuzzy_score("", "Admin"));
+    }
+}
@@ -0,0 +1,503 @@
+//! Source of the gems a project depends on, so their classes and methods can be looked up like
+//! the project's own. `Lockfile::parse` reads `Gemfile.lock`, `locate_gems` finds each locked
+//! gem's installed source on disk (in `GEM_HOME`, `vendor/bundle`, or wherever a `path:` gem
+//! lives; never over the network), and `index_gems` loads their `.rb` files.
+//!
+//! Gem sources never change (a new version is a new directory), so their `FileSource`s are set
+//! with `Durability::HIGH`: editing project files doesn't make salsa re-check anything that only
+//! depends on gems. `with_gems` adds them to a `Workspace`, after the project's own files, so
+//! queries that report in workspace order list project results first.
+//!
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use ropey::Rope;
+use salsa::Durability;
+
+use crate::{parser::FileSource, workspace::Workspace};
+
+/// Where a locked gem comes from.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum GemSource {
+    /// A `GEM` section, ex. rubygems.org.
+    Rubygems,
+
+    /// A `PATH` section; the path is as written in the lockfile (usually relative to it).
+    Path(PathBuf),
+
+    /// A `GIT` section.
+    Git { remote: String, revision: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LockedGem {
+    pub(crate) name: String,
+    pub(crate) version: String,
+
+    /// ex. `x86_64-linux` in `nokogiri (1.15.4-x86_64-linux)`.
+    pub(crate) platform: Option<String>,
+    pub(crate) source: GemSource,
+}
+
+impl LockedGem {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn version(&self) -> &str {
+        self.version.as_ref()
+    }
+
+    pub fn platform(&self) -> Option<&str> {
+        self.platform.as_deref()
+    }
+
+    pub fn source(&self) -> &GemSource {
+        &self.source
+    }
+
+    /// The name of the directory the gem is installed in, ex. `nokogiri-1.15.4-x86_64-linux`.
+    ///
+    pub fn full_name(&self) -> String {
+        match &self.platform {
+            Some(platform) => format!("{}-{}-{platform}", self.name, self.version),
+            None => format!("{}-{}", self.name, self.version),
+        }
+    }
+}
+
+/// The parts of a `Gemfile.lock` that matter for finding gem sources.
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Lockfile {
+    pub(crate) gems: Vec<LockedGem>,
+    pub(crate) bundled_with: Option<String>,
+}
+
+impl Lockfile {
+    pub fn parse(text: &str) -> Self {
+        let mut lockfile = Self::default();
+
+        let mut section = "";
+        let mut in_specs = false;
+        let mut remote: Option<String> = None;
+        let mut revision: Option<String> = None;
+
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let indent = line.len() - line.trim_start().len();
+            let content = line.trim();
+
+            if indent == 0 {
+                section = content;
+                in_specs = false;
+                remote = None;
+                revision = None;
+                continue;
+            }
+
+            match (section, indent) {
+                ("BUNDLED WITH", _) => lockfile.bundled_with = Some(content.to_string()),
+                ("GEM" | "PATH" | "GIT", 2) => {
+                    in_specs = content == "specs:";
+
+                    if let Some(value) = content.strip_prefix("remote: ") {
+                        remote = Some(value.to_string());
+                    } else if let Some(value) = content.strip_prefix("revision: ") {
+                        revision = Some(value.to_string());
+                    }
+                }
+                // Deeper lines are the gems' own dependencies.
+                ("GEM" | "PATH" | "GIT", 4) if in_specs => {
+                    let Some((name, version, platform)) = parse_spec(content) else {
+                        continue;
+                    };
+
+                    let source = match section {
+                        "PATH" => {
+                            GemSource::Path(PathBuf::from(remote.clone().unwrap_or_default()))
+                        }
+                        "GIT" => GemSource::Git {
+                            remote: remote.clone().unwrap_or_default(),
+                            revision: revision.clone().unwrap_or_default(),
+                        },
+                        _ => GemSource::Rubygems,
+                    };
+
+                    lockfile.gems.push(LockedGem {
+                        name,
+                        version,
+                        platform,
+                        source,
+                    });
+                }
+                _ => (),
+            }
+        }
+
+        lockfile
+    }
+
+    pub fn gems(&self) -> &[LockedGem] {
+        &self.gems
+    }
+
+    pub fn bundled_with(&self) -> Option<&str> {
+        self.bundled_with.as_deref()
+    }
+}
+
+/// `name (version)` or `name (version-platform)`.
+///
+fn parse_spec(spec: &str) -> Option<(String, String, Option<String>)> {
+    let (name, rest) = spec.split_once(" (")?;
+    let version = rest.strip_suffix(')')?;
+
+    // Versions don't contain `-`, but platforms (ex. `x86_64-linux`) do.
+    let (version, platform) = match version.split_once('-') {
+        Some((version, platform)) => (version, Some(platform.to_string())),
+        None => (version, None),
+    };
+
+    Some((name.to_string(), version.to_string(), platform))
+}
+
+/// A gem whose source was found on disk.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstalledGem {
+    pub(crate) name: String,
+    pub(crate) version: String,
+    pub(crate) root: PathBuf,
+}
+
+impl InstalledGem {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn version(&self) -> &str {
+        self.version.as_ref()
+    }
+
+    /// The gem's directory, ex. `.../gems/sidekiq-7.1.2`.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+/// The directories to look for gems in, given a `GEM_HOME` or `BUNDLE_PATH`. Bundler puts gems in
+/// `<BUNDLE_PATH>/ruby/<ruby version>/`, so for `vendor/bundle` that's each of
+/// `vendor/bundle/ruby/*`; anything else is taken as a gem home itself.
+///
+pub fn gem_homes(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(path.join("ruby")) else {
+        return vec![path.to_path_buf()];
+    };
+
+    let mut homes: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect();
+
+    homes.sort();
+    homes
+}
+
+/// Finds where each gem in `lockfile` is installed. `lockfile_dir` is used to resolve `PATH`
+/// gems; the others are looked up in `gem_homes` (see `gem_homes()`), in order. Gems that aren't
+/// installed are left out.
+///
+pub fn locate_gems(
+    lockfile: &Lockfile,
+    lockfile_dir: &Path,
+    gem_homes: &[PathBuf],
+) -> Vec<InstalledGem> {
+    lockfile
+        .gems
+        .iter()
+        .filter_map(|gem| {
+            let candidates: Vec<PathBuf> = match &gem.source {
+                GemSource::Path(path) => vec![lockfile_dir.join(path)],
+                GemSource::Rubygems => gem_homes
+                    .iter()
+                    .flat_map(|home| {
+                        [
+                            home.join("gems").join(gem.full_name()),
+                            home.join("gems")
+                                .join(format!("{}-{}", gem.name, gem.version)),
+                        ]
+                    })
+                    .collect(),
+                // Bundler checks git gems out as `<name>-<first 12 chars of the revision>`.
+                GemSource::Git { revision, .. } => {
+                    let short: String = revision.chars().take(12).collect();
+
+                    gem_homes
+                        .iter()
+                        .map(|home| {
+                            home.join("bundler")
+                                .join("gems")
+                                .join(format!("{}-{short}", gem.name))
+                        })
+                        .collect()
+                }
+            };
+
+            let root = candidates
+                .into_iter()
+                .find(|candidate| candidate.is_dir())?;
+
+            Some(InstalledGem {
+                name: gem.name.clone(),
+                version: gem.version.clone(),
+                root,
+            })
+        })
+        .collect()
+}
+
+/// An installed gem, with its source files.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexedGem {
+    pub(crate) gem: InstalledGem,
+    pub(crate) file_sources: Vec<FileSource>,
+}
+
+impl IndexedGem {
+    pub fn gem(&self) -> &InstalledGem {
+        &self.gem
+    }
+
+    pub fn file_sources(&self) -> &[FileSource] {
+        &self.file_sources
+    }
+}
+
+#[salsa::input]
+pub struct GemIndex {
+    #[return_ref]
+    pub gems: Vec<IndexedGem>,
+}
+
+impl GemIndex {
+    /// Is `file_source` part of a gem (and so, read-only)?
+    ///
+    pub fn contains(&self, db: &dyn crate::db::Db, file_source: FileSource) -> bool {
+        self.gems(db)
+            .iter()
+            .any(|gem| gem.file_sources.contains(&file_source))
+    }
+
+    /// The gem that `file_source` is part of.
+    ///
+    pub fn gem_of<'db>(
+        &self,
+        db: &'db dyn crate::db::Db,
+        file_source: FileSource,
+    ) -> Option<&'db InstalledGem> {
+        self.gems(db)
+            .iter()
+            .find(|gem| gem.file_sources.contains(&file_source))
+            .map(|gem| &gem.gem)
+    }
+}
+
+/// Reads the `.rb` files under each gem's `lib/` into `FileSource`s.
+///
+pub fn index_gems(db: &mut dyn crate::db::Db, gems: &[InstalledGem]) -> io::Result<GemIndex> {
+    let mut indexed = Vec::with_capacity(gems.len());
+
+    for gem in gems {
+        let mut paths = Vec::new();
+        ruby_files(&gem.root.join("lib"), &mut paths)?;
+        paths.sort();
+
+        let mut file_sources = Vec::with_capacity(paths.len());
+
+        for path in paths {
+            let code = Rope::from_str(&fs::read_to_string(&path)?);
+            let file_source = FileSource::new(db, path, Rope::new());
+
+            file_source
+                .set_code(db)
+                .with_durability(Durability::HIGH)
+                .to(code);
+
+            file_sources.push(file_source);
+        }
+
+        indexed.push(IndexedGem {
+            gem: gem.clone(),
+            file_sources,
+        });
+    }
+
+    let index = GemIndex::new(db, Vec::new());
+    index
+        .set_gems(db)
+        .with_durability(Durability::HIGH)
+        .to(indexed);
+
+    Ok(index)
+}
+
+fn ruby_files(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            ruby_files(&path, paths)?;
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "rb")
+        {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// A workspace with the project's files followed by those of every gem in `index`.
+///
+pub fn with_gems(db: &dyn crate::db::Db, workspace: Workspace, index: GemIndex) -> Workspace {
+    let file_sources = workspace
+        .file_sources(db)
+        .iter()
+        .chain(
+            index
+                .gems(db)
+                .iter()
+                .flat_map(|gem| gem.file_sources.iter()),
+        )
+        .copied()
+        .collect();
+
+    Workspace::new(db, file_sources)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    const LOCKFILE: &str = r#"GIT
+  remote: https://github.com/example/patched.git
+  revision: 0123456789abcdef0123456789abcdef01234567
+  specs:
+    patched (2.0.0)
+
+PATH
+  remote: ../engines/billing
+  specs:
+    billing (0.1.0)
+      activesupport (>= 7.0)
+
+GEM
+  remote: https://rubygems.org/
+  specs:
+    activesupport (7.1.2)
+      concurrent-ruby (~> 1.0, >= 1.0.2)
+    concurrent-ruby (1.2.2)
+    nokogiri (1.15.4-x86_64-linux)
+
+PLATFORMS
+  x86_64-linux
+
+DEPENDENCIES
+  activesupport
+  billing!
+
+BUNDLED WITH
+   2.4.10
+"#;
+
+    #[test]
+    fn lockfile_test() {
+        let lockfile = Lockfile::parse(LOCKFILE);
+
+        let gems: Vec<(&str, &str, Option<&str>)> = lockfile
+            .gems()
+            .iter()
+            .map(|gem| (gem.name(), gem.version(), gem.platform()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("patched", "2.0.0", None),
+                ("billing", "0.1.0", None),
+                ("activesupport", "7.1.2", None),
+                ("concurrent-ruby", "1.2.2", None),
+                ("nokogiri", "1.15.4", Some("x86_64-linux")),
+            ],
+            gems
+        );
+
+        assert_eq!(
+            &GemSource::Path(PathBuf::from("../engines/billing")),
+            lockfile.gems()[1].source()
+        );
+        assert_eq!(
+            "nokogiri-1.15.4-x86_64-linux",
+            lockfile.gems()[4].full_name()
+        );
+        assert_eq!(Some("2.4.10"), lockfile.bundled_with());
+    }
+
+    #[test]
+    fn index_gems_test() {
+        let root = std::env::temp_dir().join(format!("ruby-analyzer-gems-{}", std::process::id()));
+        let home = root.join("vendor/bundle/ruby/3.2.0");
+        let lib = home.join("gems/activesupport-7.1.2/lib/active_support");
+
+        fs::create_dir_all(&lib).unwrap();
+        fs::write(lib.join("inflector.rb"), "module ActiveSupport; end\n").unwrap();
+        fs::write(lib.join("README.md"), "Not Ruby.\n").unwrap();
+
+        let homes = gem_homes(&root.join("vendor/bundle"));
+        assert_eq!(vec![home.clone()], homes);
+
+        let installed = locate_gems(&Lockfile::parse(LOCKFILE), &root, &homes);
+        assert_eq!(
+            vec!["activesupport"],
+            installed.iter().map(InstalledGem::name).collect::<Vec<_>>()
+        );
+
+        let mut db = Database::default();
+        let project = FileSource::new(&db, root.join("app.rb"), Rope::from_str("App = 1\n"));
+        let index = index_gems(&mut db, &installed).unwrap();
+
+        let workspace = with_gems(&db, Workspace::new(&db, vec![project]), index);
+        let file_sources = workspace.file_sources(&db);
+
+        assert_eq!(2, file_sources.len());
+        assert_eq!(project, file_sources[0]);
+        assert!(index.contains(&db, file_sources[1]));
+        assert!(!index.contains(&db, project));
+        assert_eq!(
+            Some("activesupport"),
+            index.gem_of(&db, file_sources[1]).map(InstalledGem::name)
+        );
+        assert_eq!(
+            "module ActiveSupport; end\n",
+            file_sources[1].code(&db).to_string()
+        );
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,554 @@
+//! Hover info for the class, module, method or constant under the cursor, wherever in the
+//! workspace it's defined: its signature, fully qualified name and (YARD) doc comment, as
+//! markdown. Methods with a Sorbet `sig` (inline, or in one of the project's `.rbi` files) show it
+//! above their `def`.
+//!
+use std::collections::HashMap;
+
+use lsp_types::{Hover, HoverContents, MarkupContent, MarkupKind};
+use ropey::Rope;
+
+use crate::{
+    constants::{
+        constant_definitions, defined_constants, definition_path, lexical_namespace, method_owner,
+        ConstPath,
+    },
+    node::{in_singleton_class, index_by_id, Contains, Loc},
+    parser::{parse, FileSource},
+    properties::Properties,
+    signature_help::resolve_method,
+    sorbet::{rbi_signatures, signatures, Sig},
+    symbols::method_fully_qualified_name,
+    types::{Inference, TypeEnvironment},
+    yard::yard_docs,
+    Node,
+};
+
+#[salsa::input]
+pub struct HoverQuery {
+    /// The workspace that constants and calls are resolved in, and where to look for the `sig` of
+    /// methods that don't have one inline.
+    pub environment: TypeEnvironment,
+    pub file_source: FileSource,
+    pub offset: usize,
+}
+
+/// Finds the definition of the symbol at the query's `offset` (either the definition itself, a
+/// reference to a constant, or a call to a method, resolved from the receiver's inferred type),
+/// anywhere in the workspace, then describes it.
+///
+#[salsa::tracked]
+pub fn hover(db: &dyn crate::db::Db, query: HoverQuery) -> Option<Hover> {
+    let environment = query.environment(db);
+    let workspace = environment.workspace(db);
+    let file_source = query.file_source(db);
+    let offset = query.offset(db);
+    let code = file_source.code(db);
+
+    let nodes = parse(db, file_source);
+    let by_id = index_by_id(&nodes);
+
+    let (mut description, range_l) = nodes.iter().find_map(|node| match node.properties() {
+        Properties::Def(def) if def.name_l.contains(offset) => {
+            Some((describe(db, file_source, node.id())?, def.name_l))
+        }
+        Properties::Defs(defs) if defs.name_l.contains(offset) => {
+            Some((describe(db, file_source, node.id())?, defs.name_l))
+        }
+        Properties::Casgn(casgn) if casgn.name_l.contains(offset) => {
+            Some((describe(db, file_source, node.id())?, casgn.name_l))
+        }
+        Properties::Send(send) if send.selector_l.map_or(false, |l| l.contains(offset)) => {
+            let receiver = send.recv_id.and_then(|id| by_id.get(&id)).copied();
+            let (definition_source, definition_id) = called_method(
+                db,
+                environment,
+                file_source,
+                &nodes,
+                node,
+                receiver,
+                &send.method_name,
+            )?;
+
+            Some((
+                describe(db, definition_source, definition_id)?,
+                send.selector_l?,
+            ))
+        }
+        Properties::CSend(csend) if csend.selector_l.map_or(false, |l| l.contains(offset)) => {
+            let receiver = by_id.get(&csend.recv_id).copied();
+            let (definition_source, definition_id) = called_method(
+                db,
+                environment,
+                file_source,
+                &nodes,
+                node,
+                receiver,
+                &csend.method_name,
+            )?;
+
+            Some((
+                describe(db, definition_source, definition_id)?,
+                csend.selector_l?,
+            ))
+        }
+        Properties::Const(const_) if const_.name_l.contains(offset) => {
+            let path = ConstPath::new(&by_id, &const_.name, const_.scope_id)?.resolve(
+                &lexical_namespace(&nodes, &by_id, node),
+                &defined_constants(db, workspace),
+            );
+
+            let description = constant_definitions(db, workspace)
+                .get(&path)
+                .and_then(|&(definition_source, definition_id)| {
+                    describe(db, definition_source, definition_id)
+                })
+                .unwrap_or_else(|| Description {
+                    signature: path.join("::"),
+                    sig: None,
+                    kind: "constant",
+                    fully_qualified_name: path.join("::"),
+                    doc: None,
+                });
+
+            Some((description, const_.name_l))
+        }
+        _ => None,
+    })?;
+
+    if description.sig.is_none() && description.kind.ends_with("method") {
+        description.sig = environment.rbi_files(db).and_then(|rbi_files| {
+            rbi_signatures(db, rbi_files)
+                .get(&description.fully_qualified_name)
+                .map(Sig::to_ruby)
+        });
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: description.to_markdown(),
+        }),
+        range: Some(range_l.to_lsp_range(code)),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Description {
+    signature: String,
+
+    /// The method's Sorbet `sig`, ex. `sig { params(x: Integer).returns(String) }`.
+    sig: Option<String>,
+    kind: &'static str,
+    fully_qualified_name: String,
+    doc: Option<String>,
+}
+
+impl Description {
+    fn to_markdown(&self) -> String {
+        let signature = match &self.sig {
+            Some(sig) => format!("{sig}\n{}", self.signature),
+            None => self.signature.clone(),
+        };
+
+        let mut markdown = format!(
+            "```ruby\n{signature}\n```\n\n{} `{}`",
+            self.kind, self.fully_qualified_name
+        );
+
+        if let Some(doc) = &self.doc {
+            markdown.push_str("\n\n---\n\n");
+            markdown.push_str(doc);
+        }
+
+        markdown
+    }
+}
+
+/// Describes the `Class`, `Module`, `Casgn`, `Def` or `Defs` node `id` in `file_source`.
+///
+fn describe(db: &dyn crate::db::Db, file_source: FileSource, id: usize) -> Option<Description> {
+    let code = file_source.code(db);
+    let nodes = parse(db, file_source);
+    let by_id = index_by_id(&nodes);
+    let node = *by_id.get(&id)?;
+
+    let owner = || method_owner(&nodes, &by_id, node);
+
+    let (signature, kind, fully_qualified_name) = match node.properties() {
+        Properties::Class(class) => {
+            let name = definition_path(&nodes, &by_id, node)?.join("::");
+            let signature = match class.superclass_id.and_then(|id| by_id.get(&id)) {
+                Some(superclass) => {
+                    format!("class {name} < {}", source(code, superclass.expression_l()))
+                }
+                None => format!("class {name}"),
+            };
+
+            (signature, "class", name)
+        }
+        Properties::Module(_) => {
+            let name = definition_path(&nodes, &by_id, node)?.join("::");
+
+            (format!("module {name}"), "module", name)
+        }
+        Properties::Casgn(_) => {
+            let name = definition_path(&nodes, &by_id, node)?.join("::");
+
+            (name.clone(), "constant", name)
+        }
+        Properties::Def(def) => {
+            let singleton = in_singleton_class(&nodes, node);
+            let signature = format!("def {}{}", def.name, parameters(&by_id, code, def.args_id));
+            let kind = if singleton {
+                "singleton method"
+            } else {
+                "instance method"
+            };
+
+            let owner = owner();
+            let namespace: Vec<&str> = owner.iter().map(String::as_str).collect();
+
+            (
+                signature,
+                kind,
+                method_fully_qualified_name(&namespace, &def.name, singleton),
+            )
+        }
+        Properties::Defs(defs) => {
+            let definee = by_id.get(&defs.definee_id)?;
+            let signature = format!(
+                "def {}.{}{}",
+                source(code, definee.expression_l()),
+                defs.name,
+                parameters(&by_id, code, defs.args_id)
+            );
+
+            let owner = owner();
+            let namespace: Vec<&str> = owner.iter().map(String::as_str).collect();
+
+            (
+                signature,
+                "singleton method",
+                method_fully_qualified_name(&namespace, &defs.name, true),
+            )
+        }
+        _ => return None,
+    };
+
+    Some(Description {
+        signature,
+        sig: signatures(db, file_source).get(&id).map(Sig::to_ruby),
+        kind,
+        fully_qualified_name,
+        doc: yard_docs(db, file_source)
+            .get(&id)
+            .map(|doc| doc.to_markdown()),
+    })
+}
+
+/// Rebuilds a method's parameter list, ex. `(a, b = 1, *rest, c:, d: 2, **opts, &block)`.
+///
+pub(crate) fn parameters(
+    by_id: &HashMap<usize, &Node>,
+    code: &Rope,
+    args_id: Option<usize>,
+) -> String {
+    parameter_labels(by_id, code, args_id)
+        .map(|labels| format!("({})", labels.join(", ")))
+        .unwrap_or_default()
+}
+
+/// Each of a method's parameters as written, ex. `b = 1` or `**opts`; `None` if the method has no
+/// parameter list.
+///
+pub(crate) fn parameter_labels(
+    by_id: &HashMap<usize, &Node>,
+    code: &Rope,
+    args_id: Option<usize>,
+) -> Option<Vec<String>> {
+    let Some(Properties::Args(args)) = args_id
+        .and_then(|id| by_id.get(&id))
+        .map(|node| node.properties())
+    else {
+        return None;
+    };
+
+    let labels = args
+        .arg_ids
+        .iter()
+        .filter_map(|id| by_id.get(id))
+        .map(|arg| match arg.properties() {
+            Properties::Arg(arg) => arg.name.clone(),
+            Properties::Optarg(optarg) => match by_id.get(&optarg.default_id) {
+                Some(default) => {
+                    format!("{} = {}", optarg.name, source(code, default.expression_l()))
+                }
+                None => optarg.name.clone(),
+            },
+            Properties::Restarg(restarg) => {
+                format!("*{}", restarg.name.as_deref().unwrap_or_default())
+            }
+            Properties::Kwarg(kwarg) => format!("{}:", kwarg.name),
+            Properties::Kwoptarg(kwoptarg) => match by_id.get(&kwoptarg.default_id) {
+                Some(default) => format!(
+                    "{}: {}",
+                    kwoptarg.name,
+                    source(code, default.expression_l())
+                ),
+                None => format!("{}:", kwoptarg.name),
+            },
+            Properties::Kwrestarg(kwrestarg) => {
+                format!("**{}", kwrestarg.name.as_deref().unwrap_or_default())
+            }
+            Properties::Kwnilarg(_) => "**nil".to_string(),
+            Properties::Blockarg(blockarg) => {
+                format!("&{}", blockarg.name.as_deref().unwrap_or_default())
+            }
+            Properties::ForwardArg(_) => "...".to_string(),
+            // Ex. destructuring `(a, b)`; the source is already what we'd want to show.
+            _ => source(code, arg.expression_l()),
+        })
+        .collect();
+
+    Some(labels)
+}
+
+/// Finds the `Def` or `Defs` that a call to `method_name` at `node` calls, from the inferred type
+/// of its `receiver` (or of `self`, if there's none): the definition's file and ID.
+///
+fn called_method(
+    db: &dyn crate::db::Db,
+    environment: TypeEnvironment,
+    file_source: FileSource,
+    nodes: &[Node],
+    node: &Node,
+    receiver: Option<&Node>,
+    method_name: &str,
+) -> Option<(FileSource, usize)> {
+    let inference = Inference::new(db, environment, file_source, nodes);
+
+    let receiver = match receiver {
+        Some(receiver) => inference.infer(receiver)?,
+        None => inference.self_type(node),
+    };
+
+    resolve_method(
+        db,
+        environment.workspace(db),
+        &inference,
+        &receiver,
+        method_name,
+    )
+}
+
+fn source(code: &Rope, loc: &Loc) -> String {
+    code.byte_slice(loc.begin()..loc.end()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::{BTreeMap, BTreeSet},
+        path::PathBuf,
+    };
+
+    use super::*;
+    use crate::{db::Database, workspace::Workspace};
+
+    const CODE: &str = r#"module Admin
+  # Lists users.
+  #
+  # Only admins can see this.
+  class UsersController < ApplicationController
+    # Renders the page.
+    def index(page, per = 20, *rest, sort:, order: :asc, **opts, &block)
+      self.class.policy
+      Admin::UsersController.policy
+    end
+
+    def self.policy; end
+  end
+end"#;
+
+    fn hover_text(db: &Database, file_source: FileSource, offset: usize) -> Option<String> {
+        let workspace = Workspace::new(db, vec![file_source]);
+        workspace_hover_text(db, workspace, file_source, offset)
+    }
+
+    fn workspace_hover_text(
+        db: &Database,
+        workspace: Workspace,
+        file_source: FileSource,
+        offset: usize,
+    ) -> Option<String> {
+        let environment =
+            TypeEnvironment::new(db, workspace, None, BTreeMap::new(), BTreeSet::new());
+        let query = HoverQuery::new(db, environment, file_source, offset);
+
+        hover(db, query).map(|hover| match hover.contents {
+            HoverContents::Markup(markup) => markup.value,
+            _ => unreachable!(),
+        })
+    }
+
+    #[test]
+    fn method_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let offset = CODE.find("def index").unwrap() + 4;
+
+        assert_eq!(
+            Some(
+                r#"```ruby
+def index(page, per = 20, *rest, sort:, order: :asc, **opts, &block)
+```
+
+instance method `Admin::UsersController#index`
+
+---
+
+Renders the page."#
+                    .to_string()
+            ),
+            hover_text(&db, file_source, offset)
+        );
+
+        // Calls through a constant resolve to the singleton method.
+        let offset = CODE.find("UsersController.policy").unwrap() + 16;
+        let text = hover_text(&db, file_source, offset).unwrap();
+        assert!(text.starts_with("```ruby\ndef self.policy\n```"));
+        assert!(text.contains("singleton method `Admin::UsersController.policy`"));
+
+        // As do calls on an inferred type.
+        let offset = CODE.find("class.policy").unwrap() + 6;
+        assert!(hover_text(&db, file_source, offset)
+            .unwrap()
+            .contains("singleton method `Admin::UsersController.policy`"));
+    }
+
+    #[test]
+    fn class_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let expected = r#"```ruby
+class Admin::UsersController < ApplicationController
+```
+
+class `Admin::UsersController`
+
+---
+
+Lists users.
+
+Only admins can see this."#;
+
+        let offset = CODE.find("UsersController <").unwrap();
+        assert_eq!(
+            Some(expected.to_string()),
+            hover_text(&db, file_source, offset)
+        );
+
+        let offset = CODE.find("Admin::UsersController.policy").unwrap() + 8;
+        assert_eq!(
+            Some(expected.to_string()),
+            hover_text(&db, file_source, offset)
+        );
+
+        // Not defined in this file.
+        let offset = CODE.find("ApplicationController").unwrap();
+        assert!(hover_text(&db, file_source, offset)
+            .unwrap()
+            .contains("constant `ApplicationController`"));
+    }
+
+    #[test]
+    fn compact_class_test() {
+        let db = Database::default();
+        let code = r#"module Admin; end
+
+class Admin::UsersController
+  def index
+    policy
+  end
+
+  def self.policy; end
+end
+
+Admin::UsersController.policy"#;
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(code));
+
+        let offset = code.find("def index").unwrap() + 4;
+        assert!(hover_text(&db, file_source, offset)
+            .unwrap()
+            .contains("instance method `Admin::UsersController#index`"));
+
+        let offset = code.rfind("policy").unwrap();
+        assert!(hover_text(&db, file_source, offset)
+            .unwrap()
+            .contains("singleton method `Admin::UsersController.policy`"));
+    }
+
+    #[test]
+    fn workspace_test() {
+        let db = Database::default();
+        let mailer_code = r#"# Sends mail.
+class Mailer
+  # Delivers it.
+  def deliver(to); end
+end"#;
+        let mailer = FileSource::new(
+            &db,
+            PathBuf::from("/tmp/mailer.rb"),
+            Rope::from_str(mailer_code),
+        );
+        let code = "mailer = Mailer.new\nmailer.deliver(\"ada\")";
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(code));
+        let workspace = Workspace::new(&db, vec![mailer, file_source]);
+
+        let offset = code.find("Mailer").unwrap();
+        assert_eq!(
+            Some("```ruby\nclass Mailer\n```\n\nclass `Mailer`\n\n---\n\nSends mail.".to_string()),
+            workspace_hover_text(&db, workspace, file_source, offset)
+        );
+
+        // Calls resolve through the receiver's inferred type.
+        let offset = code.find("deliver").unwrap();
+        let text = workspace_hover_text(&db, workspace, file_source, offset).unwrap();
+        assert!(text.starts_with("```ruby\ndef deliver(to)\n```"));
+        assert!(text.contains("instance method `Mailer#deliver`"));
+        assert!(text.ends_with("Delivers it."));
+    }
+
+    #[test]
+    fn sig_test() {
+        let db = Database::default();
+        let code = r#"class Greeter
+  # Greets someone.
+  sig { params(name: String).returns(String) }
+  def greet(name); end
+end"#;
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(code));
+
+        let offset = code.find("greet").unwrap();
+
+        assert_eq!(
+            Some(
+                r#"```ruby
+sig { params(name: String).returns(String) }
+def greet(name)
+```
+
+instance method `Greeter#greet`
+
+---
+
+Greets someone."#
+                    .to_string()
+            ),
+            hover_text(&db, file_source, offset)
+        );
+    }
+}
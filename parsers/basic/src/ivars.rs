@@ -0,0 +1,282 @@
+//! Instance variables of each class and module in a `Workspace`: where they're assigned and where
+//! they're read, from within instance methods.
+//!
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    ancestors::{ancestor_chains, Ancestor},
+    constants::enclosing_namespace,
+    node::{in_singleton_class, in_singleton_context, index_by_id, Loc},
+    parser::parse,
+    properties::Properties,
+    references::Reference,
+    scope_gate,
+    workspace::Workspace,
+    Node,
+};
+
+/// The instance variables used by the instance methods of a class or module, keyed by name (ex.
+/// `@name`).
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct InstanceVariables {
+    pub(crate) assignments: BTreeMap<String, Vec<Reference>>,
+    pub(crate) reads: BTreeMap<String, Vec<Reference>>,
+    pub(crate) unassigned: BTreeMap<String, Vec<Reference>>,
+}
+
+impl InstanceVariables {
+    /// Every instance variable that's assigned, with where. `attr_writer` and `attr_accessor`
+    /// count as assignments, at their symbol.
+    ///
+    pub fn assignments(&self) -> &BTreeMap<String, Vec<Reference>> {
+        &self.assignments
+    }
+
+    /// Every instance variable that's read, with where. `attr_reader` and `attr_accessor` count
+    /// as reads, at their symbol.
+    ///
+    pub fn reads(&self) -> &BTreeMap<String, Vec<Reference>> {
+        &self.reads
+    }
+
+    /// The instance variables that are read, but that neither this class/module nor any of its
+    /// (instance) ancestors ever assign; these are always `nil`.
+    ///
+    pub fn unassigned(&self) -> &BTreeMap<String, Vec<Reference>> {
+        &self.unassigned
+    }
+
+    /// All names, assigned or not.
+    ///
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        let mut names: Vec<&str> = self
+            .assignments
+            .keys()
+            .chain(self.reads.keys())
+            .map(String::as_str)
+            .collect();
+
+        names.sort_unstable();
+        names.dedup();
+        names.into_iter()
+    }
+}
+
+/// Instance variables of every class and module in the workspace, keyed by fully qualified name.
+/// Only instance methods (and `attr_*` calls) are considered; instance variables of the class
+/// object itself (ex. in `def self.foo` or directly in the class body) aren't those of its
+/// instances.
+///
+#[salsa::tracked]
+pub fn instance_variables(
+    db: &dyn crate::db::Db,
+    workspace: Workspace,
+) -> BTreeMap<Vec<String>, InstanceVariables> {
+    let mut inventory: BTreeMap<Vec<String>, InstanceVariables> = BTreeMap::new();
+
+    for &file_source in workspace.file_sources(db) {
+        let nodes = parse(db, file_source);
+        let by_id = index_by_id(&nodes);
+
+        for node in nodes.iter() {
+            let usages = usages(&nodes, &by_id, node);
+
+            if usages.is_empty() {
+                continue;
+            }
+
+            let Some(owner) = enclosing_namespace(&nodes, &by_id, node) else {
+                continue;
+            };
+
+            let ivars = inventory.entry(owner).or_default();
+
+            for (name, usage, loc) in usages {
+                let sites = match usage {
+                    Usage::Assignment => &mut ivars.assignments,
+                    Usage::Read => &mut ivars.reads,
+                };
+
+                sites
+                    .entry(name)
+                    .or_default()
+                    .push(Reference { file_source, loc });
+            }
+        }
+    }
+
+    let chains = ancestor_chains(db, workspace);
+
+    let unassigned: Vec<(Vec<String>, BTreeMap<String, Vec<Reference>>)> = inventory
+        .iter()
+        .map(|(owner, ivars)| {
+            let ancestors: Vec<&Vec<String>> = match chains.get(owner) {
+                Some(chain) => chain
+                    .instance()
+                    .iter()
+                    .filter_map(|ancestor| match ancestor {
+                        Ancestor::Instance(path) => Some(path),
+                        Ancestor::Singleton(_) => None,
+                    })
+                    .collect(),
+                None => vec![owner],
+            };
+
+            let unassigned = ivars
+                .reads
+                .iter()
+                .filter(|(name, _)| {
+                    !ancestors.iter().any(|ancestor| {
+                        inventory
+                            .get(*ancestor)
+                            .map_or(false, |other| other.assignments.contains_key(*name))
+                    })
+                })
+                .map(|(name, sites)| (name.clone(), sites.clone()))
+                .collect();
+
+            (owner.clone(), unassigned)
+        })
+        .collect();
+
+    for (owner, unassigned) in unassigned {
+        if let Some(ivars) = inventory.get_mut(&owner) {
+            ivars.unassigned = unassigned;
+        }
+    }
+
+    inventory
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Usage {
+    Assignment,
+    Read,
+}
+
+/// The instance variables that `node` assigns or reads, as seen by an instance of the enclosing
+/// class or module.
+///
+fn usages(nodes: &[Node], by_id: &HashMap<usize, &Node>, node: &Node) -> Vec<(String, Usage, Loc)> {
+    // Only checked for `Ivasgn`s and `Ivar`s, since `in_singleton_context` scans every node.
+    let in_instance_method = || {
+        matches!(node.scope_gate().last(), Some(scope_gate::Node::Def(_)))
+            && !in_singleton_context(nodes, node)
+    };
+
+    match node.properties() {
+        Properties::Ivasgn(ivasgn) if in_instance_method() => {
+            vec![(ivasgn.name.clone(), Usage::Assignment, ivasgn.name_l)]
+        }
+        Properties::Ivar(ivar) if in_instance_method() => {
+            vec![(ivar.name.clone(), Usage::Read, *node.expression_l())]
+        }
+        Properties::Send(send) if send.recv_id.is_none() => {
+            let usages: &[Usage] = match send.method_name.as_str() {
+                "attr_reader" => &[Usage::Read],
+                "attr_writer" => &[Usage::Assignment],
+                "attr_accessor" => &[Usage::Read, Usage::Assignment],
+                _ => return Vec::new(),
+            };
+
+            // Only those that define methods on instances, not on the class (`class << self`).
+            let in_body = matches!(
+                node.scope_gate().last(),
+                Some(scope_gate::Node::Class(_) | scope_gate::Node::Module(_))
+            );
+
+            if !in_body || in_singleton_class(nodes, node) {
+                return Vec::new();
+            }
+
+            send.arg_ids
+                .iter()
+                .filter_map(|id| by_id.get(id))
+                .filter_map(|arg| match arg.properties() {
+                    Properties::Sym(sym) => Some((format!("@{}", sym.name), *arg.expression_l())),
+                    _ => None,
+                })
+                .flat_map(|(name, loc)| usages.iter().map(move |usage| (name.clone(), *usage, loc)))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ropey::Rope;
+
+    use super::*;
+    use crate::{db::Database, parser::FileSource};
+
+    const BASE: &str = r#"class Base
+  def initialize(logger)
+    @logger = logger
+  end
+end"#;
+
+    const CHILD: &str = r#"class Child < Base
+  attr_accessor :name
+  attr_reader :age
+
+  @registry = {}
+
+  class << self
+    attr_reader :config
+  end
+
+  def self.build
+    @cache ||= {}
+  end
+
+  def greet
+    @greeting = "Hi, #{@name}"
+    @logger.info(@greeting)
+    @title
+  end
+end"#;
+
+    fn names(sites: &BTreeMap<String, Vec<Reference>>) -> Vec<&str> {
+        sites.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn instance_variables_test() {
+        let db = Database::default();
+        let base = FileSource::new(&db, PathBuf::from("/tmp/base.rb"), Rope::from_str(BASE));
+        let child = FileSource::new(&db, PathBuf::from("/tmp/child.rb"), Rope::from_str(CHILD));
+        let workspace = Workspace::new(&db, vec![base, child]);
+
+        let inventory = instance_variables(&db, workspace);
+        let ivars = &inventory[&vec!["Child".to_string()]];
+
+        assert_eq!(vec!["@greeting", "@name"], names(ivars.assignments()));
+        assert_eq!(
+            vec!["@age", "@greeting", "@logger", "@name", "@title"],
+            names(ivars.reads())
+        );
+
+        // `@logger` is assigned by the superclass.
+        assert_eq!(vec!["@age", "@title"], names(ivars.unassigned()));
+
+        let greeting = &ivars.assignments()["@greeting"];
+        assert_eq!(1, greeting.len());
+        assert_eq!(greeting[0].file_source(), child);
+        assert_eq!(
+            "@greeting",
+            &CHILD[greeting[0].loc().begin()..greeting[0].loc().end()]
+        );
+
+        let name = &ivars.assignments()["@name"];
+        assert_eq!(":name", &CHILD[name[0].loc().begin()..name[0].loc().end()]);
+
+        assert_eq!(
+            vec!["@logger"],
+            names(inventory[&vec!["Base".to_string()]].assignments())
+        );
+    }
+}
@@ -1,12 +1,32 @@
+pub mod ancestors;
+pub mod comments;
+pub mod completion;
+pub(crate) mod constants;
 pub mod db;
+pub mod fuzzy;
+pub mod gems;
+pub mod hover;
+pub mod ivars;
+pub mod lint;
+pub mod locals;
 pub(crate) mod lrp_extensions;
 pub(crate) mod node;
 pub(crate) mod nodes;
 pub mod parser;
 pub(crate) mod properties;
 pub mod queries;
+pub mod references;
+pub mod requires;
 pub mod scope_gate;
+pub mod signature_help;
+pub mod sorbet;
+pub mod symbols;
 pub(crate) mod transformer;
+pub mod types;
+pub mod unused_variables;
+pub mod workspace;
+pub mod yard;
+pub mod zeitwerk;
 
 pub use self::{db::Db, node::Node};
 
@@ -14,9 +34,59 @@ pub use self::{db::Db, node::Node};
 pub struct Jar(
     crate::parser::FileSource,
     crate::parser::Diagnostics,
+    crate::parser::Comments,
+    crate::parser::MagicCommentEntries,
     crate::parser::parse,
     crate::parser::inner_transform,
     crate::parser::NodeSource,
     crate::queries::ClosestNodeQuery,
     crate::queries::find_namespace,
+    crate::workspace::Workspace,
+    crate::comments::comments,
+    crate::comments::magic_comments,
+    crate::comments::doc_comments,
+    crate::completion::CompletionQuery,
+    crate::completion::workspace_methods,
+    crate::completion::called_method_names,
+    crate::completion::method_completions,
+    crate::completion::keyword_completions,
+    crate::constants::defined_constants,
+    crate::constants::constant_definitions,
+    crate::ancestors::ancestor_chains,
+    crate::gems::GemIndex,
+    crate::hover::HoverQuery,
+    crate::hover::hover,
+    crate::ivars::instance_variables,
+    crate::lint::LintQuery,
+    crate::lint::lint,
+    crate::lint::config::LintConfigFile,
+    crate::lint::config::lint_config,
+    crate::locals::locals,
+    crate::references::ReferencesQuery,
+    crate::references::references,
+    crate::requires::LoadPath,
+    crate::requires::RequiresQuery,
+    crate::requires::require_graph,
+    crate::requires::RequireDefinitionQuery,
+    crate::requires::require_definition,
+    crate::signature_help::SignatureHelpQuery,
+    crate::signature_help::method_definitions,
+    crate::signature_help::signature_help,
+    crate::sorbet::signatures,
+    crate::sorbet::RbiFiles,
+    crate::sorbet::rbi_signatures,
+    crate::symbols::file_symbols,
+    crate::symbols::WorkspaceSymbolQuery,
+    crate::symbols::workspace_symbols,
+    crate::types::TypeEnvironment,
+    crate::types::declared_return_types,
+    crate::types::TypeQuery,
+    crate::types::type_at,
+    crate::unused_variables::variable_diagnostics,
+    crate::yard::yard_docs,
+    crate::yard::yard_directives,
+    crate::zeitwerk::AutoloadConfig,
+    crate::zeitwerk::AutoloadQuery,
+    crate::zeitwerk::autoload_map,
+    crate::zeitwerk::autoload_diagnostics,
 );
@@ -0,0 +1,565 @@
+//! A small, RuboCop-like lint engine. A `Rule` looks at each node of a file (and/or the file as a
+//! whole) and reports `Offense`s, which `lint` turns into LSP diagnostics. Which rules run, and
+//! how severe their offenses are, comes from the project's config file (see `config`). Offenses
+//! can be silenced inline:
+//!
+//! ```ruby
+//! binding.pry # ruby-analyzer:disable debugger
+//!
+//! # ruby-analyzer:disable useless_assignment, unused_argument
+//! ...
+//! # ruby-analyzer:enable useless_assignment, unused_argument
+//! ```
+//!
+//! A trailing comment only applies to its own line; a comment on a line of its own applies until
+//! the matching `enable` (or the end of the file). `all` stands for every rule.
+//!
+//! Rules look at this crate's `Node`s. Callers that also have the tbc parser's take on a file can
+//! run `check_tbc`, which hands each node of its `ScopedIndex` to the rules, for the things only it
+//! knows about (ex. `Point = Struct.new(:x, :y)` being a class).
+//!
+pub mod config;
+pub mod rules;
+
+use std::collections::HashMap;
+
+use lsp_types::{
+    CodeAction, CodeActionKind, Diagnostic, DiagnosticSeverity, NumberOrString, TextEdit, Url,
+    WorkspaceEdit,
+};
+use ropey::Rope;
+use ruby_analyzer_tbc_parser::{location::LocNode, scoped_index, ScopedIndex};
+
+use self::config::{lint_config, LintConfigFile, RuleConfig};
+use crate::{
+    comments::comments,
+    node::{index_by_id, Loc},
+    parser::{parse, FileSource},
+    unused_variables::SOURCE,
+    Node,
+};
+
+#[salsa::input]
+pub struct LintQuery {
+    pub file_source: FileSource,
+    pub config_file: LintConfigFile,
+}
+
+/// Runs the builtin rules (see `rules::builtin_rules`) over the query's file.
+///
+#[salsa::tracked]
+pub fn lint(db: &dyn crate::db::Db, query: LintQuery) -> Vec<LintDiagnostic> {
+    let config = lint_config(db, query.config_file(db));
+
+    check(db, query.file_source(db), &config, &rules::builtin_rules())
+}
+
+/// A lint rule.
+///
+pub trait Rule: Send + Sync {
+    /// Identifies the rule in the config file, `disable` comments and diagnostics' `code`, ex.
+    /// `"debugger"`.
+    fn name(&self) -> &'static str;
+
+    fn default_severity(&self) -> DiagnosticSeverity {
+        DiagnosticSeverity::WARNING
+    }
+
+    /// Whether the rule runs when the config file doesn't mention it.
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+
+    /// Called for every node in the file, in the order they were parsed (children first).
+    fn check_node(&self, _context: &RuleContext<'_>, _node: &Node, _offenses: &mut Vec<Offense>) {}
+
+    /// Called once per file, after `check_node`; for rules that work off of a whole-file query.
+    fn check_file(&self, _context: &RuleContext<'_>, _offenses: &mut Vec<Offense>) {}
+
+    /// Called for every node in the tbc parser's `ScopedIndex` of the file, along with the
+    /// `ScopeGate` it's in, when the file is checked with `check_tbc`. Those nodes don't have
+    /// locations; the context's `tbc_locations` do.
+    fn check_tbc_node(
+        &self,
+        _context: &RuleContext<'_>,
+        _scope_gate: &ruby_analyzer_tbc_parser::ScopeGate,
+        _node: &scoped_index::Node,
+        _offenses: &mut Vec<Offense>,
+    ) {
+    }
+}
+
+/// What a rule has to work with.
+///
+pub struct RuleContext<'a> {
+    pub(crate) db: &'a dyn crate::db::Db,
+    pub(crate) file_source: FileSource,
+    pub(crate) code: &'a Rope,
+    pub(crate) nodes: &'a [Node],
+    pub(crate) by_id: &'a HashMap<usize, &'a Node>,
+    pub(crate) tbc_locations: &'a [LocNode],
+    pub(crate) config: &'a RuleConfig,
+}
+
+impl<'a> RuleContext<'a> {
+    pub fn db(&self) -> &'a dyn crate::db::Db {
+        self.db
+    }
+
+    pub fn file_source(&self) -> FileSource {
+        self.file_source
+    }
+
+    pub fn code(&self) -> &'a Rope {
+        self.code
+    }
+
+    pub fn nodes(&self) -> &'a [Node] {
+        self.nodes
+    }
+
+    pub fn node(&self, id: usize) -> Option<&'a Node> {
+        self.by_id.get(&id).copied()
+    }
+
+    /// The tbc parser's `LocNode`s of the file; empty unless it's being checked with `check_tbc`.
+    pub fn tbc_locations(&self) -> &'a [LocNode] {
+        self.tbc_locations
+    }
+
+    /// The rule's own options from the config file, ex. `max: 10`.
+    pub fn option(&self, key: &str) -> Option<&'a str> {
+        self.config.option(key)
+    }
+
+    pub fn source(&self, loc: Loc) -> String {
+        self.code.byte_slice(loc.begin..loc.end).to_string()
+    }
+}
+
+/// A problem found by a rule.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offense {
+    pub(crate) loc: Loc,
+    pub(crate) message: String,
+    pub(crate) fix: Option<Fix>,
+}
+
+impl Offense {
+    pub fn new(loc: Loc, message: impl Into<String>) -> Self {
+        Self {
+            loc,
+            message: message.into(),
+            fix: None,
+        }
+    }
+
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+}
+
+/// Replaces the code at `loc` with `replacement`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    pub(crate) loc: Loc,
+    pub(crate) replacement: String,
+}
+
+impl Fix {
+    pub fn new(loc: Loc, replacement: impl Into<String>) -> Self {
+        Self {
+            loc,
+            replacement: replacement.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintDiagnostic {
+    pub(crate) rule: &'static str,
+    pub(crate) diagnostic: Diagnostic,
+    pub(crate) fix: Option<TextEdit>,
+}
+
+impl LintDiagnostic {
+    pub fn rule(&self) -> &'static str {
+        self.rule
+    }
+
+    pub fn diagnostic(&self) -> &Diagnostic {
+        &self.diagnostic
+    }
+
+    pub fn fix(&self) -> Option<&TextEdit> {
+        self.fix.as_ref()
+    }
+
+    /// The fix as an LSP code action for the file at `uri`.
+    ///
+    pub fn to_code_action(&self, uri: Url) -> Option<CodeAction> {
+        let fix = self.fix.clone()?;
+
+        Some(CodeAction {
+            title: format!("Fix `{}`", self.rule),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![self.diagnostic.clone()]),
+            edit: Some(WorkspaceEdit {
+                changes: Some([(uri, vec![fix])].into_iter().collect()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+}
+
+/// Runs `rules` over `file_source`, as configured by `config`, minus anything that's disabled by
+/// comments.
+///
+pub fn check(
+    db: &dyn crate::db::Db,
+    file_source: FileSource,
+    config: &config::LintConfig,
+    rules: &[Box<dyn Rule>],
+) -> Vec<LintDiagnostic> {
+    run(
+        db,
+        file_source,
+        config,
+        rules,
+        &[],
+        |rule, context, offenses| {
+            for node in context.nodes {
+                rule.check_node(context, node, offenses);
+            }
+
+            rule.check_file(context, offenses);
+        },
+    )
+}
+
+/// Like `check`, but hands `rules` the nodes of the tbc parser's `ScopedIndex` of `file_source`
+/// (see `Rule::check_tbc_node`) instead of this crate's. `loc_nodes` and `index` are what the tbc
+/// parser's `parse` returns for the same code.
+///
+pub fn check_tbc(
+    db: &dyn crate::db::Db,
+    file_source: FileSource,
+    config: &config::LintConfig,
+    rules: &[Box<dyn Rule>],
+    loc_nodes: &[LocNode],
+    index: &ScopedIndex,
+) -> Vec<LintDiagnostic> {
+    run(
+        db,
+        file_source,
+        config,
+        rules,
+        loc_nodes,
+        |rule, context, offenses| {
+            for (scope_gate, nodes) in index.iter() {
+                for node in nodes {
+                    rule.check_tbc_node(context, scope_gate, node, offenses);
+                }
+            }
+        },
+    )
+}
+
+/// Runs each enabled rule through `visit`, then turns the offenses it found into diagnostics.
+///
+fn run(
+    db: &dyn crate::db::Db,
+    file_source: FileSource,
+    config: &config::LintConfig,
+    rules: &[Box<dyn Rule>],
+    tbc_locations: &[LocNode],
+    visit: impl Fn(&dyn Rule, &RuleContext<'_>, &mut Vec<Offense>),
+) -> Vec<LintDiagnostic> {
+    let code = file_source.code(db);
+    let nodes = parse(db, file_source);
+    let by_id = index_by_id(&nodes);
+    let suppressions = Suppressions::new(db, file_source);
+
+    let mut diagnostics = Vec::new();
+
+    for rule in rules {
+        let rule_config = config.rule(rule.name());
+
+        if !rule_config
+            .enabled()
+            .unwrap_or_else(|| rule.enabled_by_default())
+        {
+            continue;
+        }
+
+        let context = RuleContext {
+            db,
+            file_source,
+            code,
+            nodes: &nodes,
+            by_id: &by_id,
+            tbc_locations,
+            config: rule_config,
+        };
+
+        let mut offenses = Vec::new();
+        visit(rule.as_ref(), &context, &mut offenses);
+
+        let severity = rule_config
+            .severity()
+            .unwrap_or_else(|| rule.default_severity());
+
+        diagnostics.extend(
+            offenses
+                .into_iter()
+                .filter(|offense| !suppressions.suppresses(code, rule.name(), offense.loc))
+                .map(|offense| LintDiagnostic {
+                    rule: rule.name(),
+                    diagnostic: Diagnostic {
+                        range: offense.loc.to_lsp_range(code),
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String(rule.name().to_string())),
+                        source: Some(SOURCE.to_string()),
+                        message: offense.message,
+                        ..Default::default()
+                    },
+                    fix: offense.fix.map(|fix| TextEdit {
+                        range: fix.loc.to_lsp_range(code),
+                        new_text: fix.replacement,
+                    }),
+                }),
+        );
+    }
+
+    diagnostics.sort_by_key(|diagnostic| {
+        (
+            diagnostic.diagnostic.range.start.line,
+            diagnostic.diagnostic.range.start.character,
+        )
+    });
+
+    diagnostics
+}
+
+const DISABLE: &str = "ruby-analyzer:disable";
+const ENABLE: &str = "ruby-analyzer:enable";
+
+/// The line ranges where rules are disabled by comments.
+///
+struct Suppressions {
+    /// (rule or `all`, first line, last line)
+    ranges: Vec<(String, usize, usize)>,
+}
+
+impl Suppressions {
+    fn new(db: &dyn crate::db::Db, file_source: FileSource) -> Self {
+        let code = file_source.code(db);
+        let last_line = code.len_lines().saturating_sub(1);
+
+        let mut ranges = Vec::new();
+
+        // (rule, line) of `disable`s that haven't been `enable`d yet.
+        let mut open: Vec<(String, usize)> = Vec::new();
+
+        for comment in comments(db, file_source) {
+            let body = comment.body();
+            let line = code.byte_to_line(comment.loc().begin());
+
+            let line_start = code.line_to_byte(line);
+            let own_line = code
+                .byte_slice(line_start..comment.loc().begin())
+                .chars()
+                .all(char::is_whitespace);
+
+            if let Some(names) = body.trim().strip_prefix(DISABLE) {
+                for name in rule_names(names) {
+                    if own_line {
+                        open.push((name, line));
+                    } else {
+                        ranges.push((name, line, line));
+                    }
+                }
+            } else if let Some(names) = body.trim().strip_prefix(ENABLE) {
+                for name in rule_names(names) {
+                    open.retain(|(rule, start)| {
+                        if *rule == name || name == "all" {
+                            ranges.push((rule.clone(), *start, line));
+                            false
+                        } else {
+                            true
+                        }
+                    });
+                }
+            }
+        }
+
+        ranges.extend(
+            open.into_iter()
+                .map(|(rule, start)| (rule, start, last_line)),
+        );
+
+        Self { ranges }
+    }
+
+    fn suppresses(&self, code: &Rope, rule: &str, loc: Loc) -> bool {
+        let line = code.byte_to_line(loc.begin);
+
+        self.ranges.iter().any(|(name, first, last)| {
+            (name == rule || name == "all") && *first <= line && line <= *last
+        })
+    }
+}
+
+fn rule_names(names: &str) -> Vec<String> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ruby_analyzer_tbc_parser::scoped_index::{nodes::ClassConstructorKind, NodeProperties};
+
+    use super::*;
+    use crate::db::Database;
+
+    const CODE: &str = r#"def run(job)
+  binding.pry
+  byebug # ruby-analyzer:disable debugger
+  # ruby-analyzer:disable all
+  debugger
+  # ruby-analyzer:enable all
+  remote_byebug
+end"#;
+
+    fn lint_rules(db: &Database, config: &str) -> Vec<(String, u32, Option<DiagnosticSeverity>)> {
+        let file_source = FileSource::new(db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+        let config_file = LintConfigFile::new(
+            db,
+            PathBuf::from("/tmp/.ruby-analyzer.yml"),
+            config.to_string(),
+        );
+
+        lint(db, LintQuery::new(db, file_source, config_file))
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.rule().to_string(),
+                    diagnostic.diagnostic().range.start.line,
+                    diagnostic.diagnostic().severity,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn lint_test() {
+        let db = Database::default();
+
+        assert_eq!(
+            vec![
+                (
+                    "unused_argument".to_string(),
+                    0,
+                    Some(DiagnosticSeverity::WARNING)
+                ),
+                ("debugger".to_string(), 1, Some(DiagnosticSeverity::WARNING)),
+                ("debugger".to_string(), 6, Some(DiagnosticSeverity::WARNING)),
+            ],
+            lint_rules(&db, "")
+        );
+
+        let config = r#"
+unused_argument:
+  enabled: false
+
+debugger:
+  severity: error
+"#;
+
+        assert_eq!(
+            vec![
+                ("debugger".to_string(), 1, Some(DiagnosticSeverity::ERROR)),
+                ("debugger".to_string(), 6, Some(DiagnosticSeverity::ERROR)),
+            ],
+            lint_rules(&db, config)
+        );
+    }
+
+    /// Flags classes made with `Struct.new`, which only the tbc parser knows are classes.
+    struct NoStructs;
+
+    impl Rule for NoStructs {
+        fn name(&self) -> &'static str {
+            "no_structs"
+        }
+
+        fn check_tbc_node(
+            &self,
+            context: &RuleContext<'_>,
+            _scope_gate: &ruby_analyzer_tbc_parser::ScopeGate,
+            node: &scoped_index::Node,
+            offenses: &mut Vec<Offense>,
+        ) {
+            let NodeProperties::ClassConstructor(constructor) = node.properties() else {
+                return;
+            };
+
+            if constructor.kind() != ClassConstructorKind::Struct {
+                return;
+            }
+
+            if let Some(loc_node) = context
+                .tbc_locations()
+                .iter()
+                .find(|loc_node| loc_node.name() == constructor.name())
+            {
+                offenses.push(Offense::new(loc_node.name_l().into(), "Use a class"));
+            }
+        }
+    }
+
+    #[test]
+    fn tbc_test() {
+        let db = Database::default();
+        let code = r#"Point = Struct.new(:x, :y)
+Pair = Struct.new(:a, :b) # ruby-analyzer:disable no_structs
+"#;
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(code));
+
+        let tbc_db = ruby_analyzer_tbc_parser::Database::default();
+        let tbc_file_source = ruby_analyzer_tbc_parser::parser::FileSource::new(
+            &tbc_db,
+            PathBuf::from("/tmp/test.rb"),
+            Rope::from_str(code),
+        );
+        let (loc_nodes, index) = ruby_analyzer_tbc_parser::parser::parse(&tbc_db, tbc_file_source);
+
+        let config = config::LintConfig::default();
+        let rules: Vec<Box<dyn Rule>> = vec![Box::new(NoStructs)];
+
+        let diagnostics = check_tbc(&db, file_source, &config, &rules, &loc_nodes, &index);
+        assert_eq!(
+            vec![(0, "Use a class")],
+            diagnostics
+                .iter()
+                .map(|diagnostic| (
+                    diagnostic.diagnostic().range.start.line,
+                    diagnostic.diagnostic().message.as_str()
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        // `check` only hands out this crate's nodes.
+        assert!(check(&db, file_source, &config, &rules).is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+//! Lint configuration, read from the project's `.ruby-analyzer.yml`. Each top-level key names a
+//! rule; its settings are indented below it:
+//!
+//! ```yaml
+//! # Turn a rule off.
+//! unused_argument:
+//!   enabled: false
+//!
+//! debugger:
+//!   severity: error   # error, warning, info or hint
+//!
+//! some_rule:
+//!   max: 10           # Anything else is an option for the rule itself.
+//! ```
+//!
+//! Only that subset of YAML is understood; anything else is reported in `LintConfig::errors`.
+//!
+use std::{collections::BTreeMap, path::PathBuf};
+
+use lsp_types::DiagnosticSeverity;
+
+/// The name of the config file, at the root of the project.
+///
+pub const CONFIG_FILE_NAME: &str = ".ruby-analyzer.yml";
+
+/// The path and contents of the config file. A project without one can use an empty `text`.
+///
+#[salsa::input]
+pub struct LintConfigFile {
+    #[return_ref]
+    pub path: PathBuf,
+
+    #[return_ref]
+    pub text: String,
+}
+
+#[salsa::tracked]
+pub fn lint_config(db: &dyn crate::db::Db, config_file: LintConfigFile) -> LintConfig {
+    LintConfig::parse(config_file.text(db))
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LintConfig {
+    pub(crate) rules: BTreeMap<String, RuleConfig>,
+    pub(crate) errors: Vec<ConfigError>,
+}
+
+static DEFAULT_RULE_CONFIG: RuleConfig = RuleConfig {
+    enabled: None,
+    severity: None,
+    options: BTreeMap::new(),
+};
+
+impl LintConfig {
+    pub fn parse(text: &str) -> Self {
+        let mut config = Self::default();
+        let mut current: Option<String> = None;
+
+        for (index, line) in text.lines().enumerate() {
+            let content = strip_comment(line);
+
+            if content.trim().is_empty() {
+                continue;
+            }
+
+            let indented = content.starts_with([' ', '\t']);
+
+            let Some((key, value)) = content.trim().split_once(':') else {
+                config.error(
+                    index,
+                    format!("Expected `key: value`, got `{}`", content.trim()),
+                );
+                continue;
+            };
+
+            let (key, value) = (key.trim(), unquote(value.trim()));
+
+            if !indented {
+                if !value.is_empty() {
+                    config.error(
+                        index,
+                        format!("Expected settings for rule `{key}` below it"),
+                    );
+                }
+
+                config.rules.entry(key.to_string()).or_default();
+                current = Some(key.to_string());
+                continue;
+            }
+
+            let Some(rule) = current.as_ref().and_then(|name| config.rules.get_mut(name)) else {
+                config.error(index, format!("Setting `{key}` doesn't belong to a rule"));
+                continue;
+            };
+
+            match key {
+                "enabled" => match value {
+                    "true" => rule.enabled = Some(true),
+                    "false" => rule.enabled = Some(false),
+                    _ => config.error(index, format!("Expected `true` or `false`, got `{value}`")),
+                },
+                "severity" => match parse_severity(value) {
+                    Some(severity) => rule.severity = Some(severity),
+                    None => config.error(
+                        index,
+                        format!("Expected `error`, `warning`, `info` or `hint`, got `{value}`"),
+                    ),
+                },
+                _ => {
+                    rule.options.insert(key.to_string(), value.to_string());
+                }
+            }
+        }
+
+        config
+    }
+
+    fn error(&mut self, line: usize, message: String) {
+        self.errors.push(ConfigError { line, message });
+    }
+
+    /// Settings for the rule `name`; all defaults if the file doesn't mention it.
+    ///
+    pub fn rule(&self, name: &str) -> &RuleConfig {
+        self.rules.get(name).unwrap_or(&DEFAULT_RULE_CONFIG)
+    }
+
+    pub fn rules(&self) -> &BTreeMap<String, RuleConfig> {
+        &self.rules
+    }
+
+    /// Lines that couldn't be made sense of.
+    ///
+    pub fn errors(&self) -> &[ConfigError] {
+        &self.errors
+    }
+}
+
+/// A rule's settings. `None`s mean "use the rule's default".
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RuleConfig {
+    pub(crate) enabled: Option<bool>,
+    pub(crate) severity: Option<DiagnosticSeverity>,
+    pub(crate) options: BTreeMap<String, String>,
+}
+
+impl RuleConfig {
+    pub fn enabled(&self) -> Option<bool> {
+        self.enabled
+    }
+
+    pub fn severity(&self) -> Option<DiagnosticSeverity> {
+        self.severity
+    }
+
+    pub fn option(&self, key: &str) -> Option<&str> {
+        self.options.get(key).map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// 0-based.
+    pub(crate) line: usize,
+    pub(crate) message: String,
+}
+
+impl ConfigError {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn message(&self) -> &str {
+        self.message.as_ref()
+    }
+}
+
+fn parse_severity(value: &str) -> Option<DiagnosticSeverity> {
+    match value {
+        "error" => Some(DiagnosticSeverity::ERROR),
+        "warning" => Some(DiagnosticSeverity::WARNING),
+        "info" | "information" => Some(DiagnosticSeverity::INFORMATION),
+        "hint" => Some(DiagnosticSeverity::HINT),
+        _ => None,
+    }
+}
+
+/// Drops a trailing `# ...`, unless the `#` is inside quotes.
+///
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+
+    for (index, c) in line.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('#', None) => return &line[..index],
+            _ => (),
+        }
+    }
+
+    line
+}
+
+fn unquote(value: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+        .unwrap_or(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+# Project-wide lint settings.
+unused_argument:
+  enabled: false
+
+debugger:
+  severity: error # Never ship these.
+  message: "Don't #commit this"
+
+  enabled: maybe
+stray
+"#;
+
+    #[test]
+    fn parse_test() {
+        let config = LintConfig::parse(CONFIG);
+
+        assert_eq!(Some(false), config.rule("unused_argument").enabled());
+        assert_eq!(None, config.rule("unused_argument").severity());
+
+        let debugger = config.rule("debugger");
+        assert_eq!(None, debugger.enabled());
+        assert_eq!(Some(DiagnosticSeverity::ERROR), debugger.severity());
+        assert_eq!(Some("Don't #commit this"), debugger.option("message"));
+
+        assert_eq!(&RuleConfig::default(), config.rule("useless_assignment"));
+
+        let errors: Vec<usize> = config.errors().iter().map(ConfigError::line).collect();
+        assert_eq!(vec![9, 10], errors);
+    }
+}
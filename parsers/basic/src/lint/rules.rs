@@ -0,0 +1,163 @@
+//! The rules that come with the analyzer.
+//!
+use super::{Fix, Offense, Rule, RuleContext};
+use crate::{
+    node::Loc,
+    properties::Properties,
+    unused_variables::{variable_diagnostics, VariableDiagnosticKind},
+    Node,
+};
+
+/// Every builtin rule, in the order their offenses are reported.
+///
+pub fn builtin_rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(Variables(VariableDiagnosticKind::UnusedArgument)),
+        Box::new(Variables(VariableDiagnosticKind::UselessAssignment)),
+        Box::new(Variables(VariableDiagnosticKind::ShadowingBlockParameter)),
+        Box::new(Variables(VariableDiagnosticKind::UnusedShadowarg)),
+        Box::new(Debugger),
+    ]
+}
+
+/// One kind of `crate::unused_variables` diagnostic, as a rule; the rule's name is the kind's
+/// `code()`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Variables(pub VariableDiagnosticKind);
+
+impl Rule for Variables {
+    fn name(&self) -> &'static str {
+        self.0.code()
+    }
+
+    fn check_file(&self, context: &RuleContext<'_>, offenses: &mut Vec<Offense>) {
+        for diagnostic in variable_diagnostics(context.db(), context.file_source()) {
+            if diagnostic.kind() != self.0 {
+                continue;
+            }
+
+            let loc = diagnostic.loc();
+            let offense = Offense::new(loc, diagnostic.diagnostic().message.clone());
+
+            offenses.push(match diagnostic.fix() {
+                Some(fix) => offense.with_fix(Fix::new(
+                    Loc {
+                        begin: loc.begin,
+                        end: loc.begin,
+                    },
+                    fix.new_text.clone(),
+                )),
+                None => offense,
+            });
+        }
+    }
+}
+
+/// Leftover calls to a debugger: `binding.pry`, `binding.irb`, `byebug`, `debugger` and
+/// `remote_byebug`. When the call is all there is on its line, the fix deletes the line.
+///
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Debugger;
+
+const DEBUGGER_METHODS: &[&str] = &["byebug", "debugger", "remote_byebug"];
+const BINDING_METHODS: &[&str] = &["pry", "irb", "remote_pry", "pry_remote"];
+
+impl Rule for Debugger {
+    fn name(&self) -> &'static str {
+        "debugger"
+    }
+
+    fn check_node(&self, context: &RuleContext<'_>, node: &Node, offenses: &mut Vec<Offense>) {
+        let Properties::Send(send) = node.properties() else {
+            return;
+        };
+
+        let is_debugger = match send.recv_id.and_then(|id| context.node(id)) {
+            None => DEBUGGER_METHODS.contains(&send.method_name.as_str()),
+            Some(recv) => {
+                BINDING_METHODS.contains(&send.method_name.as_str())
+                    && matches!(
+                        recv.properties(),
+                        Properties::Send(binding)
+                            if binding.recv_id.is_none() && binding.method_name == "binding"
+                    )
+            }
+        };
+
+        if !is_debugger {
+            return;
+        }
+
+        let loc = *node.expression_l();
+        let offense = Offense::new(
+            loc,
+            format!("Remove debugger entry point `{}`.", context.source(loc)),
+        );
+
+        offenses.push(match own_line(context, loc) {
+            Some(line) => offense.with_fix(Fix::new(line, "")),
+            None => offense,
+        });
+    }
+}
+
+/// The whole line `loc` is on (including its line break), if there's nothing else on it.
+///
+fn own_line(context: &RuleContext<'_>, loc: Loc) -> Option<Loc> {
+    let code = context.code();
+    let line = code.byte_to_line(loc.begin);
+
+    if code.byte_to_line(loc.end) != line {
+        return None;
+    }
+
+    let begin = code.line_to_byte(line);
+    let end = code.line_to_byte((line + 1).min(code.len_lines()));
+
+    let before = code.byte_slice(begin..loc.begin).to_string();
+    let after = code.byte_slice(loc.end..end).to_string();
+
+    (before.trim().is_empty() && after.trim().is_empty()).then_some(Loc { begin, end })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ropey::Rope;
+
+    use super::*;
+    use crate::{db::Database, lint::check, lint::config::LintConfig, parser::FileSource};
+
+    const CODE: &str = r#"def run
+  binding.pry
+  value = binding.irb
+  binding.local_variable_get(:x)
+  debugger
+end
+"#;
+
+    #[test]
+    fn debugger_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let rules: Vec<Box<dyn Rule>> = vec![Box::new(Debugger)];
+        let diagnostics = check(&db, file_source, &LintConfig::default(), &rules);
+
+        let lines: Vec<u32> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.diagnostic().range.start.line)
+            .collect();
+        assert_eq!(vec![1, 2, 4], lines);
+
+        let fix = diagnostics[0].fix().unwrap();
+        assert_eq!("", fix.new_text);
+        assert_eq!((1, 0), (fix.range.start.line, fix.range.start.character));
+        assert_eq!((2, 0), (fix.range.end.line, fix.range.end.character));
+
+        // Not alone on its line.
+        assert!(diagnostics[1].fix().is_none());
+    }
+}
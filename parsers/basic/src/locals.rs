@@ -0,0 +1,893 @@
+//! Local variables: which scope each one lives in, where it's defined and used, and which
+//! definitions may have produced the value seen at each use (reaching definitions).
+//!
+//! Ruby decides what's a local variable when parsing: an identifier is a local from the point of
+//! its first assignment (or parameter) onward, so lib-ruby-parser only produces `Lvar`s for names
+//! that were already assigned. Methods, classes and modules start a fresh set of locals, while
+//! blocks can see (and assign) the locals of the scope they're in.
+//!
+//! The data-flow part is based on where things are in the source, rather than on a control-flow
+//! graph: a later definition hides an earlier one from a use unless the later one might not run
+//! (ex. it's in one branch of an `if` that the use isn't in). Loops and blocks (which can run more
+//! than once) let definitions reach uses earlier in the same loop. Early exits (`return`, `break`,
+//! `raise`, ...) aren't taken into account, so the results err on the side of "may reach".
+//!
+use std::collections::{HashMap, HashSet};
+
+use ropey::Rope;
+
+use crate::{
+    node::{index_by_id, Contains, Loc},
+    parser::{parse, FileSource},
+    properties::Properties,
+    Node,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LocalScopeKind {
+    /// The top level of the file.
+    TopLevel,
+
+    /// The body of a class, module or `class << self`.
+    Class,
+
+    /// The body of a `def`.
+    Method,
+
+    /// A block or lambda, which can see the locals of its parent scope.
+    Block,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocalScope {
+    pub(crate) kind: LocalScopeKind,
+
+    /// ID of the node that opens the scope (ex. the `Def`); `None` for the top level.
+    pub(crate) node_id: Option<usize>,
+    pub(crate) loc: Loc,
+
+    /// Index (in `Locals::scopes`) of the enclosing scope.
+    pub(crate) parent: Option<usize>,
+}
+
+impl LocalScope {
+    pub fn kind(&self) -> LocalScopeKind {
+        self.kind
+    }
+
+    pub fn node_id(&self) -> Option<usize> {
+        self.node_id
+    }
+
+    pub fn loc(&self) -> Loc {
+        self.loc
+    }
+
+    pub fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DefinitionKind {
+    /// A method or block parameter (including `|a; b|` block-locals).
+    Parameter,
+
+    /// `a = 1`, `a += 1`, `for a in ...`, `rescue => a`, ...
+    Assignment,
+
+    /// `a, b = 1, 2`
+    MultipleAssignment,
+
+    /// `in [a, b]`, `=> a`
+    PatternMatch,
+
+    /// `/(?<a>\w+)/ =~ str`
+    RegexpCapture,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Definition {
+    pub(crate) node_id: usize,
+    pub(crate) kind: DefinitionKind,
+    pub(crate) name_l: Loc,
+
+    /// Where the variable gets its value, ex. after the right-hand side of `a = b + 1`.
+    pub(crate) offset: usize,
+
+    /// `a ||= 1` and `a &&= 1` may not assign at all.
+    pub(crate) conditional: bool,
+}
+
+impl Definition {
+    pub fn node_id(&self) -> usize {
+        self.node_id
+    }
+
+    pub fn kind(&self) -> DefinitionKind {
+        self.kind
+    }
+
+    pub fn name_l(&self) -> Loc {
+        self.name_l
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Use {
+    pub(crate) node_id: usize,
+    pub(crate) loc: Loc,
+
+    /// Indexes (in `LocalVariable::definitions`) of the definitions whose value may be seen here.
+    /// Empty when the variable can only be `nil` here, ex. `a = a`.
+    pub(crate) reaching_definitions: Vec<usize>,
+}
+
+impl Use {
+    pub fn node_id(&self) -> usize {
+        self.node_id
+    }
+
+    pub fn loc(&self) -> Loc {
+        self.loc
+    }
+
+    pub fn reaching_definitions(&self) -> &[usize] {
+        self.reaching_definitions.as_ref()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LocalVariable {
+    pub(crate) name: String,
+
+    /// Index (in `Locals::scopes`) of the scope the variable belongs to.
+    pub(crate) scope: usize,
+    pub(crate) definitions: Vec<Definition>,
+    pub(crate) uses: Vec<Use>,
+}
+
+impl LocalVariable {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn scope(&self) -> usize {
+        self.scope
+    }
+
+    /// In source order.
+    ///
+    pub fn definitions(&self) -> &[Definition] {
+        self.definitions.as_ref()
+    }
+
+    /// In source order.
+    ///
+    pub fn uses(&self) -> &[Use] {
+        self.uses.as_ref()
+    }
+
+    /// Where the variable comes into existence.
+    ///
+    pub fn declaration(&self) -> Option<&Definition> {
+        self.definitions.first()
+    }
+}
+
+/// All local scopes and variables of a file.
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Locals {
+    pub(crate) scopes: Vec<LocalScope>,
+    pub(crate) variables: Vec<LocalVariable>,
+}
+
+impl Locals {
+    /// Outermost first; a scope's parent always comes before it.
+    ///
+    pub fn scopes(&self) -> &[LocalScope] {
+        self.scopes.as_ref()
+    }
+
+    pub fn variables(&self) -> &[LocalVariable] {
+        self.variables.as_ref()
+    }
+
+    /// Index of the innermost scope at `offset`.
+    ///
+    pub fn scope_at(&self, offset: usize) -> Option<usize> {
+        innermost_scope(&self.scopes, offset)
+    }
+
+    /// The variable that's defined or used at `offset`.
+    ///
+    pub fn variable_at(&self, offset: usize) -> Option<&LocalVariable> {
+        self.variables.iter().find(|variable| {
+            variable
+                .definitions
+                .iter()
+                .any(|definition| definition.name_l.contains(offset))
+                || variable.uses.iter().any(|use_| use_.loc.contains(offset))
+        })
+    }
+
+    /// The variables that can be referred to at `offset`: those of the innermost scope, plus
+    /// those of the enclosing scopes when in a block, that were declared before `offset`.
+    ///
+    pub fn visible_at(&self, offset: usize) -> Vec<&LocalVariable> {
+        let Some(scope) = self.scope_at(offset) else {
+            return Vec::new();
+        };
+
+        let chain = visible_scopes(&self.scopes, scope);
+
+        self.variables
+            .iter()
+            .filter(|variable| chain.contains(&variable.scope))
+            .filter(|variable| {
+                variable
+                    .declaration()
+                    .map_or(false, |declaration| declaration.name_l.end <= offset)
+            })
+            .collect()
+    }
+}
+
+#[salsa::tracked]
+pub fn locals(db: &dyn crate::db::Db, file_source: FileSource) -> Locals {
+    let code = file_source.code(db);
+    let nodes = parse(db, file_source);
+
+    let scopes = local_scopes(&nodes, code);
+    let (declarations, uses) = events(&nodes, code);
+
+    let mut locals = Locals {
+        scopes,
+        variables: Vec::new(),
+    };
+
+    resolve(&mut locals, declarations, uses);
+
+    let regions = Regions::new(&nodes);
+
+    for variable in locals.variables.iter_mut() {
+        for use_ in variable.uses.iter_mut() {
+            use_.reaching_definitions = regions.reaching_definitions(&variable.definitions, use_);
+        }
+    }
+
+    locals
+}
+
+fn local_scopes(nodes: &[Node], code: &Rope) -> Vec<LocalScope> {
+    // This goes first, so that it stays before a method that spans the whole file when sorting.
+    let mut scopes = vec![LocalScope {
+        kind: LocalScopeKind::TopLevel,
+        node_id: None,
+        loc: Loc {
+            begin: 0,
+            end: code.len_bytes(),
+        },
+        parent: None,
+    }];
+
+    scopes.extend(nodes.iter().filter_map(|node| {
+        let expression_l = *node.expression_l();
+
+        let (kind, loc) = match node.properties() {
+            Properties::Class(_) | Properties::Module(_) | Properties::SClass(_) => {
+                (LocalScopeKind::Class, expression_l)
+            }
+            Properties::Def(_) => (LocalScopeKind::Method, expression_l),
+            // `def obj.foo`; `obj` is evaluated outside of the method.
+            Properties::Defs(defs) => (
+                LocalScopeKind::Method,
+                Loc {
+                    begin: defs.name_l.begin,
+                    end: expression_l.end,
+                },
+            ),
+            // The call (ex. `foo(a)` in `foo(a) { |b| }`) is outside of the block.
+            Properties::Block(block) => (
+                LocalScopeKind::Block,
+                Loc {
+                    begin: block.begin_l.begin,
+                    end: expression_l.end,
+                },
+            ),
+            Properties::Numblock(numblock) => (
+                LocalScopeKind::Block,
+                Loc {
+                    begin: numblock.begin_l.begin,
+                    end: expression_l.end,
+                },
+            ),
+            _ => return None,
+        };
+
+        Some(LocalScope {
+            kind,
+            node_id: Some(node.id()),
+            loc,
+            parent: None,
+        })
+    }));
+
+    // Outer scopes first.
+    scopes.sort_by_key(|scope| (scope.loc.begin, std::cmp::Reverse(scope.loc.end)));
+
+    for i in 0..scopes.len() {
+        scopes[i].parent = (0..i)
+            .rev()
+            .find(|&j| scopes[j].loc.contains(&scopes[i].loc));
+    }
+
+    scopes
+}
+
+/// Index of the innermost scope containing `offset`.
+///
+fn innermost_scope(scopes: &[LocalScope], offset: usize) -> Option<usize> {
+    // Since outer scopes come first, the last match is the innermost one.
+    scopes
+        .iter()
+        .rposition(|scope| scope.loc.begin <= offset && offset < scope.loc.end.max(1))
+}
+
+/// `scope`, then its parents, for as long as they can see their parent's locals.
+///
+fn visible_scopes(scopes: &[LocalScope], scope: usize) -> Vec<usize> {
+    let mut chain = vec![scope];
+    let mut current = scope;
+
+    while scopes[current].kind == LocalScopeKind::Block {
+        let Some(parent) = scopes[current].parent else {
+            break;
+        };
+
+        chain.push(parent);
+        current = parent;
+    }
+
+    chain
+}
+
+struct Declaration {
+    name: String,
+    definition: Definition,
+}
+
+struct UseEvent {
+    name: String,
+    node_id: usize,
+    loc: Loc,
+}
+
+/// Every definition and use of a local in the file.
+///
+fn events(nodes: &[Node], code: &Rope) -> (Vec<Declaration>, Vec<UseEvent>) {
+    let by_id = index_by_id(nodes);
+
+    // Assignments whose value comes from somewhere other than their own `value_id`: the
+    // `Lvasgn`s in `a, b = ...`, `a += 1`, `a ||= 1` and `a &&= 1`. Maps their ID to where the
+    // value is assigned, whether they're part of a multiple assignment and whether they may not
+    // assign at all.
+    let mut indirect: HashMap<usize, (usize, bool, bool)> = HashMap::new();
+
+    // `a += 1` and friends read `a` first.
+    let mut read_first: HashSet<usize> = HashSet::new();
+
+    for node in nodes {
+        let end = node.expression_l().end;
+
+        match node.properties() {
+            Properties::Masgn(masgn) => {
+                let Some(lhs) = by_id.get(&masgn.lhs_id) else {
+                    continue;
+                };
+
+                for other in nodes {
+                    if matches!(other.properties(), Properties::Lvasgn(_))
+                        && lhs.expression_l().contains(other.expression_l())
+                    {
+                        indirect.insert(other.id(), (end, true, false));
+                    }
+                }
+            }
+            Properties::OpAsgn(op_asgn) => {
+                indirect.insert(op_asgn.recv_id, (end, false, false));
+                read_first.insert(op_asgn.recv_id);
+            }
+            Properties::OrAsgn(or_asgn) => {
+                indirect.insert(or_asgn.recv_id, (end, false, true));
+                read_first.insert(or_asgn.recv_id);
+            }
+            Properties::AndAsgn(and_asgn) => {
+                indirect.insert(and_asgn.recv_id, (end, false, true));
+                read_first.insert(and_asgn.recv_id);
+            }
+            _ => (),
+        }
+    }
+
+    let mut declarations = Vec::new();
+    let mut uses = Vec::new();
+
+    for node in nodes {
+        let expression_l = *node.expression_l();
+
+        let parameter = |name: &str, name_l: Loc| Declaration {
+            name: name.to_string(),
+            definition: Definition {
+                node_id: node.id(),
+                kind: DefinitionKind::Parameter,
+                name_l,
+                offset: name_l.end,
+                conditional: false,
+            },
+        };
+
+        match node.properties() {
+            Properties::Lvasgn(lvasgn) => {
+                let (offset, multiple, conditional) = indirect
+                    .get(&node.id())
+                    .copied()
+                    .unwrap_or((expression_l.end, false, false));
+
+                if read_first.contains(&node.id()) {
+                    uses.push(UseEvent {
+                        name: lvasgn.name.clone(),
+                        node_id: node.id(),
+                        loc: lvasgn.name_l,
+                    });
+                }
+
+                declarations.push(Declaration {
+                    name: lvasgn.name.clone(),
+                    definition: Definition {
+                        node_id: node.id(),
+                        kind: if multiple {
+                            DefinitionKind::MultipleAssignment
+                        } else {
+                            DefinitionKind::Assignment
+                        },
+                        name_l: lvasgn.name_l,
+                        offset,
+                        conditional,
+                    },
+                });
+            }
+            Properties::Lvar(lvar) => uses.push(UseEvent {
+                name: lvar.name.clone(),
+                node_id: node.id(),
+                loc: expression_l,
+            }),
+            Properties::Arg(arg) => declarations.push(parameter(&arg.name, expression_l)),
+            Properties::Shadowarg(shadowarg) => {
+                declarations.push(parameter(&shadowarg.name, expression_l))
+            }
+            Properties::Optarg(optarg) => declarations.push(parameter(&optarg.name, optarg.name_l)),
+            Properties::Kwarg(kwarg) => declarations.push(parameter(&kwarg.name, kwarg.name_l)),
+            Properties::Kwoptarg(kwoptarg) => {
+                declarations.push(parameter(&kwoptarg.name, kwoptarg.name_l))
+            }
+            Properties::Restarg(restarg) => {
+                if let (Some(name), Some(name_l)) = (&restarg.name, restarg.name_l) {
+                    declarations.push(parameter(name, name_l));
+                }
+            }
+            Properties::Kwrestarg(kwrestarg) => {
+                if let (Some(name), Some(name_l)) = (&kwrestarg.name, kwrestarg.name_l) {
+                    declarations.push(parameter(name, name_l));
+                }
+            }
+            Properties::Blockarg(blockarg) => {
+                if let (Some(name), Some(name_l)) = (&blockarg.name, blockarg.name_l) {
+                    declarations.push(parameter(name, name_l));
+                }
+            }
+            Properties::MatchVar(match_var) => declarations.push(Declaration {
+                name: match_var.name.clone(),
+                definition: Definition {
+                    node_id: node.id(),
+                    kind: DefinitionKind::PatternMatch,
+                    name_l: match_var.name_l,
+                    offset: match_var.name_l.end,
+                    conditional: false,
+                },
+            }),
+            Properties::MatchWithLvasgn(match_with_lvasgn) => {
+                let Some(re) = by_id.get(&match_with_lvasgn.re_id) else {
+                    continue;
+                };
+
+                for (name, name_l) in named_captures(code, re.expression_l()) {
+                    declarations.push(Declaration {
+                        name,
+                        definition: Definition {
+                            node_id: node.id(),
+                            kind: DefinitionKind::RegexpCapture,
+                            name_l,
+                            offset: expression_l.end,
+                            conditional: false,
+                        },
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+
+    declarations.sort_by_key(|declaration| declaration.definition.name_l.begin);
+    uses.sort_by_key(|use_| use_.loc.begin);
+
+    (declarations, uses)
+}
+
+/// The names of the named captures (ex. `(?<year>\d+)`) in a regexp literal, which `=~` assigns
+/// to locals.
+///
+fn named_captures(code: &Rope, re_l: &Loc) -> Vec<(String, Loc)> {
+    let source = code.byte_slice(re_l.begin..re_l.end).to_string();
+    let mut captures = Vec::new();
+
+    for (start, _) in source.match_indices("(?<") {
+        let name_begin = start + 3;
+        let name: String = source[name_begin..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+
+        let is_local = name
+            .chars()
+            .next()
+            .map_or(false, |c| c.is_lowercase() || c == '_');
+
+        if is_local && source[name_begin + name.len()..].starts_with('>') {
+            let begin = re_l.begin + name_begin;
+
+            captures.push((
+                name.clone(),
+                Loc {
+                    begin,
+                    end: begin + name.len(),
+                },
+            ));
+        }
+    }
+
+    captures
+}
+
+/// Works out which variable each declaration and use belongs to.
+///
+fn resolve(locals: &mut Locals, declarations: Vec<Declaration>, uses: Vec<UseEvent>) {
+    for Declaration { name, definition } in declarations {
+        let Some(scope) = innermost_scope(&locals.scopes, definition.name_l.begin) else {
+            continue;
+        };
+
+        // Parameters always belong to their own scope (shadowing any outer variable).
+        let existing = if definition.kind == DefinitionKind::Parameter {
+            locals
+                .variables
+                .iter()
+                .position(|variable| variable.scope == scope && variable.name == name)
+        } else {
+            lookup(locals, scope, &name)
+        };
+
+        match existing {
+            Some(index) => locals.variables[index].definitions.push(definition),
+            None => locals.variables.push(LocalVariable {
+                name,
+                scope,
+                definitions: vec![definition],
+                uses: Vec::new(),
+            }),
+        }
+    }
+
+    for UseEvent { name, node_id, loc } in uses {
+        let Some(scope) = innermost_scope(&locals.scopes, loc.begin) else {
+            continue;
+        };
+
+        if let Some(index) = lookup(locals, scope, &name) {
+            locals.variables[index].uses.push(Use {
+                node_id,
+                loc,
+                reaching_definitions: Vec::new(),
+            });
+        }
+    }
+
+    for variable in locals.variables.iter_mut() {
+        variable
+            .definitions
+            .sort_by_key(|definition| definition.name_l.begin);
+    }
+}
+
+/// The variable named `name` that's visible in `scope`: its own, or (from a block) one of an
+/// enclosing scope.
+///
+fn lookup(locals: &Locals, scope: usize, name: &str) -> Option<usize> {
+    visible_scopes(&locals.scopes, scope)
+        .into_iter()
+        .find_map(|scope| {
+            locals
+                .variables
+                .iter()
+                .position(|variable| variable.scope == scope && variable.name == name)
+        })
+}
+
+/// The parts of the code that may not run (ex. the branches of an `if`), and the ones that may run
+/// more than once (loops and blocks).
+///
+struct Regions {
+    conditional: Vec<Loc>,
+    looping: Vec<Loc>,
+}
+
+impl Regions {
+    fn new(nodes: &[Node]) -> Self {
+        let by_id = index_by_id(nodes);
+        let loc_of = |id: &usize| by_id.get(id).map(|node| *node.expression_l());
+
+        let mut conditional = Vec::new();
+        let mut looping = Vec::new();
+
+        for node in nodes {
+            let expression_l = *node.expression_l();
+
+            match node.properties() {
+                Properties::If(if_) => {
+                    conditional.extend(if_.if_true_id.iter().filter_map(loc_of));
+                    conditional.extend(if_.if_false_id.iter().filter_map(loc_of));
+                }
+                Properties::IfMod(if_mod) => {
+                    conditional.extend(if_mod.if_true_id.iter().filter_map(loc_of));
+                    conditional.extend(if_mod.if_false_id.iter().filter_map(loc_of));
+                }
+                Properties::IfTernary(if_ternary) => {
+                    conditional.extend(loc_of(&if_ternary.if_true_id));
+                    conditional.extend(loc_of(&if_ternary.if_false_id));
+                }
+                Properties::Case(case) => {
+                    conditional.extend(case.when_body_ids.iter().filter_map(loc_of));
+                    conditional.extend(case.else_body_id.iter().filter_map(loc_of));
+                }
+                Properties::CaseMatch(case_match) => {
+                    conditional.extend(case_match.in_body_ids.iter().filter_map(loc_of));
+                    conditional.extend(case_match.else_body_id.iter().filter_map(loc_of));
+                }
+                Properties::And(and) => conditional.extend(loc_of(&and.rhs_id)),
+                Properties::Or(or) => conditional.extend(loc_of(&or.rhs_id)),
+                Properties::OrAsgn(or_asgn) => conditional.extend(loc_of(&or_asgn.value_id)),
+                Properties::AndAsgn(and_asgn) => conditional.extend(loc_of(&and_asgn.value_id)),
+                Properties::While(while_) => {
+                    conditional.extend(while_.body_id.iter().filter_map(loc_of));
+                    looping.push(expression_l);
+                }
+                Properties::Until(until) => {
+                    conditional.extend(until.body_id.iter().filter_map(loc_of));
+                    looping.push(expression_l);
+                }
+                // The body runs at least once.
+                Properties::WhilePost(_) | Properties::UntilPost(_) => looping.push(expression_l),
+                Properties::For(for_) => {
+                    conditional.extend(for_.body_id.iter().filter_map(loc_of));
+                    looping.push(expression_l);
+                }
+                Properties::Block(block) => {
+                    let body_l = Loc {
+                        begin: block.begin_l.begin,
+                        end: expression_l.end,
+                    };
+
+                    conditional.push(body_l);
+                    looping.push(body_l);
+                }
+                Properties::Numblock(numblock) => {
+                    let body_l = Loc {
+                        begin: numblock.begin_l.begin,
+                        end: expression_l.end,
+                    };
+
+                    conditional.push(body_l);
+                    looping.push(body_l);
+                }
+                // An exception can interrupt the body anywhere.
+                Properties::Rescue(rescue) => {
+                    conditional.extend(rescue.body_id.iter().filter_map(loc_of));
+                    conditional.extend(rescue.rescue_body_ids.iter().filter_map(loc_of));
+                    conditional.extend(rescue.else_id.iter().filter_map(loc_of));
+                }
+                _ => (),
+            }
+        }
+
+        Self {
+            conditional,
+            looping,
+        }
+    }
+
+    fn reaching_definitions(&self, definitions: &[Definition], use_: &Use) -> Vec<usize> {
+        let use_offset = use_.loc.begin;
+
+        definitions
+            .iter()
+            .enumerate()
+            .filter(|(_, definition)| {
+                let position = definition.name_l.begin;
+
+                if definition.offset <= use_offset {
+                    !definitions
+                        .iter()
+                        .any(|other| self.kills(other, definition, use_offset))
+                } else {
+                    // Only via another iteration of a loop (or call of a block).
+                    self.looping
+                        .iter()
+                        .any(|loop_l| loop_l.contains(position) && loop_l.contains(use_offset))
+                }
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Does `other` always overwrite `definition` before `use_offset`? It does if it comes
+    /// between them and, once `definition` has run, nothing can skip it on the way to the use.
+    ///
+    fn kills(&self, other: &Definition, definition: &Definition, use_offset: usize) -> bool {
+        if other.conditional
+            || other.offset <= definition.offset
+            || other.offset > use_offset
+            || other == definition
+        {
+            return false;
+        }
+
+        let position = other.name_l.begin;
+
+        self.conditional
+            .iter()
+            .filter(|region| region.contains(position))
+            .all(|region| region.contains(definition.name_l.begin) || region.contains(use_offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::db::Database;
+
+    const CODE: &str = r#"def process(items, limit = 10)
+  count = 0
+  total = nil
+
+  items.each do |item; tmp|
+    count += 1
+    tmp = item
+  end
+
+  if count > limit
+    total = limit
+  else
+    total = count
+  end
+
+  a, b = total, count
+  if /(?<year>\d+)/ =~ a.to_s
+    puts year
+  end
+
+  total = total + b
+  total
+end"#;
+
+    fn setup(db: &Database) -> Locals {
+        let file_source = FileSource::new(db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        locals(db, file_source)
+    }
+
+    fn variable<'a>(locals: &'a Locals, name: &str) -> &'a LocalVariable {
+        locals
+            .variables()
+            .iter()
+            .find(|variable| variable.name() == name)
+            .unwrap()
+    }
+
+    /// The lines of the definitions that reach each use.
+    ///
+    fn reaching_lines(variable: &LocalVariable) -> Vec<Vec<usize>> {
+        let code = Rope::from_str(CODE);
+
+        variable
+            .uses()
+            .iter()
+            .map(|use_| {
+                use_.reaching_definitions()
+                    .iter()
+                    .map(|&index| code.byte_to_line(variable.definitions()[index].name_l().begin))
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scopes_test() {
+        let db = Database::default();
+        let locals = setup(&db);
+
+        let kinds: Vec<LocalScopeKind> = locals.scopes().iter().map(LocalScope::kind).collect();
+        assert_eq!(
+            vec![
+                LocalScopeKind::TopLevel,
+                LocalScopeKind::Method,
+                LocalScopeKind::Block
+            ],
+            kinds
+        );
+
+        // Block parameters and block-locals belong to the block; `count` is the method's.
+        assert_eq!(2, variable(&locals, "item").scope());
+        assert_eq!(2, variable(&locals, "tmp").scope());
+        assert_eq!(1, variable(&locals, "count").scope());
+
+        let mut names: Vec<&str> = locals
+            .visible_at(CODE.find("tmp = item").unwrap())
+            .into_iter()
+            .map(LocalVariable::name)
+            .collect();
+        names.sort_unstable();
+        assert_eq!(
+            vec!["count", "item", "items", "limit", "tmp", "total"],
+            names
+        );
+
+        let kinds: Vec<DefinitionKind> = ["items", "a", "year"]
+            .iter()
+            .map(|name| variable(&locals, name).definitions()[0].kind())
+            .collect();
+        assert_eq!(
+            vec![
+                DefinitionKind::Parameter,
+                DefinitionKind::MultipleAssignment,
+                DefinitionKind::RegexpCapture
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn reaching_definitions_test() {
+        let db = Database::default();
+        let locals = setup(&db);
+
+        // In the block, `count += 1` reads either the initial value or the previous call's.
+        // After the block, the block may not have been called at all.
+        assert_eq!(
+            vec![vec![1, 5], vec![1, 5], vec![1, 5], vec![1, 5]],
+            reaching_lines(variable(&locals, "count"))
+        );
+
+        // Both branches reach `a, b = total, ...`. Each only overwrites `total = nil` on its own
+        // path, so that one's (conservatively) kept too. `total = total + b` always runs, so it's
+        // the only one that reaches the last line.
+        assert_eq!(
+            vec![vec![2, 10, 12], vec![2, 10, 12], vec![20]],
+            reaching_lines(variable(&locals, "total"))
+        );
+
+        assert_eq!(vec![vec![16]], reaching_lines(variable(&locals, "year")));
+    }
+}
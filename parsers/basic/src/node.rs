@@ -1,4 +1,11 @@
-use crate::{properties::Properties, scope_gate::ScopeGate};
+use std::collections::HashMap;
+
+use ropey::Rope;
+
+use crate::{
+    properties::Properties,
+    scope_gate::{Node as ScopeGateNode, ScopeGate},
+};
 
 /// A `Node` represents an item in a ruby `Ast`. Unlike `lib-ruby-parser`'s Ast, which represents
 /// a node's hierarchy as part of the node itself (ex. a `class` node contains all of its `def`
@@ -41,12 +48,56 @@ impl Node {
     }
 }
 
+pub trait Contains<T> {
+    fn contains(&self, other: T) -> bool;
+}
+
+impl<'a> Contains<&'a Loc> for Loc {
+    fn contains(&self, other: &Self) -> bool {
+        self.begin <= other.begin && other.end <= self.end
+    }
+}
+
+impl Contains<usize> for Loc {
+    fn contains(&self, offset: usize) -> bool {
+        self.begin <= offset && offset <= self.end
+    }
+}
+
+/// Maps each of `nodes` by its ID, for when a node needs to look up its children (via their
+/// `*_id`s).
+///
+pub(crate) fn index_by_id(nodes: &[Node]) -> HashMap<usize, &Node> {
+    nodes.iter().map(|node| (node.id, node)).collect()
+}
+
+/// Is `node` (a `Def`, most likely) inside a `class << self` block?
+///
+pub(crate) fn in_singleton_class(nodes: &[Node], node: &Node) -> bool {
+    nodes.iter().any(|other| {
+        other.id() != node.id()
+            && matches!(other.properties(), Properties::SClass(_))
+            && other.expression_l().contains(node.expression_l())
+    })
+}
+
+/// When `node` is evaluated, is `self` a class/module (as opposed to an instance)?
+///
+pub(crate) fn in_singleton_context(nodes: &[Node], node: &Node) -> bool {
+    match node.scope_gate().last() {
+        Some(ScopeGateNode::Defs(_)) => true,
+        Some(ScopeGateNode::Def(_)) => in_singleton_class(nodes, node),
+        Some(ScopeGateNode::Class(_) | ScopeGateNode::Module(_)) => true,
+        None => false,
+    }
+}
+
 /// Represents the beginning and end of a Node.
 ///
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Loc {
-    begin: usize,
-    end: usize,
+    pub(crate) begin: usize,
+    pub(crate) end: usize,
 }
 
 impl Loc {
@@ -61,6 +112,45 @@ impl Loc {
     pub fn end(&self) -> usize {
         self.end
     }
+
+    /// Converts this byte-offset-based `Loc` to an LSP `Range`, which is line/UTF-16-based.
+    ///
+    pub fn to_lsp_range(&self, code: &Rope) -> lsp_types::Range {
+        lsp_types::Range::new(
+            offset_to_position(self.begin, code),
+            offset_to_position(self.end, code),
+        )
+    }
+}
+
+/// Converts a byte offset in `code` to an LSP `Position`.
+///
+pub fn offset_to_position(offset: usize, code: &Rope) -> lsp_types::Position {
+    let line = code.byte_to_line(offset);
+    let line_start = code.char_to_utf16_cu(code.line_to_char(line));
+    let character = code.char_to_utf16_cu(code.byte_to_char(offset)) - line_start;
+
+    lsp_types::Position::new(line as u32, character as u32)
+}
+
+/// Converts an LSP `Position` to a byte offset in `code`. Positions past the end of a line (or of
+/// the file) are clamped to it, as LSP asks.
+///
+pub fn position_to_offset(position: lsp_types::Position, code: &Rope) -> usize {
+    let line_idx = (position.line as usize).min(code.len_lines() - 1);
+    let line = code.line(line_idx);
+
+    // The line's length, not counting its line ending.
+    let mut line_chars = line.len_chars();
+    while line_chars > 0 && matches!(line.char(line_chars - 1), '\n' | '\r') {
+        line_chars -= 1;
+    }
+    let character = (position.character as usize).min(line.char_to_utf16_cu(line_chars));
+
+    let line_start = code.char_to_utf16_cu(code.line_to_char(line_idx));
+    let char_idx = code.utf16_cu_to_char(line_start + character);
+
+    code.char_to_byte(char_idx)
 }
 
 impl From<lib_ruby_parser::Loc> for Loc {
@@ -72,3 +162,35 @@ impl From<lib_ruby_parser::Loc> for Loc {
         }
     }
 }
+
+impl From<ruby_analyzer_tbc_parser::location::Loc> for Loc {
+    #[inline]
+    fn from(value: ruby_analyzer_tbc_parser::location::Loc) -> Self {
+        Self {
+            begin: value.begin(),
+            end: value.end(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Position;
+
+    use super::*;
+
+    #[test]
+    fn position_to_offset_test() {
+        let code = Rope::from_str("héllo\r\nworld");
+
+        assert_eq!(3, position_to_offset(Position::new(0, 2), &code));
+        assert_eq!(9, position_to_offset(Position::new(1, 1), &code));
+
+        // Past the end of the line, before its line ending.
+        assert_eq!(6, position_to_offset(Position::new(0, 40), &code));
+
+        // Past the end of the file.
+        assert_eq!(13, position_to_offset(Position::new(1, 40), &code));
+        assert_eq!(13, position_to_offset(Position::new(7, 40), &code));
+    }
+}
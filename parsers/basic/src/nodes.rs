@@ -236,6 +236,18 @@ pub struct Def {
     pub(crate) name_l: Loc,
     pub(crate) end_l: Option<Loc>,
     pub(crate) assignment_l: Option<Loc>,
+
+    pub(crate) visibility: Visibility,
+}
+
+impl Def {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -260,6 +272,18 @@ pub struct Defs {
     pub(crate) name_l: Loc,
     pub(crate) assignment_l: Option<Loc>,
     pub(crate) end_l: Option<Loc>,
+
+    pub(crate) visibility: Visibility,
+}
+
+impl Defs {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
@@ -910,6 +934,38 @@ pub struct UntilPost {
     pub(crate) keyword_l: Loc,
 }
 
+/// A method's visibility, as set by `public`, `protected`, `private` or `module_function`.
+///
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Visibility {
+    #[default]
+    Public,
+    Protected,
+    Private,
+
+    /// A private instance method, plus a public singleton method copy of it.
+    ModuleFunction,
+}
+
+impl Visibility {
+    pub(crate) fn from_method_name(method_name: &str) -> Option<Self> {
+        match method_name {
+            "public" => Some(Self::Public),
+            "protected" => Some(Self::Protected),
+            "private" => Some(Self::Private),
+            "module_function" => Some(Self::ModuleFunction),
+            _ => None,
+        }
+    }
+
+    /// Can the method be called with an explicit receiver (ex. `foo.bar`)? `protected` methods
+    /// can, as long as it's from within the same class hierarchy.
+    ///
+    pub fn callable_with_explicit_receiver(&self) -> bool {
+        matches!(self, Self::Public | Self::Protected)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct When {
     pub(crate) pattern_ids: Vec<usize>,
@@ -6,7 +6,11 @@ use std::{
 use lib_ruby_parser::traverse::visitor::Visitor;
 use ropey::Rope;
 
-use crate::{node::Node, transformer};
+use crate::{
+    comments::{Comment, MagicComment},
+    node::Node,
+    transformer,
+};
 
 /// The path and contents of a source file. Typically, this is what we parse.
 ///
@@ -26,6 +30,16 @@ pub struct FileSource {
 #[salsa::accumulator]
 pub struct Diagnostics(lib_ruby_parser::Diagnostic);
 
+/// Every comment in the file. See `crate::comments::comments` for retrieving these.
+///
+#[salsa::accumulator]
+pub struct Comments(Comment);
+
+/// Every magic comment in the file. See `crate::comments::magic_comments` for retrieving these.
+///
+#[salsa::accumulator]
+pub struct MagicCommentEntries(MagicComment);
+
 /// This is the main entry point / purpose to this crate. Takes source code from a single file,
 /// parses it using lib-ruby-parser, then transforms the lib-ruby-parser output to our custom
 /// `Node`s.
@@ -41,6 +55,14 @@ pub fn parse(db: &dyn crate::db::Db, file_source: FileSource) -> Arc<Vec<Node>>
         Diagnostics::push(db, diagnostic);
     }
 
+    for comment in &result.comments {
+        Comments::push(db, Comment::new(comment, code));
+    }
+
+    for magic_comment in &result.magic_comments {
+        MagicCommentEntries::push(db, MagicComment::new(magic_comment, code));
+    }
+
     if let Some(root_node) = result.ast {
         let node_source = NodeSource::new(db, *root_node);
         Arc::new(inner_transform(db, node_source))
@@ -116,4 +138,115 @@ mod tests {
         let diags = parse::accumulated::<Diagnostics>(&db, file_source);
         assert_eq!(diags.len(), 1);
     }
+
+    #[test]
+    fn parse_method_visibility_test() {
+        use crate::{nodes::Visibility, properties::Properties};
+
+        let db = crate::db::Database::default();
+        let file_uri = PathBuf::from("/tmp/test.rb");
+        let code = Rope::from_str(
+            r#"class Foo
+  def a; end
+  private
+  def b; end
+  public
+  def c; end
+  def d; end
+  private :d
+  protected def e; end
+  def initialize; end
+  def self.f; end
+  private_class_method :f
+
+  class << self
+    def g; end
+    private
+    def h; end
+  end
+
+  def i; end
+end
+
+module Bar
+  module_function
+  def j; end
+end
+
+def top; end"#,
+        );
+
+        let file_source = FileSource::new(&db, file_uri, code);
+        let nodes = parse(&db, file_source);
+
+        let mut visibilities: Vec<(&str, Visibility)> = nodes
+            .iter()
+            .filter_map(|node| match node.properties() {
+                Properties::Def(def) => Some((def.name(), def.visibility())),
+                Properties::Defs(defs) => Some((defs.name(), defs.visibility())),
+                _ => None,
+            })
+            .collect();
+        visibilities.sort_by_key(|(name, _)| *name);
+
+        assert_eq!(
+            vec![
+                ("a", Visibility::Public),
+                ("b", Visibility::Private),
+                ("c", Visibility::Public),
+                ("d", Visibility::Private),
+                ("e", Visibility::Protected),
+                ("f", Visibility::Private),
+                ("g", Visibility::Public),
+                ("h", Visibility::Private),
+                ("i", Visibility::Public),
+                ("initialize", Visibility::Private),
+                ("j", Visibility::ModuleFunction),
+                ("top", Visibility::Private),
+            ],
+            visibilities
+        );
+    }
+
+    #[test]
+    fn parse_singleton_class_visibility_test() {
+        use crate::{nodes::Visibility, properties::Properties};
+
+        let db = crate::db::Database::default();
+        let file_uri = PathBuf::from("/tmp/test.rb");
+        let code = Rope::from_str(
+            r#"class Foo
+  def call; end
+
+  class << self
+    def build; end
+    def call; end
+  end
+
+  private_class_method :build
+  private :call
+end"#,
+        );
+
+        let file_source = FileSource::new(&db, file_uri, code);
+        let nodes = parse(&db, file_source);
+
+        let visibilities: Vec<(&str, Visibility)> = nodes
+            .iter()
+            .filter_map(|node| match node.properties() {
+                Properties::Def(def) => Some((def.name(), def.visibility())),
+                _ => None,
+            })
+            .collect();
+
+        // `private :call` is about the instance method, not the one in `class << self`.
+        assert_eq!(
+            vec![
+                ("call", Visibility::Private),
+                ("build", Visibility::Private),
+                ("call", Visibility::Public),
+            ],
+            visibilities
+        );
+    }
 }
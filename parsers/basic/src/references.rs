@@ -0,0 +1,422 @@
+//! Find-all-references for methods and constants, across every file in a `Workspace`.
+//!
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use crate::{
+    constants::{defined_constants, definition_path, lexical_namespace, method_owner, ConstPath},
+    node::{in_singleton_class, in_singleton_context, index_by_id, Contains, Loc},
+    parser::{parse, FileSource},
+    properties::Properties,
+    scope_gate,
+    workspace::Workspace,
+    Node,
+};
+
+#[salsa::input]
+pub struct ReferencesQuery {
+    pub workspace: Workspace,
+    pub file_source: FileSource,
+    pub offset: usize,
+
+    /// Same as LSP's `ReferenceContext::include_declaration`.
+    pub include_declaration: bool,
+}
+
+/// A single place in the workspace that refers to (or declares) the symbol that was looked up.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Reference {
+    pub(crate) file_source: FileSource,
+    pub(crate) loc: Loc,
+}
+
+impl Reference {
+    pub fn file_source(&self) -> FileSource {
+        self.file_source
+    }
+
+    /// Location of just the name (ex. a `Send`'s `selector_l`), not the whole expression.
+    ///
+    pub fn loc(&self) -> Loc {
+        self.loc
+    }
+
+    pub fn to_lsp_location(&self, db: &dyn crate::db::Db) -> Option<lsp_types::Location> {
+        let uri = lsp_types::Url::from_file_path(self.file_source.file_uri(db)).ok()?;
+        let range = self.loc.to_lsp_range(self.file_source.code(db));
+
+        Some(lsp_types::Location::new(uri, range))
+    }
+}
+
+/// Finds the method or constant at the query's `offset`, then finds everywhere in the workspace
+/// that refers to it. Method call sites are matched by name, then by receiver when the receiver
+/// can be known without type info (ex. implicit `self`, `self.` or `SomeConst.`).
+///
+#[salsa::tracked]
+pub fn references(db: &dyn crate::db::Db, query: ReferencesQuery) -> Vec<Reference> {
+    let workspace = query.workspace(db);
+    let defined = defined_constants(db, workspace);
+
+    let nodes = parse(db, query.file_source(db));
+    let by_id = index_by_id(&nodes);
+
+    let Some(target) = Target::at_offset(&nodes, &by_id, query.offset(db), &defined) else {
+        return Vec::new();
+    };
+
+    let include_declaration = query.include_declaration(db);
+
+    workspace
+        .file_sources(db)
+        .iter()
+        .flat_map(|&file_source| {
+            let nodes = parse(db, file_source);
+
+            target
+                .find_locs(&nodes, include_declaration, &defined)
+                .into_iter()
+                .map(move |loc| Reference { file_source, loc })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Method {
+        name: String,
+
+        /// `None` when looking up from a call site, since we can't know which method is being
+        /// called.
+        owner: Option<MethodOwner>,
+    },
+    Constant(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct MethodOwner {
+    namespace: Vec<String>,
+    singleton: bool,
+}
+
+impl MethodOwner {
+    fn matches(&self, namespace: &[String], singleton: bool) -> bool {
+        self.singleton == singleton && self.namespace == namespace
+    }
+}
+
+impl Target {
+    fn at_offset(
+        nodes: &[Node],
+        by_id: &HashMap<usize, &Node>,
+        offset: usize,
+        defined: &BTreeSet<Vec<String>>,
+    ) -> Option<Self> {
+        nodes.iter().find_map(|node| match node.properties() {
+            Properties::Def(def) if def.name_l.contains(offset) => Some(Self::Method {
+                name: def.name.clone(),
+                owner: Some(MethodOwner {
+                    namespace: method_owner(nodes, by_id, node),
+                    singleton: in_singleton_class(nodes, node),
+                }),
+            }),
+            Properties::Defs(defs) if defs.name_l.contains(offset) => Some(Self::Method {
+                name: defs.name.clone(),
+                owner: Some(MethodOwner {
+                    namespace: method_owner(nodes, by_id, node),
+                    singleton: true,
+                }),
+            }),
+            Properties::Send(send) if send.selector_l.map_or(false, |l| l.contains(offset)) => {
+                Some(Self::Method {
+                    name: send.method_name.clone(),
+                    owner: None,
+                })
+            }
+            Properties::CSend(csend) if csend.selector_l.map_or(false, |l| l.contains(offset)) => {
+                Some(Self::Method {
+                    name: csend.method_name.clone(),
+                    owner: None,
+                })
+            }
+            Properties::Const(const_) if const_.name_l.contains(offset) => {
+                let path = ConstPath::new(by_id, &const_.name, const_.scope_id)?;
+                Some(Self::Constant(
+                    path.resolve(&lexical_namespace(nodes, by_id, node), defined),
+                ))
+            }
+            Properties::Casgn(casgn) if casgn.name_l.contains(offset) => {
+                definition_path(nodes, by_id, node).map(Self::Constant)
+            }
+            _ => None,
+        })
+    }
+
+    fn find_locs(
+        &self,
+        nodes: &[Node],
+        include_declaration: bool,
+        defined: &BTreeSet<Vec<String>>,
+    ) -> Vec<Loc> {
+        let by_id = index_by_id(nodes);
+
+        match self {
+            Self::Method { name, owner } => nodes
+                .iter()
+                .filter_map(|node| {
+                    method_loc(
+                        nodes,
+                        &by_id,
+                        node,
+                        name,
+                        owner.as_ref(),
+                        include_declaration,
+                        defined,
+                    )
+                })
+                .collect(),
+            Self::Constant(path) => {
+                // The `Const`s that name classes and modules are declarations, not references.
+                let name_ids: HashSet<usize> = nodes
+                    .iter()
+                    .filter_map(|node| match node.properties() {
+                        Properties::Class(class) => Some(class.name_id),
+                        Properties::Module(module) => Some(module.name_id),
+                        _ => None,
+                    })
+                    .collect();
+
+                nodes
+                    .iter()
+                    .filter_map(|node| match node.properties() {
+                        // Resolving is the costly part, so only do it when the name matches.
+                        Properties::Const(const_)
+                            if !name_ids.contains(&node.id())
+                                && path.last() == Some(&const_.name) =>
+                        {
+                            let const_path = ConstPath::new(&by_id, &const_.name, const_.scope_id)?;
+                            let resolved = const_path
+                                .resolve(&lexical_namespace(nodes, &by_id, node), defined);
+
+                            (&resolved == path).then_some(const_.name_l)
+                        }
+                        Properties::Class(_) | Properties::Module(_) | Properties::Casgn(_)
+                            if include_declaration =>
+                        {
+                            (&definition_path(nodes, &by_id, node)? == path)
+                                .then(|| declaration_name_l(&by_id, node))
+                                .flatten()
+                        }
+                        _ => None,
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+fn method_loc(
+    nodes: &[Node],
+    by_id: &HashMap<usize, &Node>,
+    node: &Node,
+    name: &str,
+    owner: Option<&MethodOwner>,
+    include_declaration: bool,
+    defined: &BTreeSet<Vec<String>>,
+) -> Option<Loc> {
+    match node.properties() {
+        Properties::Def(def) if include_declaration && def.name == name => owner
+            .map_or(true, |o| {
+                o.matches(
+                    &method_owner(nodes, by_id, node),
+                    in_singleton_class(nodes, node),
+                )
+            })
+            .then_some(def.name_l),
+        Properties::Defs(defs) if include_declaration && defs.name == name => owner
+            .map_or(true, |o| o.matches(&method_owner(nodes, by_id, node), true))
+            .then_some(defs.name_l),
+        Properties::Send(send) if send.method_name == name => {
+            let Some(owner) = owner else {
+                return send.selector_l;
+            };
+
+            let receiver = send.recv_id.and_then(|id| by_id.get(&id));
+
+            let matches = match receiver.map(|recv| recv.properties()) {
+                None | Some(Properties::Self_(_)) => {
+                    owner.singleton == in_singleton_context(nodes, node)
+                }
+                Some(Properties::Const(const_)) => {
+                    owner.singleton
+                        && ConstPath::new(by_id, &const_.name, const_.scope_id).map_or(
+                            true,
+                            |path| {
+                                path.resolve(&lexical_namespace(nodes, by_id, node), defined)
+                                    == owner.namespace
+                            },
+                        )
+                }
+                // Some arbitrary expression; we'd need type info to know more.
+                Some(_) => true,
+            };
+
+            matches.then_some(send.selector_l).flatten()
+        }
+        Properties::CSend(csend) if csend.method_name == name => csend.selector_l,
+        Properties::Super(super_) if enclosing_method_name(node) == Some(name) => {
+            // `super` calls the method of the same name, but never the one it's called from.
+            owner
+                .map_or(true, |o| o.namespace != method_owner(nodes, by_id, node))
+                .then_some(super_.keyword_l)
+        }
+        Properties::ZSuper(_) if enclosing_method_name(node) == Some(name) => owner
+            .map_or(true, |o| o.namespace != method_owner(nodes, by_id, node))
+            .then_some(*node.expression_l()),
+        _ => None,
+    }
+}
+
+/// Location of the name of the constant that's declared by a `Class`, `Module` or `Casgn`.
+///
+fn declaration_name_l(by_id: &HashMap<usize, &Node>, node: &Node) -> Option<Loc> {
+    let name_id = match node.properties() {
+        Properties::Casgn(casgn) => return Some(casgn.name_l),
+        Properties::Class(class) => class.name_id,
+        Properties::Module(module) => module.name_id,
+        _ => return None,
+    };
+
+    match by_id.get(&name_id)?.properties() {
+        Properties::Const(const_) => Some(const_.name_l),
+        _ => None,
+    }
+}
+
+fn enclosing_method_name(node: &Node) -> Option<&str> {
+    match node.scope_gate().last()? {
+        scope_gate::Node::Def(name) | scope_gate::Node::Defs(name) => Some(name),
+        scope_gate::Node::Class(_) | scope_gate::Node::Module(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ropey::Rope;
+
+    use super::*;
+    use crate::db::Database;
+
+    const FOO: &str = r#"class Foo
+  def bar
+  end
+
+  def baz
+    bar
+    self.bar
+  end
+end"#;
+
+    const CALLER: &str = r#"Foo.new.bar
+Foo.bar"#;
+
+    fn setup(db: &Database) -> (Workspace, FileSource, FileSource) {
+        let foo = FileSource::new(db, PathBuf::from("/tmp/foo.rb"), Rope::from_str(FOO));
+        let caller = FileSource::new(db, PathBuf::from("/tmp/caller.rb"), Rope::from_str(CALLER));
+        let workspace = Workspace::new(db, vec![foo, caller]);
+
+        (workspace, foo, caller)
+    }
+
+    fn texts(db: &Database, references: &[Reference]) -> Vec<String> {
+        references
+            .iter()
+            .map(|r| {
+                r.file_source()
+                    .code(db)
+                    .byte_slice(r.loc().begin()..r.loc().end())
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn instance_method_test() {
+        let db = Database::default();
+        let (workspace, foo, caller) = setup(&db);
+        let offset = FOO.find("def bar").unwrap() + 4;
+
+        let query = ReferencesQuery::new(&db, workspace, foo, offset, true);
+        let refs = references(&db, query);
+
+        // Declaration, `bar`, `self.bar` and `Foo.new.bar`, but not `Foo.bar`.
+        assert_eq!(4, refs.len());
+        assert_eq!(vec!["bar"; 4], texts(&db, &refs));
+        assert_eq!(3, refs.iter().filter(|r| r.file_source() == foo).count());
+        assert_eq!(1, refs.iter().filter(|r| r.file_source() == caller).count());
+
+        let query = ReferencesQuery::new(&db, workspace, foo, offset, false);
+        assert_eq!(3, references(&db, query).len());
+    }
+
+    #[test]
+    fn compact_class_test() {
+        let db = Database::default();
+        let code = r#"module Admin; end
+
+class Admin::UsersController
+  def self.policy
+  end
+end
+
+Admin::UsersController.policy"#;
+        let file_source =
+            FileSource::new(&db, PathBuf::from("/tmp/users.rb"), Rope::from_str(code));
+        let workspace = Workspace::new(&db, vec![file_source]);
+        let offset = code.find("policy").unwrap();
+
+        let query = ReferencesQuery::new(&db, workspace, file_source, offset, true);
+        let refs = references(&db, query);
+
+        assert_eq!(vec!["policy"; 2], texts(&db, &refs));
+    }
+
+    #[test]
+    fn compact_class_constant_test() {
+        let db = Database::default();
+        let code = r#"class Admin::UsersController
+  PER_PAGE = 1
+
+  def index
+    PER_PAGE
+  end
+end
+
+Admin::UsersController::PER_PAGE"#;
+        let file_source =
+            FileSource::new(&db, PathBuf::from("/tmp/users.rb"), Rope::from_str(code));
+        let workspace = Workspace::new(&db, vec![file_source]);
+        let offset = code.find("PER_PAGE").unwrap();
+
+        let query = ReferencesQuery::new(&db, workspace, file_source, offset, true);
+        let refs = references(&db, query);
+
+        assert_eq!(vec!["PER_PAGE"; 3], texts(&db, &refs));
+    }
+
+    #[test]
+    fn constant_test() {
+        let db = Database::default();
+        let (workspace, _foo, caller) = setup(&db);
+
+        let query = ReferencesQuery::new(&db, workspace, caller, 0, true);
+        let refs = references(&db, query);
+        assert_eq!(3, refs.len());
+        assert_eq!(vec!["Foo"; 3], texts(&db, &refs));
+
+        let query = ReferencesQuery::new(&db, workspace, caller, 0, false);
+        assert_eq!(2, references(&db, query).len());
+    }
+}
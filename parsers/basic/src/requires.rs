@@ -0,0 +1,442 @@
+//! Resolves `require`, `require_relative`, `load` and `autoload` calls to the files they load,
+//! which makes for a file dependency graph of the workspace. Only files that are part of the
+//! `Workspace` can be resolved; calls with a non-literal path (ex. `require "#{dir}/foo"`) are
+//! skipped.
+//!
+//! `require_relative` paths are relative to the requiring file's directory; `require`, `load` and
+//! `autoload` paths are looked up in each directory of the `LoadPath`, in order (like Ruby's
+//! `$LOAD_PATH`), unless they're absolute.
+//!
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+use crate::{
+    node::{index_by_id, Contains, Loc},
+    parser::{parse, FileSource},
+    properties::Properties,
+    references::Reference,
+    unused_variables::SOURCE,
+    workspace::Workspace,
+    Node,
+};
+
+/// The directories that `require` looks in, ex. the project's `lib/` and the `lib/` of gems that
+/// are vendored or checked out locally.
+///
+#[salsa::input]
+pub struct LoadPath {
+    #[return_ref]
+    pub directories: Vec<PathBuf>,
+}
+
+#[salsa::input]
+pub struct RequiresQuery {
+    pub workspace: Workspace,
+    pub load_path: LoadPath,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RequireKind {
+    Require,
+    RequireRelative,
+    Load,
+    Autoload,
+}
+
+impl RequireKind {
+    fn new(method_name: &str) -> Option<Self> {
+        match method_name {
+            "require" => Some(Self::Require),
+            "require_relative" => Some(Self::RequireRelative),
+            "load" => Some(Self::Load),
+            "autoload" => Some(Self::Autoload),
+            _ => None,
+        }
+    }
+
+    pub fn method_name(&self) -> &'static str {
+        match self {
+            Self::Require => "require",
+            Self::RequireRelative => "require_relative",
+            Self::Load => "load",
+            Self::Autoload => "autoload",
+        }
+    }
+}
+
+/// A single call that loads a file, ex. `require "app/models/user"`.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Require {
+    pub(crate) kind: RequireKind,
+
+    /// The path, as written.
+    pub(crate) path: String,
+
+    /// Location of the string literal.
+    pub(crate) path_l: Loc,
+
+    /// For `autoload :Config, "..."`, `Config`.
+    pub(crate) constant: Option<String>,
+
+    /// The file that's loaded; `None` if it isn't in the workspace.
+    pub(crate) target: Option<FileSource>,
+}
+
+impl Require {
+    pub fn kind(&self) -> RequireKind {
+        self.kind
+    }
+
+    pub fn path(&self) -> &str {
+        self.path.as_ref()
+    }
+
+    pub fn path_l(&self) -> Loc {
+        self.path_l
+    }
+
+    pub fn constant(&self) -> Option<&str> {
+        self.constant.as_deref()
+    }
+
+    pub fn target(&self) -> Option<FileSource> {
+        self.target
+    }
+}
+
+/// Every file in the workspace, with what it loads.
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RequireGraph {
+    pub(crate) files: Vec<(FileSource, Vec<Require>)>,
+}
+
+impl RequireGraph {
+    /// The calls in `file_source` that load other files.
+    ///
+    pub fn requires(&self, file_source: FileSource) -> &[Require] {
+        self.files
+            .iter()
+            .find(|(other, _)| *other == file_source)
+            .map(|(_, requires)| requires.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The files that `file_source` loads directly.
+    ///
+    pub fn dependencies(&self, file_source: FileSource) -> Vec<FileSource> {
+        let mut dependencies: Vec<FileSource> = Vec::new();
+
+        for target in self
+            .requires(file_source)
+            .iter()
+            .filter_map(Require::target)
+        {
+            if !dependencies.contains(&target) {
+                dependencies.push(target);
+            }
+        }
+
+        dependencies
+    }
+
+    /// The files that load `file_source` directly.
+    ///
+    pub fn dependents(&self, file_source: FileSource) -> Vec<FileSource> {
+        self.files
+            .iter()
+            .filter(|(_, requires)| {
+                requires
+                    .iter()
+                    .any(|require| require.target == Some(file_source))
+            })
+            .map(|(other, _)| *other)
+            .collect()
+    }
+
+    /// A diagnostic for each call in `file_source` whose file can't be found. Those from
+    /// `require_relative` are warnings, since they should always point into the project; the
+    /// others are informational, since the file may well come from a gem or the standard library.
+    ///
+    pub fn diagnostics(&self, db: &dyn crate::db::Db, file_source: FileSource) -> Vec<Diagnostic> {
+        let code = file_source.code(db);
+
+        self.requires(file_source)
+            .iter()
+            .filter(|require| require.target.is_none())
+            .map(|require| {
+                let severity = match require.kind {
+                    RequireKind::RequireRelative => DiagnosticSeverity::WARNING,
+                    _ => DiagnosticSeverity::INFORMATION,
+                };
+
+                Diagnostic {
+                    range: require.path_l.to_lsp_range(code),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("unresolved_require".to_string())),
+                    source: Some(SOURCE.to_string()),
+                    message: format!(
+                        "Cannot resolve `{} {:?}`.",
+                        require.kind.method_name(),
+                        require.path
+                    ),
+                    ..Default::default()
+                }
+            })
+            .collect()
+    }
+}
+
+#[salsa::tracked]
+pub fn require_graph(db: &dyn crate::db::Db, query: RequiresQuery) -> RequireGraph {
+    let workspace = query.workspace(db);
+    let load_path = query.load_path(db).directories(db);
+
+    let files_by_path: HashMap<PathBuf, FileSource> = workspace
+        .file_sources(db)
+        .iter()
+        .map(|&file_source| (normalize(file_source.file_uri(db)), file_source))
+        .collect();
+
+    let files = workspace
+        .file_sources(db)
+        .iter()
+        .map(|&file_source| {
+            let nodes = parse(db, file_source);
+            let by_id = index_by_id(&nodes);
+
+            let requires = nodes
+                .iter()
+                .filter_map(|node| unresolved_require(&by_id, node))
+                .map(|mut require| {
+                    require.target = candidates(&require, file_source.file_uri(db), load_path)
+                        .into_iter()
+                        .find_map(|candidate| files_by_path.get(&candidate).copied());
+
+                    require
+                })
+                .collect();
+
+            (file_source, requires)
+        })
+        .collect();
+
+    RequireGraph { files }
+}
+
+#[salsa::input]
+pub struct RequireDefinitionQuery {
+    pub requires: RequiresQuery,
+    pub file_source: FileSource,
+    pub offset: usize,
+}
+
+/// Go-to-definition on the path of a `require` (or similar): the start of the file it loads.
+///
+#[salsa::tracked]
+pub fn require_definition(
+    db: &dyn crate::db::Db,
+    query: RequireDefinitionQuery,
+) -> Option<Reference> {
+    let offset = query.offset(db);
+    let graph = require_graph(db, query.requires(db));
+
+    let target = graph
+        .requires(query.file_source(db))
+        .iter()
+        .find(|require| require.path_l.contains(offset))?
+        .target?;
+
+    Some(Reference {
+        file_source: target,
+        loc: Loc { begin: 0, end: 0 },
+    })
+}
+
+/// The `Require` that `node` is, if it is one; its `target` is left for the caller to fill in.
+///
+fn unresolved_require(by_id: &HashMap<usize, &Node>, node: &Node) -> Option<Require> {
+    let Properties::Send(send) = node.properties() else {
+        return None;
+    };
+
+    if send.recv_id.is_some() {
+        return None;
+    }
+
+    let kind = RequireKind::new(&send.method_name)?;
+
+    let (constant, path_id) = match (kind, send.arg_ids.as_slice()) {
+        (RequireKind::Autoload, [constant_id, path_id]) => {
+            let constant = match by_id.get(constant_id)?.properties() {
+                Properties::Sym(sym) => sym.name.clone(),
+                Properties::Str(str_) => String::from_utf8_lossy(&str_.value).to_string(),
+                _ => return None,
+            };
+
+            (Some(constant), path_id)
+        }
+        (RequireKind::Autoload, _) => return None,
+        // `load` takes an optional `wrap` argument.
+        (RequireKind::Load, [path_id, ..]) => (None, path_id),
+        (_, [path_id]) => (None, path_id),
+        _ => return None,
+    };
+
+    let path_node = by_id.get(path_id)?;
+
+    let Properties::Str(str_) = path_node.properties() else {
+        return None;
+    };
+
+    Some(Require {
+        kind,
+        path: String::from_utf8_lossy(&str_.value).to_string(),
+        path_l: *path_node.expression_l(),
+        constant,
+        target: None,
+    })
+}
+
+/// The paths `require` could refer to, in the order Ruby would try them.
+///
+fn candidates(require: &Require, requiring_file: &Path, load_path: &[PathBuf]) -> Vec<PathBuf> {
+    let path = Path::new(&require.path);
+
+    let bases: Vec<PathBuf> = if path.is_absolute() {
+        vec![PathBuf::new()]
+    } else if require.kind == RequireKind::RequireRelative {
+        requiring_file
+            .parent()
+            .map(Path::to_path_buf)
+            .into_iter()
+            .collect()
+    } else {
+        load_path.to_vec()
+    };
+
+    bases
+        .iter()
+        .flat_map(|base| {
+            let joined = base.join(path);
+
+            // `load` needs the extension; the others add `.rb` when it's missing.
+            match (require.kind, joined.extension()) {
+                (RequireKind::Load, _) | (_, Some(_)) => vec![joined],
+                _ => vec![joined.with_extension("rb")],
+            }
+        })
+        .map(|candidate| normalize(&candidate))
+        .collect()
+}
+
+/// Resolves `.` and `..` without touching the file system.
+///
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use ropey::Rope;
+
+    use super::*;
+    use crate::db::Database;
+
+    const APP: &str = r##"require "app/models/user"
+require_relative "app/helpers"
+require_relative "./missing"
+require "json"
+autoload :Config, "app/config"
+load "tasks.rb"
+require "#{ROOT}/dynamic"
+"##;
+
+    const HELPERS: &str = r#"require_relative "models/../models/user.rb"
+"#;
+
+    #[test]
+    fn require_graph_test() {
+        let db = Database::default();
+
+        let file = |path: &str, code: &str| {
+            FileSource::new(&db, PathBuf::from(path), Rope::from_str(code))
+        };
+
+        let app = file("/project/lib/app.rb", APP);
+        let user = file("/project/lib/app/models/user.rb", "class User; end\n");
+        let helpers = file("/project/lib/app/helpers.rb", HELPERS);
+        let config = file("/project/lib/app/config.rb", "class Config; end\n");
+
+        let workspace = Workspace::new(&db, vec![app, user, helpers, config]);
+        let load_path = LoadPath::new(&db, vec![PathBuf::from("/project/lib")]);
+        let query = RequiresQuery::new(&db, workspace, load_path);
+
+        let graph = require_graph(&db, query);
+
+        let requires: Vec<(&str, bool)> = graph
+            .requires(app)
+            .iter()
+            .map(|require| (require.path(), require.target().is_some()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("app/models/user", true),
+                ("app/helpers", true),
+                ("./missing", false),
+                ("json", false),
+                ("app/config", true),
+                ("tasks.rb", false),
+            ],
+            requires
+        );
+        assert_eq!(Some("Config"), graph.requires(app)[4].constant());
+
+        assert_eq!(vec![user, helpers, config], graph.dependencies(app));
+        assert_eq!(vec![app, helpers], graph.dependents(user));
+
+        let diagnostics: Vec<(u32, Option<DiagnosticSeverity>)> = graph
+            .diagnostics(&db, app)
+            .iter()
+            .map(|diagnostic| (diagnostic.range.start.line, diagnostic.severity))
+            .collect();
+        assert_eq!(
+            vec![
+                (2, Some(DiagnosticSeverity::WARNING)),
+                (3, Some(DiagnosticSeverity::INFORMATION)),
+                (5, Some(DiagnosticSeverity::INFORMATION)),
+            ],
+            diagnostics
+        );
+
+        let offset = APP.find("app/helpers").unwrap();
+        let definition =
+            require_definition(&db, RequireDefinitionQuery::new(&db, query, app, offset));
+        assert_eq!(
+            Some(helpers),
+            definition.map(|reference| reference.file_source())
+        );
+
+        let offset = APP.find("json").unwrap();
+        let definition =
+            require_definition(&db, RequireDefinitionQuery::new(&db, query, app, offset));
+        assert_eq!(None, definition);
+    }
+}
@@ -47,6 +47,31 @@ impl ScopeGate {
     pub fn leaf(&self) -> &Node {
         self.inner.last().unwrap()
     }
+
+    /// The names of the classes and modules that make up the Ruby namespace of this scope gate,
+    /// outermost first. Method scope gates don't contribute to the namespace.
+    ///
+    /// ```
+    /// use ruby_analyzer_basic_parser::scope_gate::{ScopeGate, Node};
+    ///
+    /// let scope_gate = ScopeGate::new(vec![
+    ///     Node::Module("Foo".to_string()),
+    ///     Node::Class("Bar".to_string()),
+    ///     Node::Def("baz".to_string()),
+    /// ]);
+    ///
+    /// assert_eq!(vec!["Foo", "Bar"], scope_gate.namespace());
+    /// ```
+    ///
+    pub fn namespace(&self) -> Vec<&str> {
+        self.inner
+            .iter()
+            .filter_map(|node| match node {
+                Node::Class(name) | Node::Module(name) => Some(name.as_str()),
+                Node::Def(_) | Node::Defs(_) => None,
+            })
+            .collect()
+    }
 }
 
 impl Deref for ScopeGate {
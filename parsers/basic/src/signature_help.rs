@@ -0,0 +1,622 @@
+//! Signature help: while typing a call's arguments, the called method's parameters, with the one
+//! being typed highlighted. The method is resolved from the receiver's inferred type (see
+//! `crate::types`), or for `super(...)`, from the next ancestor of the enclosing method's class.
+//!
+use std::collections::{BTreeMap, HashMap};
+
+use lsp_types::{
+    Documentation, MarkupContent, MarkupKind, ParameterInformation, ParameterLabel, SignatureHelp,
+    SignatureInformation,
+};
+use ropey::Rope;
+
+use crate::{
+    ancestors::Ancestor,
+    constants::method_owner,
+    hover::parameter_labels,
+    node::{in_singleton_class, index_by_id, Contains, Loc},
+    nodes::Visibility,
+    parser::{parse, FileSource},
+    properties::Properties,
+    sorbet::signatures,
+    types::{Inference, Type, TypeEnvironment},
+    workspace::Workspace,
+    yard::yard_docs,
+    Node,
+};
+
+#[salsa::input]
+pub struct SignatureHelpQuery {
+    pub environment: TypeEnvironment,
+    pub file_source: FileSource,
+    pub offset: usize,
+}
+
+/// Every `Def`/`Defs` in the workspace, keyed by owner (the class or module path, and whether
+/// it's a singleton method) and name. When a method is defined more than once, the first wins.
+///
+#[salsa::tracked]
+pub fn method_definitions(
+    db: &dyn crate::db::Db,
+    workspace: Workspace,
+) -> BTreeMap<(Vec<String>, bool, String), (FileSource, usize)> {
+    let mut definitions = BTreeMap::new();
+
+    for &file_source in workspace.file_sources(db) {
+        let nodes = parse(db, file_source);
+        let by_id = index_by_id(&nodes);
+
+        for node in nodes.iter() {
+            let (name, visibility, singleton) = match node.properties() {
+                Properties::Def(def) => {
+                    (&def.name, def.visibility, in_singleton_class(&nodes, node))
+                }
+                Properties::Defs(defs) => (&defs.name, defs.visibility, true),
+                _ => continue,
+            };
+
+            let owner = method_owner(&nodes, &by_id, node);
+
+            let mut add = |singleton: bool| {
+                definitions
+                    .entry((owner.clone(), singleton, name.clone()))
+                    .or_insert((file_source, node.id()));
+            };
+
+            add(singleton);
+
+            if visibility == Visibility::ModuleFunction {
+                add(true);
+            }
+        }
+    }
+
+    definitions
+}
+
+/// A call whose arguments can be typed: a `Send`, `CSend` or `Super`.
+///
+pub(crate) struct Call<'a> {
+    pub(crate) node: &'a Node,
+    pub(crate) method_name: Option<&'a str>,
+    pub(crate) recv_id: Option<usize>,
+    pub(crate) arg_ids: &'a [usize],
+
+    /// Where the arguments are: between the parentheses, or (for a call without them, ex.
+    /// `puts a, b`) from after the method name to the end of the call.
+    pub(crate) arguments_l: Loc,
+}
+
+impl<'a> Call<'a> {
+    fn new(node: &'a Node) -> Option<Self> {
+        let (method_name, recv_id, arg_ids, selector_l, begin_l, end_l) = match node.properties() {
+            Properties::Send(send) => (
+                Some(send.method_name.as_str()),
+                send.recv_id,
+                &send.arg_ids,
+                send.selector_l,
+                send.begin_l,
+                send.end_l,
+            ),
+            Properties::CSend(csend) => (
+                Some(csend.method_name.as_str()),
+                Some(csend.recv_id),
+                &csend.arg_ids,
+                csend.selector_l,
+                csend.begin_l,
+                csend.end_l,
+            ),
+            Properties::Super(super_) => (
+                None,
+                None,
+                &super_.arg_ids,
+                Some(super_.keyword_l),
+                super_.begin_l,
+                super_.end_l,
+            ),
+            _ => return None,
+        };
+
+        let arguments_l = match (begin_l, end_l) {
+            (Some(begin_l), Some(end_l)) => Loc {
+                begin: begin_l.end,
+                end: end_l.begin,
+            },
+            (Some(begin_l), None) => Loc {
+                begin: begin_l.end,
+                end: node.expression_l().end,
+            },
+            // Without parentheses, only once there's an argument.
+            _ if !arg_ids.is_empty() => Loc {
+                begin: selector_l?.end + 1,
+                end: node.expression_l().end,
+            },
+            _ => return None,
+        };
+
+        Some(Self {
+            node,
+            method_name,
+            recv_id,
+            arg_ids,
+            arguments_l,
+        })
+    }
+}
+
+/// The innermost call whose arguments `offset` is in.
+///
+pub(crate) fn call_at(nodes: &[Node], offset: usize) -> Option<Call> {
+    nodes
+        .iter()
+        .filter_map(Call::new)
+        .filter(|call| call.arguments_l.begin <= offset && offset <= call.arguments_l.end)
+        .max_by_key(|call| call.arguments_l.begin)
+}
+
+/// The definition of the method that `call` calls: the `Def`/`Defs` node's file and ID.
+///
+pub(crate) fn resolve_call(
+    db: &dyn crate::db::Db,
+    workspace: Workspace,
+    inference: &Inference,
+    nodes: &[Node],
+    call: &Call,
+) -> Option<(FileSource, usize)> {
+    let by_id = index_by_id(nodes);
+
+    match call.method_name {
+        Some(method_name) => {
+            let receiver = match call.recv_id {
+                Some(id) => inference.infer(by_id.get(&id)?)?,
+                None => inference.self_type(call.node),
+            };
+
+            resolve_method(db, workspace, inference, &receiver, method_name)
+        }
+        // `super`: the same method, further up the enclosing method's ancestors.
+        None => {
+            let method = nodes
+                .iter()
+                .filter(|node| {
+                    matches!(node.properties(), Properties::Def(_) | Properties::Defs(_))
+                })
+                .filter(|node| node.expression_l().contains(call.node.expression_l()))
+                .max_by_key(|node| node.expression_l().begin)?;
+
+            let name = match method.properties() {
+                Properties::Def(def) => &def.name,
+                Properties::Defs(defs) => &defs.name,
+                _ => return None,
+            };
+
+            let owner = method_owner(nodes, &by_id, method);
+            let owners = inference.method_owners(&inference.self_type(call.node));
+
+            let after = owners
+                .iter()
+                .position(|ancestor| match ancestor {
+                    Ancestor::Instance(path) | Ancestor::Singleton(path) => *path == owner,
+                })
+                .map_or(0, |index| index + 1);
+
+            lookup_method(&method_definitions(db, workspace), &owners[after..], name)
+        }
+    }
+}
+
+/// The definition of the method `name` when it's called on `receiver`: the `Def`/`Defs` node's
+/// file and ID.
+///
+pub(crate) fn resolve_method(
+    db: &dyn crate::db::Db,
+    workspace: Workspace,
+    inference: &Inference,
+    receiver: &Type,
+    name: &str,
+) -> Option<(FileSource, usize)> {
+    let definitions = method_definitions(db, workspace);
+
+    receiver
+        .members()
+        .iter()
+        .find_map(|member| lookup_method(&definitions, &inference.method_owners(member), name))
+}
+
+/// The first of `owners` that defines `name`.
+///
+fn lookup_method(
+    definitions: &BTreeMap<(Vec<String>, bool, String), (FileSource, usize)>,
+    owners: &[Ancestor],
+    name: &str,
+) -> Option<(FileSource, usize)> {
+    owners.iter().find_map(|owner| {
+        let (path, singleton) = match owner {
+            Ancestor::Instance(path) => (path, false),
+            Ancestor::Singleton(path) => (path, true),
+        };
+
+        definitions
+            .get(&(path.clone(), singleton, name.to_string()))
+            .copied()
+    })
+}
+
+/// What kind of argument a parameter takes, for matching arguments to parameters.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ParameterKind {
+    /// `a`, `b = 1` or a destructured `(a, b)`.
+    Positional,
+
+    /// `*rest`
+    Rest,
+
+    /// `c:` or `d: 2`
+    Keyword(String),
+
+    /// `**opts`
+    KeywordRest,
+
+    /// `&block`
+    Block,
+
+    /// `...`, which takes everything.
+    Forward,
+
+    /// `**nil`
+    NoKeywords,
+}
+
+/// The kinds of the parameters of the `Def`/`Defs` node `method`, in order.
+///
+pub(crate) fn parameter_kinds(by_id: &HashMap<usize, &Node>, method: &Node) -> Vec<ParameterKind> {
+    let args_id = match method.properties() {
+        Properties::Def(def) => def.args_id,
+        Properties::Defs(defs) => defs.args_id,
+        _ => None,
+    };
+
+    let Some(Properties::Args(args)) = args_id
+        .and_then(|id| by_id.get(&id))
+        .map(|node| node.properties())
+    else {
+        return Vec::new();
+    };
+
+    args.arg_ids
+        .iter()
+        .filter_map(|id| by_id.get(id))
+        .map(|arg| match arg.properties() {
+            Properties::Restarg(_) => ParameterKind::Rest,
+            Properties::Kwarg(kwarg) => ParameterKind::Keyword(kwarg.name.clone()),
+            Properties::Kwoptarg(kwoptarg) => ParameterKind::Keyword(kwoptarg.name.clone()),
+            Properties::Kwrestarg(_) => ParameterKind::KeywordRest,
+            Properties::Kwnilarg(_) => ParameterKind::NoKeywords,
+            Properties::Blockarg(_) => ParameterKind::Block,
+            Properties::ForwardArg(_) => ParameterKind::Forward,
+            _ => ParameterKind::Positional,
+        })
+        .collect()
+}
+
+/// Which parameter the argument at `offset` goes to. Positional arguments are counted by the
+/// commas before `offset`; within keyword arguments, the keyword being typed picks the parameter
+/// (or the first keyword not yet given, after a comma).
+///
+fn active_parameter(
+    by_id: &HashMap<usize, &Node>,
+    code: &Rope,
+    call: &Call,
+    kinds: &[ParameterKind],
+    offset: usize,
+) -> Option<usize> {
+    let forward = kinds
+        .iter()
+        .position(|kind| *kind == ParameterKind::Forward);
+    let arguments: Vec<&Node> = call.arg_ids.iter().filter_map(|id| by_id.get(id)).collect();
+
+    // The argument being typed: the one `offset` is in, or none if it's after a comma.
+    let current = arguments.iter().find(|arg| {
+        arg.expression_l().begin <= offset
+            && offset <= arg.expression_l().end
+            && !has_comma(code, arg.expression_l().end, offset)
+    });
+
+    match current.map(|arg| arg.properties()) {
+        Some(Properties::Kwargs(kwargs)) => {
+            let pairs: Vec<&Node> = kwargs
+                .pair_ids
+                .iter()
+                .filter_map(|id| by_id.get(id))
+                .collect();
+
+            let keyword = pairs
+                .iter()
+                .find(|pair| {
+                    pair.expression_l().begin <= offset
+                        && offset <= pair.expression_l().end
+                        && !has_comma(code, pair.expression_l().end, offset)
+                })
+                .and_then(|pair| pair_keyword(by_id, pair));
+
+            let keyword_parameter = match keyword {
+                Some(keyword) => kinds
+                    .iter()
+                    .position(|kind| *kind == ParameterKind::Keyword(keyword.to_string())),
+                // After a comma: the first keyword that hasn't been given yet.
+                None => {
+                    let given: Vec<&str> = pairs
+                        .iter()
+                        .filter_map(|pair| pair_keyword(by_id, pair))
+                        .collect();
+
+                    kinds.iter().position(|kind| match kind {
+                        ParameterKind::Keyword(name) => !given.contains(&name.as_str()),
+                        _ => false,
+                    })
+                }
+            };
+
+            keyword_parameter
+                .or_else(|| {
+                    kinds
+                        .iter()
+                        .position(|kind| *kind == ParameterKind::KeywordRest)
+                })
+                .or(forward)
+        }
+        Some(Properties::BlockPass(_)) => kinds
+            .iter()
+            .position(|kind| *kind == ParameterKind::Block)
+            .or(forward),
+        _ => {
+            let index = arguments
+                .iter()
+                .filter(|arg| arg.expression_l().end <= offset)
+                .filter(|arg| has_comma(code, arg.expression_l().end, offset))
+                .count();
+
+            let positional: Vec<usize> = kinds
+                .iter()
+                .enumerate()
+                .filter(|(_, kind)| matches!(kind, ParameterKind::Positional | ParameterKind::Rest))
+                .map(|(index, _)| index)
+                .collect();
+
+            // Arguments past the required and optional ones go to `*rest`.
+            positional
+                .get(index)
+                .copied()
+                .or_else(|| {
+                    positional
+                        .iter()
+                        .copied()
+                        .find(|&index| kinds[index] == ParameterKind::Rest)
+                })
+                .or(forward)
+        }
+    }
+}
+
+fn has_comma(code: &Rope, begin: usize, end: usize) -> bool {
+    begin < end && code.byte_slice(begin..end).chars().any(|char| char == ',')
+}
+
+/// `c` in `c: 1` (or `:c => 1`).
+///
+pub(crate) fn pair_keyword<'a>(by_id: &HashMap<usize, &'a Node>, pair: &Node) -> Option<&'a str> {
+    let Properties::Pair(pair) = pair.properties() else {
+        return None;
+    };
+
+    match by_id.get(&pair.key_id)?.properties() {
+        Properties::Sym(sym) => Some(sym.name.as_str()),
+        _ => None,
+    }
+}
+
+/// Signature help for the call whose arguments the query's `offset` is in.
+///
+#[salsa::tracked]
+pub fn signature_help(db: &dyn crate::db::Db, query: SignatureHelpQuery) -> Option<SignatureHelp> {
+    let environment = query.environment(db);
+    let file_source = query.file_source(db);
+    let offset = query.offset(db);
+    let code = file_source.code(db);
+
+    let nodes = parse(db, file_source);
+    let by_id = index_by_id(&nodes);
+    let inference = Inference::new(db, environment, file_source, &nodes);
+
+    let call = call_at(&nodes, offset)?;
+    let (definition_source, definition_id) =
+        resolve_call(db, environment.workspace(db), &inference, &nodes, &call)?;
+
+    let definition_code = definition_source.code(db);
+    let definition_nodes = parse(db, definition_source);
+    let definition_by_id = index_by_id(&definition_nodes);
+    let definition = definition_by_id.get(&definition_id)?;
+
+    let (name, args_id) = match definition.properties() {
+        Properties::Def(def) => (&def.name, def.args_id),
+        Properties::Defs(defs) => (&defs.name, defs.args_id),
+        _ => return None,
+    };
+
+    let labels = parameter_labels(&definition_by_id, definition_code, args_id).unwrap_or_default();
+    let kinds = parameter_kinds(&definition_by_id, definition);
+    let active_parameter =
+        active_parameter(&by_id, code, &call, &kinds, offset).map(|index| index as u32);
+
+    let yard = yard_docs(db, definition_source).remove(&definition_id);
+    let sig = signatures(db, definition_source).remove(&definition_id);
+
+    let parameters = labels
+        .iter()
+        .zip(&kinds)
+        .map(|(label, kind)| {
+            let name = match kind {
+                ParameterKind::Keyword(name) => name.as_str(),
+                _ => label
+                    .trim_start_matches(['*', '&'])
+                    .split([' ', '='])
+                    .next()
+                    .unwrap_or_default(),
+            };
+
+            let types = sig.as_ref().and_then(|sig| sig.param(name));
+            let text = yard.as_ref().and_then(|yard| {
+                yard.tags()
+                    .iter()
+                    .find(|tag| tag.name() == "param" && tag.key() == Some(name))
+                    .map(|tag| tag.text())
+                    .filter(|text| !text.is_empty())
+            });
+
+            let documentation = match (types, text) {
+                (Some(types), Some(text)) => Some(format!("`{types}` — {text}")),
+                (Some(types), None) => Some(format!("`{types}`")),
+                (None, Some(text)) => Some(text.to_string()),
+                (None, None) => None,
+            };
+
+            ParameterInformation {
+                label: ParameterLabel::Simple(label.clone()),
+                documentation: documentation.map(|value| {
+                    Documentation::MarkupContent(MarkupContent {
+                        kind: MarkupKind::Markdown,
+                        value,
+                    })
+                }),
+            }
+        })
+        .collect();
+
+    let documentation = [
+        sig.map(|sig| format!("```ruby\n{}\n```", sig.to_ruby())),
+        yard.map(|yard| yard.to_markdown()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>()
+    .join("\n\n");
+
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label: format!("{name}({})", labels.join(", ")),
+            documentation: (!documentation.is_empty()).then(|| {
+                Documentation::MarkupContent(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: documentation,
+                })
+            }),
+            parameters: Some(parameters),
+            active_parameter,
+        }],
+        active_signature: Some(0),
+        active_parameter,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeSet, path::PathBuf};
+
+    use super::*;
+    use crate::db::Database;
+
+    const CODE: &str = r#"class Mailer
+  # Sends a message.
+  #
+  # @param to [String] who gets it
+  def deliver(to, subject = nil, *attachments, cc:, bcc: [], **headers, &block); end
+
+  def forward(...); end
+end
+
+class DigestMailer < Mailer
+  def deliver(to)
+    super(to, "Digest", cc: nil)
+  end
+end
+
+mailer = Mailer.new
+mailer.deliver("ada@example.com", "Hi", "a.pdf", "b.pdf", bcc: [], cc: "bob", x: 1, &done)
+mailer.forward(1, a: 2)
+"#;
+
+    fn active(
+        db: &Database,
+        file_source: FileSource,
+        needle: &str,
+    ) -> Option<(String, Option<u32>)> {
+        let workspace = Workspace::new(db, vec![file_source]);
+        let environment =
+            TypeEnvironment::new(db, workspace, None, BTreeMap::new(), BTreeSet::new());
+        let offset = CODE.find(needle).unwrap() + needle.len();
+        let query = SignatureHelpQuery::new(db, environment, file_source, offset);
+
+        signature_help(db, query)
+            .map(|help| (help.signatures[0].label.clone(), help.active_parameter))
+    }
+
+    #[test]
+    fn signature_help_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let label =
+            "deliver(to, subject = nil, *attachments, cc:, bcc: [], **headers, &block)".to_string();
+        let expect = |index: u32| Some((label.clone(), Some(index)));
+
+        assert_eq!(expect(0), active(&db, file_source, "mailer.deliver(\"ada"));
+        assert_eq!(expect(1), active(&db, file_source, "\"H"));
+        assert_eq!(expect(2), active(&db, file_source, "\"a.p"));
+        assert_eq!(expect(2), active(&db, file_source, "\"b.p"));
+        assert_eq!(expect(4), active(&db, file_source, "bcc: ["));
+        assert_eq!(expect(3), active(&db, file_source, "cc: \"b"));
+        assert_eq!(expect(5), active(&db, file_source, "x: "));
+        assert_eq!(expect(6), active(&db, file_source, "&do"));
+
+        // `super` goes to `Mailer#deliver`, not `DigestMailer#deliver`.
+        assert_eq!(expect(3), active(&db, file_source, "cc: n"));
+
+        // `...` takes everything.
+        assert_eq!(
+            Some(("forward(...)".to_string(), Some(0))),
+            active(&db, file_source, "a: ")
+        );
+
+        // Not in a call's arguments.
+        assert_eq!(None, active(&db, file_source, "mailer = Mai"));
+    }
+
+    #[test]
+    fn documentation_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+        let workspace = Workspace::new(&db, vec![file_source]);
+        let environment =
+            TypeEnvironment::new(&db, workspace, None, BTreeMap::new(), BTreeSet::new());
+        let offset = CODE.find("\"ada").unwrap();
+        let query = SignatureHelpQuery::new(&db, environment, file_source, offset);
+
+        let help = signature_help(&db, query).unwrap();
+        let signature = &help.signatures[0];
+
+        assert_eq!(
+            Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: "who gets it".to_string(),
+            })),
+            signature.parameters.as_ref().unwrap()[0].documentation
+        );
+        match &signature.documentation {
+            Some(Documentation::MarkupContent(markup)) => {
+                assert!(markup.value.starts_with("Sends a message."))
+            }
+            documentation => panic!("unexpected documentation: {documentation:?}"),
+        }
+    }
+}
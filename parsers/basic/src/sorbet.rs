@@ -0,0 +1,508 @@
+//! Sorbet signatures. A `sig { params(x: Integer).returns(String) }` is a plain `Block` around a
+//! `sig` `Send`, written right before the `Def`/`Defs` it types; `signatures` pairs the two up and
+//! reads the parameter and return types out of the block's call chain.
+//!
+//! `.rbi` files (usually generated into `sorbet/rbi/` by tapioca) are ordinary Ruby, but they only
+//! declare signatures: their method bodies are empty and their classes are re-openings of ones
+//! defined elsewhere. So they're loaded into `RbiFiles` rather than the `Workspace`, keeping them
+//! out of symbols, references and diagnostics, and only `rbi_signatures` looks at them.
+//!
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use ropey::Rope;
+use salsa::Durability;
+
+use crate::{
+    constants::method_owner,
+    node::{in_singleton_class, index_by_id, Loc},
+    nodes::{self, Block},
+    parser::{parse, FileSource},
+    properties::Properties,
+    symbols::method_fully_qualified_name,
+    Node,
+};
+
+/// Where `load_rbi_files` looks for `.rbi` files, relative to the project root.
+///
+pub const RBI_DIRECTORY: &str = "sorbet/rbi";
+
+/// Words that may sit between a `sig` and its `def`, ex. `sig { void }` then `private def foo`.
+///
+const MODIFIERS: &[&str] = &[
+    "private",
+    "protected",
+    "public",
+    "module_function",
+    "private_class_method",
+    "public_class_method",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sig {
+    /// `(name, type)`, ex. `("x", "T.nilable(Integer)")`, in the order they're written.
+    pub(crate) params: Vec<(String, String)>,
+
+    /// `None` for `void`.
+    pub(crate) returns: Option<String>,
+
+    /// ex. `abstract`, `override` or `final` (from `sig(:final)`).
+    pub(crate) modifiers: Vec<String>,
+
+    /// `type_parameters(:U)`'s `U`.
+    pub(crate) type_parameters: Vec<String>,
+
+    /// The whole `sig { ... }`.
+    pub(crate) loc: Loc,
+}
+
+impl Sig {
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, type_)| type_.as_str())
+    }
+
+    pub fn returns(&self) -> Option<&str> {
+        self.returns.as_deref()
+    }
+
+    pub fn modifiers(&self) -> &[String] {
+        &self.modifiers
+    }
+
+    pub fn type_parameters(&self) -> &[String] {
+        &self.type_parameters
+    }
+
+    pub fn loc(&self) -> Loc {
+        self.loc
+    }
+
+    /// Rebuilds the signature in a canonical form, ex.
+    /// `sig { override.params(x: Integer).returns(String) }`.
+    ///
+    pub fn to_ruby(&self) -> String {
+        let mut chain: Vec<String> = self
+            .modifiers
+            .iter()
+            .filter(|modifier| *modifier != "final")
+            .cloned()
+            .collect();
+
+        if !self.type_parameters.is_empty() {
+            let names: Vec<String> = self
+                .type_parameters
+                .iter()
+                .map(|name| format!(":{name}"))
+                .collect();
+
+            chain.push(format!("type_parameters({})", names.join(", ")));
+        }
+
+        if !self.params.is_empty() {
+            let params: Vec<String> = self
+                .params
+                .iter()
+                .map(|(name, type_)| format!("{name}: {type_}"))
+                .collect();
+
+            chain.push(format!("params({})", params.join(", ")));
+        }
+
+        chain.push(match &self.returns {
+            Some(type_) => format!("returns({type_})"),
+            None => "void".to_string(),
+        });
+
+        let sig = if self.modifiers.iter().any(|modifier| modifier == "final") {
+            "sig(:final)"
+        } else {
+            "sig"
+        };
+
+        format!("{sig} {{ {} }}", chain.join("."))
+    }
+}
+
+/// The `sig` of each `Def`/`Defs` in the file that has one, keyed by the `Def`/`Defs` node's id.
+///
+#[salsa::tracked]
+pub fn signatures(db: &dyn crate::db::Db, file_source: FileSource) -> BTreeMap<usize, Sig> {
+    let code = file_source.code(db);
+    let nodes = parse(db, file_source);
+    let by_id = index_by_id(&nodes);
+
+    let mut definitions: Vec<&Node> = nodes
+        .iter()
+        .filter(|node| matches!(node.properties(), Properties::Def(_) | Properties::Defs(_)))
+        .collect();
+    definitions.sort_by_key(|node| node.expression_l().begin);
+
+    nodes
+        .iter()
+        .filter_map(|node| {
+            let Properties::Block(block) = node.properties() else {
+                return None;
+            };
+
+            let sig = parse_sig(&by_id, code, block, *node.expression_l())?;
+
+            let definition = definitions
+                .iter()
+                .find(|definition| definition.expression_l().begin >= sig.loc.end)?;
+
+            only_modifiers_between(code, sig.loc.end, definition.expression_l().begin)
+                .then(|| (definition.id(), sig))
+        })
+        .collect()
+}
+
+/// Reads `sig { ... }`'s call chain from the outside in, ex. `returns(String)` then
+/// `params(x: Integer)` then `override`. Chains that have neither `returns` nor `void` aren't
+/// signatures.
+///
+fn parse_sig(by_id: &HashMap<usize, &Node>, code: &Rope, block: &Block, loc: Loc) -> Option<Sig> {
+    let Properties::Send(call) = by_id.get(&block.call_id)?.properties() else {
+        return None;
+    };
+
+    if call.method_name != "sig" {
+        return None;
+    }
+
+    let mut params = Vec::new();
+    let mut returns = None;
+    let mut modifiers = Vec::new();
+    let mut type_parameters = Vec::new();
+
+    let mut current = block.body_id.and_then(|id| by_id.get(&id));
+
+    while let Some(node) = current {
+        let Properties::Send(send) = node.properties() else {
+            break;
+        };
+
+        let args = send.arg_ids.iter().filter_map(|id| by_id.get(id));
+
+        match send.method_name.as_str() {
+            "params" => {
+                for arg in args {
+                    let (Properties::Kwargs(nodes::Kwargs { pair_ids })
+                    | Properties::Hash(nodes::Hash { pair_ids, .. })) = arg.properties()
+                    else {
+                        continue;
+                    };
+
+                    params.extend(pair_ids.iter().filter_map(|id| {
+                        let Properties::Pair(pair) = by_id.get(id)?.properties() else {
+                            return None;
+                        };
+                        let Properties::Sym(key) = by_id.get(&pair.key_id)?.properties() else {
+                            return None;
+                        };
+
+                        Some((
+                            key.name.clone(),
+                            source(code, by_id.get(&pair.value_id)?.expression_l()),
+                        ))
+                    }));
+                }
+            }
+            "returns" => {
+                returns = Some(args.map(|arg| source(code, arg.expression_l())).next());
+            }
+            "void" => returns = Some(None),
+            "type_parameters" => {
+                type_parameters.extend(args.filter_map(|arg| match arg.properties() {
+                    Properties::Sym(sym) => Some(sym.name.clone()),
+                    _ => None,
+                }));
+            }
+            "abstract" | "override" | "overridable" => modifiers.push(send.method_name.clone()),
+            // ex. `checked(:never)`, `on_failure(...)`; nothing a reader needs.
+            _ => {}
+        }
+
+        current = send.recv_id.and_then(|id| by_id.get(&id));
+    }
+
+    modifiers.reverse();
+
+    if call.arg_ids.iter().any(|id| {
+        by_id.get(id).map_or(
+            false,
+            |arg| matches!(arg.properties(), Properties::Sym(sym) if sym.name == "final"),
+        )
+    }) {
+        modifiers.push("final".to_string());
+    }
+
+    Some(Sig {
+        params,
+        returns: returns?,
+        modifiers,
+        type_parameters,
+        loc,
+    })
+}
+
+/// Is there nothing but whitespace, comments and visibility modifiers between `begin` and `end`?
+///
+fn only_modifiers_between(code: &Rope, begin: usize, end: usize) -> bool {
+    code.byte_slice(begin..end)
+        .to_string()
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .flat_map(str::split_whitespace)
+        .all(|word| MODIFIERS.contains(&word))
+}
+
+/// Whitespace-normalized source, so types written across lines read as they would on one.
+///
+fn source(code: &Rope, loc: &Loc) -> String {
+    code.byte_slice(loc.begin()..loc.end())
+        .to_string()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The project's `.rbi` files. They're kept apart from the `Workspace`: see the module docs.
+///
+#[salsa::input]
+pub struct RbiFiles {
+    #[return_ref]
+    pub file_sources: Vec<FileSource>,
+}
+
+/// Every `.rbi` file under `root`'s `sorbet/rbi/`, sorted.
+///
+pub fn rbi_paths(root: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    rbi_files(&root.join(RBI_DIRECTORY), &mut paths)?;
+    paths.sort();
+
+    Ok(paths)
+}
+
+fn rbi_files(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            rbi_files(&path, paths)?;
+        } else if path
+            .extension()
+            .map_or(false, |extension| extension == "rbi")
+        {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads `root`'s `sorbet/rbi/**/*.rbi` into `RbiFiles`. They're regenerated rather than edited,
+/// so, like gem sources, their code is set with `Durability::HIGH`.
+///
+pub fn load_rbi_files(db: &mut dyn crate::db::Db, root: &Path) -> io::Result<RbiFiles> {
+    let mut file_sources = Vec::new();
+
+    for path in rbi_paths(root)? {
+        let code = Rope::from_str(&fs::read_to_string(&path)?);
+        let file_source = FileSource::new(db, path, Rope::new());
+
+        file_source
+            .set_code(db)
+            .with_durability(Durability::HIGH)
+            .to(code);
+
+        file_sources.push(file_source);
+    }
+
+    let rbi_files = RbiFiles::new(db, Vec::new());
+    rbi_files
+        .set_file_sources(db)
+        .with_durability(Durability::HIGH)
+        .to(file_sources);
+
+    Ok(rbi_files)
+}
+
+/// The signatures declared in `.rbi` files, keyed by the method's fully qualified name, ex.
+/// `Foo::Bar#baz` or `Foo::Bar.baz`. When several files declare the same method, the first wins.
+///
+#[salsa::tracked]
+pub fn rbi_signatures(db: &dyn crate::db::Db, rbi_files: RbiFiles) -> BTreeMap<String, Sig> {
+    let mut rbi_signatures = BTreeMap::new();
+
+    for &file_source in rbi_files.file_sources(db) {
+        let nodes = parse(db, file_source);
+        let by_id = index_by_id(&nodes);
+
+        for (id, sig) in signatures(db, file_source) {
+            let Some(node) = by_id.get(&id) else {
+                continue;
+            };
+
+            let (name, singleton) = match node.properties() {
+                Properties::Def(def) => (&def.name, in_singleton_class(&nodes, node)),
+                Properties::Defs(defs) => (&defs.name, true),
+                _ => continue,
+            };
+
+            let owner = method_owner(&nodes, &by_id, node);
+            let namespace: Vec<&str> = owner.iter().map(String::as_str).collect();
+
+            rbi_signatures
+                .entry(method_fully_qualified_name(&namespace, name, singleton))
+                .or_insert(sig);
+        }
+    }
+
+    rbi_signatures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    const CODE: &str = r#"class Greeter
+  extend T::Sig
+
+  sig { params(name: String, times: T.nilable(Integer)).returns(String) }
+  def greet(name, times = nil)
+    name * (times || 1)
+  end
+
+  sig do
+    override
+      .params(
+        other: Greeter
+      )
+      .void
+  end
+  private def compare(other); end
+
+  sig(:final) { type_parameters(:U).params(x: T.type_parameter(:U)).void }
+  def self.identity(x) = x
+
+  sig { void }
+  attr_reader :name
+
+  def untyped; end
+end
+"#;
+
+    #[test]
+    fn signatures_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let nodes = parse(&db, file_source);
+        let signatures = signatures(&db, file_source);
+
+        let sig_for = |name: &str| {
+            nodes
+                .iter()
+                .find(|node| match node.properties() {
+                    Properties::Def(def) => def.name == name,
+                    Properties::Defs(defs) => defs.name == name,
+                    _ => false,
+                })
+                .and_then(|node| signatures.get(&node.id()))
+                .map(Sig::to_ruby)
+        };
+
+        assert_eq!(
+            Some(
+                "sig { params(name: String, times: T.nilable(Integer)).returns(String) }"
+                    .to_string()
+            ),
+            sig_for("greet")
+        );
+        assert_eq!(
+            Some("sig { override.params(other: Greeter).void }".to_string()),
+            sig_for("compare")
+        );
+        assert_eq!(
+            Some(
+                "sig(:final) { type_parameters(:U).params(x: T.type_parameter(:U)).void }"
+                    .to_string()
+            ),
+            sig_for("identity")
+        );
+
+        // The `sig { void }` belongs to `attr_reader`, not the next `def`.
+        assert_eq!(None, sig_for("untyped"));
+        assert_eq!(3, signatures.len());
+    }
+
+    #[test]
+    fn rbi_signatures_test() {
+        let root = std::env::temp_dir().join(format!("ruby-analyzer-rbi-{}", std::process::id()));
+        let dir = root.join("sorbet/rbi/gems");
+
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("greeter.rbi"),
+            r#"class Greeter
+  sig { params(name: String).returns(String) }
+  def greet(name); end
+
+  class << self
+    sig { returns(Greeter) }
+    def build; end
+  end
+end"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("active_record.rbi"),
+            r#"class ActiveRecord::Base
+  sig { returns(T::Boolean) }
+  def persisted?; end
+end"#,
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "Not an RBI.\n").unwrap();
+
+        assert_eq!(
+            vec![dir.join("active_record.rbi"), dir.join("greeter.rbi")],
+            rbi_paths(&root).unwrap()
+        );
+
+        let mut db = Database::default();
+        let rbi_files = load_rbi_files(&mut db, &root).unwrap();
+        let signatures = rbi_signatures(&db, rbi_files);
+
+        assert_eq!(
+            vec![
+                "ActiveRecord::Base#persisted?",
+                "Greeter#greet",
+                "Greeter.build"
+            ],
+            signatures.keys().map(String::as_str).collect::<Vec<_>>()
+        );
+        assert_eq!(Some("String"), signatures["Greeter#greet"].param("name"));
+        assert_eq!(Some("Greeter"), signatures["Greeter.build"].returns());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
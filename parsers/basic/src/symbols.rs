@@ -0,0 +1,452 @@
+//! A project-wide table of classes, modules, methods and constants, for `workspace/symbol`.
+//!
+use std::collections::HashMap;
+
+use lsp_types::{SymbolInformation, SymbolKind};
+
+use crate::{
+    constants::{definition_path, method_owner},
+    fuzzy::fuzzy_score,
+    node::{in_singleton_class, index_by_id, Loc},
+    parser::{parse, FileSource},
+    properties::Properties,
+    workspace::Workspace,
+    yard::{yard_directives, DirectiveKind},
+    Node,
+};
+
+/// A class, module, method or constant definition.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub(crate) kind: SymbolKind,
+    pub(crate) name: String,
+
+    /// Ex. `Foo::Bar` for a class or constant, `Foo::Bar#baz` for an instance method and
+    /// `Foo::Bar.baz` for a singleton method.
+    pub(crate) fully_qualified_name: String,
+
+    pub(crate) expression_l: Loc,
+    pub(crate) name_l: Loc,
+}
+
+impl Symbol {
+    pub fn kind(&self) -> SymbolKind {
+        self.kind
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn fully_qualified_name(&self) -> &str {
+        self.fully_qualified_name.as_ref()
+    }
+
+    pub fn expression_l(&self) -> Loc {
+        self.expression_l
+    }
+
+    pub fn name_l(&self) -> Loc {
+        self.name_l
+    }
+
+    /// The namespace that the symbol is defined in, ex. `Foo` for `Foo::Bar` or `Foo#bar`.
+    ///
+    pub fn container_name(&self) -> Option<&str> {
+        let name_start = self
+            .fully_qualified_name
+            .rfind(['#', '.'])
+            .or_else(|| self.fully_qualified_name.rfind("::"))?;
+
+        Some(&self.fully_qualified_name[..name_start]).filter(|container| !container.is_empty())
+    }
+}
+
+/// Formats the fully qualified name of a method, ex. `Foo::Bar#baz` or `Foo::Bar.baz`.
+///
+pub(crate) fn method_fully_qualified_name(
+    namespace: &[&str],
+    method_name: &str,
+    singleton: bool,
+) -> String {
+    if namespace.is_empty() {
+        return method_name.to_string();
+    }
+
+    let separator = if singleton { '.' } else { '#' };
+
+    format!("{}{separator}{method_name}", namespace.join("::"))
+}
+
+/// All symbols defined in a single file. This is memoized per file, so when one file changes,
+/// only its symbols need to be rebuilt.
+///
+#[salsa::tracked]
+pub fn file_symbols(db: &dyn crate::db::Db, file_source: FileSource) -> Vec<Symbol> {
+    let nodes = parse(db, file_source);
+    let by_id = index_by_id(&nodes);
+
+    let mut symbols: Vec<Symbol> = nodes
+        .iter()
+        .filter_map(|node| {
+            let method_namespace = || method_owner(&nodes, &by_id, node);
+
+            let (kind, name, fully_qualified_name, name_l) = match node.properties() {
+                Properties::Class(_) | Properties::Module(_) | Properties::Casgn(_) => {
+                    let path = definition_path(&nodes, &by_id, node)?;
+
+                    let (kind, name_l) = match node.properties() {
+                        Properties::Class(class) => {
+                            (SymbolKind::CLASS, const_name_l(&by_id, class.name_id)?)
+                        }
+                        Properties::Module(module) => {
+                            (SymbolKind::MODULE, const_name_l(&by_id, module.name_id)?)
+                        }
+                        Properties::Casgn(casgn) => (SymbolKind::CONSTANT, casgn.name_l),
+                        _ => unreachable!(),
+                    };
+
+                    (kind, path.last()?.clone(), path.join("::"), name_l)
+                }
+                Properties::Def(def) => {
+                    let owner = method_namespace();
+                    let namespace: Vec<&str> = owner.iter().map(String::as_str).collect();
+
+                    (
+                        SymbolKind::METHOD,
+                        def.name.clone(),
+                        method_fully_qualified_name(
+                            &namespace,
+                            &def.name,
+                            in_singleton_class(&nodes, node),
+                        ),
+                        def.name_l,
+                    )
+                }
+                Properties::Defs(defs) => {
+                    let owner = method_namespace();
+                    let namespace: Vec<&str> = owner.iter().map(String::as_str).collect();
+
+                    (
+                        SymbolKind::METHOD,
+                        defs.name.clone(),
+                        method_fully_qualified_name(&namespace, &defs.name, true),
+                        defs.name_l,
+                    )
+                }
+                _ => return None,
+            };
+
+            Some(Symbol {
+                kind,
+                name,
+                fully_qualified_name,
+                expression_l: *node.expression_l(),
+                name_l,
+            })
+        })
+        .collect();
+
+    // Methods that only exist as YARD `@!method`/`@!attribute` directives.
+    for directive in yard_directives(db, file_source) {
+        let namespace: Vec<&str> = directive.namespace.iter().map(String::as_str).collect();
+
+        let kind = match directive.kind {
+            DirectiveKind::Method => SymbolKind::METHOD,
+            DirectiveKind::Attribute { .. } => SymbolKind::PROPERTY,
+        };
+
+        symbols.extend(directive.method_names().into_iter().map(|name| Symbol {
+            kind,
+            fully_qualified_name: method_fully_qualified_name(
+                &namespace,
+                &name,
+                directive.singleton,
+            ),
+            name,
+            expression_l: directive.name_l,
+            name_l: directive.name_l,
+        }));
+    }
+
+    symbols.sort_by_key(|symbol| symbol.name_l.begin());
+
+    symbols
+}
+
+fn const_name_l(by_id: &HashMap<usize, &Node>, name_id: usize) -> Option<Loc> {
+    match by_id.get(&name_id)?.properties() {
+        Properties::Const(const_) => Some(const_.name_l),
+        _ => None,
+    }
+}
+
+#[salsa::input]
+pub struct WorkspaceSymbolQuery {
+    pub workspace: Workspace,
+
+    #[return_ref]
+    pub query: String,
+}
+
+/// A `Symbol` that matched a `WorkspaceSymbolQuery`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolMatch {
+    pub(crate) file_source: FileSource,
+    pub(crate) symbol: Symbol,
+    pub(crate) score: i64,
+}
+
+impl SymbolMatch {
+    pub fn file_source(&self) -> FileSource {
+        self.file_source
+    }
+
+    pub fn symbol(&self) -> &Symbol {
+        &self.symbol
+    }
+
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    #[allow(deprecated)]
+    pub fn to_symbol_information(&self, db: &dyn crate::db::Db) -> Option<SymbolInformation> {
+        let uri = lsp_types::Url::from_file_path(self.file_source.file_uri(db)).ok()?;
+        let range = self.symbol.name_l.to_lsp_range(self.file_source.code(db));
+
+        Some(SymbolInformation {
+            name: self.symbol.fully_qualified_name.clone(),
+            kind: self.symbol.kind,
+            tags: None,
+            deprecated: None,
+            location: lsp_types::Location::new(uri, range),
+            container_name: self.symbol.container_name().map(ToString::to_string),
+        })
+    }
+}
+
+/// Fuzzy-matches the query against the fully qualified name of every symbol in the workspace,
+/// best matches first.
+///
+#[salsa::tracked]
+pub fn workspace_symbols(db: &dyn crate::db::Db, query: WorkspaceSymbolQuery) -> Vec<SymbolMatch> {
+    let pattern = query.query(db);
+
+    let mut matches: Vec<SymbolMatch> = query
+        .workspace(db)
+        .file_sources(db)
+        .iter()
+        .flat_map(|&file_source| {
+            file_symbols(db, file_source)
+                .into_iter()
+                .filter_map(move |symbol| {
+                    let score = fuzzy_score(pattern, &symbol.fully_qualified_name)?;
+
+                    Some(SymbolMatch {
+                        file_source,
+                        symbol,
+                        score,
+                    })
+                })
+        })
+        .collect();
+
+    matches.sort_by(|a, b| {
+        b.score.cmp(&a.score).then_with(|| {
+            a.symbol
+                .fully_qualified_name
+                .cmp(&b.symbol.fully_qualified_name)
+        })
+    });
+
+    matches
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ropey::Rope;
+
+    use super::*;
+    use crate::db::Database;
+
+    const USERS_CONTROLLER: &str = r#"module Admin
+  class UsersController
+    PER_PAGE = 20
+
+    def index; end
+
+    def self.policy; end
+  end
+end"#;
+
+    const USER: &str = r#"class AuditUsage
+  class << self
+    def count; end
+  end
+end"#;
+
+    #[test]
+    fn file_symbols_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(
+            &db,
+            PathBuf::from("/tmp/users_controller.rb"),
+            Rope::from_str(USERS_CONTROLLER),
+        );
+
+        let names: Vec<(String, Option<String>)> = file_symbols(&db, file_source)
+            .iter()
+            .map(|s| {
+                (
+                    s.fully_qualified_name().to_string(),
+                    s.container_name().map(ToString::to_string),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("Admin".to_string(), None),
+                (
+                    "Admin::UsersController".to_string(),
+                    Some("Admin".to_string())
+                ),
+                (
+                    "Admin::UsersController::PER_PAGE".to_string(),
+                    Some("Admin::UsersController".to_string())
+                ),
+                (
+                    "Admin::UsersController#index".to_string(),
+                    Some("Admin::UsersController".to_string())
+                ),
+                (
+                    "Admin::UsersController.policy".to_string(),
+                    Some("Admin::UsersController".to_string())
+                ),
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn workspace_symbols_test() {
+        let mut db = Database::default();
+        let users_controller = FileSource::new(
+            &db,
+            PathBuf::from("/tmp/users_controller.rb"),
+            Rope::from_str(USERS_CONTROLLER),
+        );
+        let user = FileSource::new(&db, PathBuf::from("/tmp/user.rb"), Rope::from_str(USER));
+        let workspace = Workspace::new(&db, vec![users_controller, user]);
+
+        let query = WorkspaceSymbolQuery::new(&db, workspace, "AUC".to_string());
+        let matches = workspace_symbols(&db, query);
+        assert_eq!(
+            "Admin::UsersController",
+            matches[0].symbol().fully_qualified_name()
+        );
+
+        let query = WorkspaceSymbolQuery::new(&db, workspace, "count".to_string());
+        let matches = workspace_symbols(&db, query);
+        assert_eq!(1, matches.len());
+        assert_eq!(
+            "AuditUsage.count",
+            matches[0].symbol().fully_qualified_name()
+        );
+
+        // Editing a file updates the results.
+        user.set_code(&mut db)
+            .to(Rope::from_str("class AuditUsage; def recount; end; end"));
+        let matches = workspace_symbols(&db, query);
+        assert_eq!(
+            "AuditUsage#recount",
+            matches[0].symbol().fully_qualified_name()
+        );
+    }
+
+    #[test]
+    fn compact_class_test() {
+        let db = Database::default();
+        let code = "class Admin::UsersController\n  def index; end\nend";
+        let file_source =
+            FileSource::new(&db, PathBuf::from("/tmp/users.rb"), Rope::from_str(code));
+        let workspace = Workspace::new(&db, vec![file_source]);
+
+        let query =
+            WorkspaceSymbolQuery::new(&db, workspace, "Admin::UsersController#index".into());
+        let matches = workspace_symbols(&db, query);
+
+        assert_eq!(
+            "Admin::UsersController#index",
+            matches[0].symbol().fully_qualified_name()
+        );
+        assert_eq!(
+            Some("Admin::UsersController"),
+            matches[0].symbol().container_name()
+        );
+    }
+
+    #[test]
+    fn compact_class_constants_test() {
+        let db = Database::default();
+        let code = r#"class Admin::UsersController
+  PER_PAGE = 1
+end
+
+module Api
+  class Admin::Users
+    class Inner; end
+  end
+end"#;
+        let file_source =
+            FileSource::new(&db, PathBuf::from("/tmp/users.rb"), Rope::from_str(code));
+
+        let mut names: Vec<String> = file_symbols(&db, file_source)
+            .iter()
+            .map(|s| s.fully_qualified_name().to_string())
+            .collect();
+        names.sort();
+
+        assert_eq!(
+            vec![
+                "Admin::UsersController",
+                "Admin::UsersController::PER_PAGE",
+                "Api",
+                "Api::Admin::Users",
+                "Api::Admin::Users::Inner",
+            ],
+            names
+        );
+    }
+
+    #[test]
+    fn yard_directive_symbols_test() {
+        let db = Database::default();
+        let code = r#"class Account
+  # @!attribute [rw] balance
+  #   @return [Integer]
+  store_accessor :data, :balance
+end"#;
+        let file_source =
+            FileSource::new(&db, PathBuf::from("/tmp/account.rb"), Rope::from_str(code));
+
+        let symbols: Vec<(SymbolKind, String)> = file_symbols(&db, file_source)
+            .iter()
+            .map(|s| (s.kind(), s.fully_qualified_name().to_string()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (SymbolKind::CLASS, "Account".to_string()),
+                (SymbolKind::PROPERTY, "Account#balance".to_string()),
+                (SymbolKind::PROPERTY, "Account#balance=".to_string()),
+            ],
+            symbols
+        );
+    }
+}
@@ -5,16 +5,37 @@ use crate::node::Loc;
 #[allow(clippy::wildcard_imports)]
 use crate::{
     lrp_extensions::{NameFromNode, OptionNameFromNode},
-    node::Node,
+    node::{in_singleton_class, Node},
     nodes::*,
     properties::Properties,
     scope_gate::{Node as ScopeGateNode, ScopeGate},
 };
 
+/// Methods that Ruby always makes private, regardless of where they're defined.
+///
+const ALWAYS_PRIVATE: &[&str] = &[
+    "initialize",
+    "initialize_copy",
+    "initialize_clone",
+    "initialize_dup",
+    "respond_to_missing?",
+];
+
 pub(crate) struct Transformer {
     current_id: usize,
     scope_gate: ScopeGate,
     nodes: Vec<Node>,
+    visibility_frames: Vec<VisibilityFrame>,
+}
+
+/// The default visibility of `def`s in a class, module, `class << self` or method body, which a
+/// bare `private` (or `public`, etc.) changes for the rest of that body.
+///
+struct VisibilityFrame {
+    default: Visibility,
+
+    /// Every node in the body has a greater ID than this.
+    first_id: usize,
 }
 
 impl Transformer {
@@ -23,6 +44,7 @@ impl Transformer {
             current_id: 0,
             scope_gate: ScopeGate::default(),
             nodes: Vec::new(),
+            visibility_frames: Vec::new(),
         }
     }
 
@@ -68,6 +90,106 @@ impl Transformer {
 
         self.nodes.last().map(|n| n.id()).unwrap()
     }
+
+    fn push_visibility_frame(&mut self) {
+        self.visibility_frames.push(VisibilityFrame {
+            default: Visibility::Public,
+            first_id: self.current_id,
+        });
+    }
+
+    fn pop_visibility_frame(&mut self) {
+        self.visibility_frames.pop();
+    }
+
+    // Top-level methods end up as private methods on `Object`.
+    //
+    fn def_visibility(&self, method_name: &str) -> Visibility {
+        if ALWAYS_PRIVATE.contains(&method_name) {
+            return Visibility::Private;
+        }
+
+        self.visibility_frames
+            .last()
+            .map_or(Visibility::Private, |frame| frame.default)
+    }
+
+    // Handles `private` (and friends) when called without arguments, which changes the default for
+    // the rest of the body, and with arguments, ex. `private :foo` or `private def foo`, which
+    // changes the visibility of the named methods that have already been defined in this body.
+    //
+    fn apply_visibility_call(&mut self, node: &lrp_nodes::Send, arg_ids: &[usize]) {
+        if node.recv.is_some() {
+            return;
+        }
+
+        let (visibility, singleton) = match node.method_name.as_str() {
+            "private_class_method" => (Visibility::Private, true),
+            "public_class_method" => (Visibility::Public, true),
+            method_name => match Visibility::from_method_name(method_name) {
+                Some(visibility) => (visibility, false),
+                None => return,
+            },
+        };
+
+        if arg_ids.is_empty() {
+            if let (false, Some(frame)) = (singleton, self.visibility_frames.last_mut()) {
+                frame.default = visibility;
+            }
+
+            return;
+        }
+
+        let first_id = self
+            .visibility_frames
+            .last()
+            .map_or(0, |frame| frame.first_id);
+
+        let method_names: Vec<String> = arg_ids
+            .iter()
+            .filter_map(|arg_id| self.nodes.iter().rev().find(|n| n.id == *arg_id))
+            .filter_map(|arg| match &arg.properties {
+                Properties::Sym(sym) => Some(sym.name.clone()),
+                Properties::Def(def) => Some(def.name.clone()),
+                Properties::Defs(defs) => Some(defs.name.clone()),
+                _ => None,
+            })
+            .collect();
+
+        for method_name in method_names {
+            // `class << self` doesn't add to the scope gate, so the `def`s in it are told apart
+            // from instance methods by where they are.
+            let target_id = self
+                .nodes
+                .iter()
+                .rev()
+                .filter(|n| n.id > first_id && n.scope_gate == self.scope_gate)
+                .find(|n| match &n.properties {
+                    Properties::Def(def) => {
+                        def.name == method_name && in_singleton_class(&self.nodes, n) == singleton
+                    }
+                    Properties::Defs(defs) => singleton && defs.name == method_name,
+                    _ => false,
+                })
+                .map(|n| n.id);
+
+            let Some(target_id) = target_id else {
+                continue;
+            };
+
+            match self
+                .nodes
+                .iter_mut()
+                .rev()
+                .find(|n| n.id == target_id)
+                .map(|n| &mut n.properties)
+            {
+                Some(Properties::Def(def)) => def.visibility = visibility,
+                Some(Properties::Defs(defs)) => defs.visibility = visibility,
+                _ => (),
+            }
+        }
+    }
 }
 
 impl Visitor for Transformer {
@@ -402,7 +524,9 @@ impl Visitor for Transformer {
             &self.scope_gate
         );
 
+        self.push_visibility_frame();
         let body_id = self.visit_optional_child(&node.body);
+        self.pop_visibility_frame();
 
         self.scope_gate.pop();
         debug!(
@@ -513,7 +637,9 @@ impl Visitor for Transformer {
         self.scope_gate
             .push_owned(ScopeGateNode::Def(node.name.clone()));
 
+        self.push_visibility_frame();
         let body_id = self.visit_optional_child(&node.body);
+        self.pop_visibility_frame();
 
         self.scope_gate.pop();
 
@@ -529,6 +655,7 @@ impl Visitor for Transformer {
                 name_l: Loc::from(node.name_l),
                 end_l: node.end_l.map(Loc::from),
                 assignment_l: node.assignment_l.map(Loc::from),
+                visibility: self.def_visibility(&node.name),
             }),
         });
     }
@@ -558,7 +685,9 @@ impl Visitor for Transformer {
         self.scope_gate
             .push_owned(ScopeGateNode::Defs(node.name.clone()));
 
+        self.push_visibility_frame();
         let body_id = self.visit_optional_child(&node.body);
+        self.pop_visibility_frame();
 
         self.scope_gate.pop();
 
@@ -576,6 +705,7 @@ impl Visitor for Transformer {
                 name_l: Loc::from(node.name_l),
                 assignment_l: node.assignment_l.map(Loc::from),
                 end_l: node.end_l.map(Loc::from),
+                visibility: Visibility::Public,
             }),
         });
     }
@@ -1407,7 +1537,9 @@ impl Visitor for Transformer {
         self.scope_gate
             .push_owned(ScopeGateNode::Module(name.clone()));
 
+        self.push_visibility_frame();
         let body_id = self.visit_optional_child(&node.body);
+        self.pop_visibility_frame();
 
         self.scope_gate.pop();
 
@@ -1774,7 +1906,9 @@ impl Visitor for Transformer {
     fn on_s_class(&mut self, node: &lrp_nodes::SClass) {
         let id = self.new_id();
         let expr_id = self.visit_child(&node.expr);
+        self.push_visibility_frame();
         let body_id = self.visit_optional_child(&node.body);
+        self.pop_visibility_frame();
 
         self.nodes.push(Node {
             id,
@@ -1806,6 +1940,8 @@ impl Visitor for Transformer {
         let recv_id = self.visit_optional_child(&node.recv);
         let arg_ids = self.visit_children(&node.args);
 
+        self.apply_visibility_call(node, &arg_ids);
+
         self.nodes.push(Node {
             id,
             scope_gate: self.scope_gate.clone(),
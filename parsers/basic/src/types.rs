@@ -0,0 +1,801 @@
+//! Basic type inference: what class an expression's value is an instance of, as far as can be
+//! told without running anything. Literals have their obvious types, `Foo.new` is a `Foo`, locals
+//! have the types of the values that reach them (see `crate::locals`), and method calls have the
+//! declared return type of the method they resolve to: from its Sorbet `sig`, its YARD `@return`,
+//! the project's `.rbi` files or, failing those, `TypeEnvironment::return_types` (which is where
+//! callers put what they know from elsewhere, ex. the RBS parser's `RbsIndex::return_types`).
+//!
+//! Anything else is unknown (`None`) rather than guessed.
+//!
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+};
+
+use crate::{
+    ancestors::{ancestor_chains, Ancestor, Ancestors},
+    constants::{
+        defined_constants, enclosing_namespace, lexical_namespace, method_owner, ConstPath,
+    },
+    locals::{locals, DefinitionKind, Locals},
+    node::{in_singleton_class, in_singleton_context, index_by_id, Contains},
+    parser::{parse, FileSource},
+    properties::Properties,
+    sorbet::{rbi_signatures, signatures, RbiFiles, Sig},
+    symbols::method_fully_qualified_name,
+    workspace::Workspace,
+    yard::{yard_directives, yard_docs, DirectiveKind, YardDoc},
+    Node,
+};
+
+/// How far `Inference::infer` follows values (through locals, instance variables and calls)
+/// before giving up; this also keeps ex. `@count = @count + 1` from recursing forever.
+///
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Type {
+    /// An instance of the class, ex. `String` or `Admin::User`.
+    Instance(Vec<String>),
+
+    /// The class or module itself, ex. the `User` in `User.new`.
+    Singleton(Vec<String>),
+
+    /// One of several types, ex. `String | nil`; never nested, and never just one.
+    Union(Vec<Type>),
+}
+
+impl Type {
+    /// An instance of the class named `name`, ex. `"Admin::User"`.
+    ///
+    pub fn instance(name: &str) -> Self {
+        Self::Instance(name.split("::").map(ToString::to_string).collect())
+    }
+
+    pub fn nil() -> Self {
+        Self::instance("NilClass")
+    }
+
+    /// Flattens and dedups `types`; `None` if there are none.
+    ///
+    pub fn union(types: impl IntoIterator<Item = Type>) -> Option<Self> {
+        let mut members = BTreeSet::new();
+
+        for type_ in types {
+            match type_ {
+                Self::Union(nested) => members.extend(nested),
+                other => {
+                    members.insert(other);
+                }
+            }
+        }
+
+        let mut members: Vec<Type> = members.into_iter().collect();
+
+        match members.len() {
+            0 => None,
+            1 => members.pop(),
+            _ => Some(Self::Union(members)),
+        }
+    }
+
+    /// The types this could be: itself, or the members of a union.
+    ///
+    pub fn members(&self) -> &[Type] {
+        match self {
+            Self::Union(members) => members,
+            _ => std::slice::from_ref(self),
+        }
+    }
+
+    /// Reads a type annotation written in Sorbet (`T.nilable(String)`), YARD (`Array<String>`,
+    /// `String, nil`) or RBS (`String?`, `Array[String]`) syntax. Generic arguments are dropped,
+    /// and `self`-like types become `receiver`. Returns `None` for `void`, `untyped` and the like.
+    ///
+    pub fn parse(annotation: &str, receiver: &Type) -> Option<Self> {
+        let annotation = annotation.trim();
+
+        let alternatives = split_top_level(annotation, '|');
+        if alternatives.len() > 1 {
+            return Self::union_of_all(alternatives, receiver);
+        }
+
+        // YARD's `[String, nil]`.
+        let alternatives = split_top_level(annotation, ',');
+        if alternatives.len() > 1 {
+            return Self::union_of_all(alternatives, receiver);
+        }
+
+        if let Some(inner) = annotation.strip_suffix('?') {
+            return Self::union([Self::parse(inner, receiver)?, Self::nil()]);
+        }
+
+        if let Some(inner) = call_argument(annotation, "T.nilable") {
+            return Self::union([Self::parse(inner, receiver)?, Self::nil()]);
+        }
+
+        if let Some(inner) = call_argument(annotation, "T.any") {
+            return Self::union_of_all(split_top_level(inner, ','), receiver);
+        }
+
+        if let Some(inner) = call_argument(annotation, "T.class_of")
+            .or_else(|| call_argument(annotation, "singleton"))
+        {
+            return match Self::parse(inner, receiver)? {
+                Self::Instance(path) => Some(Self::Singleton(path)),
+                _ => None,
+            };
+        }
+
+        match annotation {
+            "self" | "T.self_type" => return Some(receiver.clone()),
+            "instance" | "T.attached_class" => {
+                return Self::union(receiver.members().iter().map(|member| match member {
+                    Self::Singleton(path) => Self::Instance(path.clone()),
+                    other => other.clone(),
+                }));
+            }
+            "nil" | "NilClass" => return Some(Self::nil()),
+            "true" | "TrueClass" => return Some(Self::instance("TrueClass")),
+            "false" | "FalseClass" => return Some(Self::instance("FalseClass")),
+            "bool" | "boolish" | "Boolean" | "T::Boolean" => {
+                return Self::union([Self::instance("TrueClass"), Self::instance("FalseClass")]);
+            }
+            _ => {}
+        }
+
+        // Drop generic arguments, ex. `Array[String]`, `Array<String>`, `Hash{Symbol => String}`.
+        let name = annotation
+            .split(['[', '<', '{', '('])
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .trim_start_matches("::");
+
+        // Sorbet's generic aliases of the core collections.
+        let name = match name {
+            "T::Array" | "T::Hash" | "T::Set" | "T::Range" | "T::Enumerable" | "T::Enumerator" => {
+                &name[3..]
+            }
+            _ => name,
+        };
+
+        let is_constant = !name.is_empty()
+            && name.split("::").all(|segment| {
+                segment.starts_with(|char: char| char.is_ascii_uppercase())
+                    && segment
+                        .chars()
+                        .all(|char| char.is_ascii_alphanumeric() || char == '_')
+            });
+
+        is_constant.then(|| Self::instance(name))
+    }
+
+    fn union_of_all(annotations: Vec<&str>, receiver: &Type) -> Option<Self> {
+        let types: Option<Vec<Type>> = annotations
+            .into_iter()
+            .map(|annotation| Self::parse(annotation, receiver))
+            .collect();
+
+        Self::union(types?)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Instance(path) if path.len() == 1 && path[0] == "NilClass" => write!(f, "nil"),
+            Self::Instance(path) => write!(f, "{}", path.join("::")),
+            Self::Singleton(path) => write!(f, "singleton({})", path.join("::")),
+            Self::Union(members) => {
+                // `nil` reads best last, ex. `String | nil`.
+                let (nils, others): (Vec<&Type>, Vec<&Type>) =
+                    members.iter().partition(|member| **member == Self::nil());
+                let members: Vec<String> = others
+                    .into_iter()
+                    .chain(nils)
+                    .map(ToString::to_string)
+                    .collect();
+
+                write!(f, "{}", members.join(" | "))
+            }
+        }
+    }
+}
+
+/// Splits on `separator`s that aren't inside brackets.
+///
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0_i32;
+    let mut start = 0;
+    let mut previous = None;
+
+    for (i, char) in text.char_indices() {
+        match char {
+            '[' | '(' | '{' | '<' => depth += 1,
+            // `Hash{Symbol => String}`'s `=>` and `^() -> void`'s `->` don't close anything.
+            '>' if matches!(previous, Some('=' | '-')) => {}
+            ']' | ')' | '}' | '>' => depth -= 1,
+            _ if char == separator && depth == 0 => {
+                parts.push(text[start..i].trim());
+                start = i + char.len_utf8();
+            }
+            _ => {}
+        }
+
+        previous = Some(char);
+    }
+
+    parts.push(text[start..].trim());
+    parts
+}
+
+/// `T.nilable(String)` → `String`, for `name` `T.nilable`.
+///
+fn call_argument<'a>(text: &'a str, name: &str) -> Option<&'a str> {
+    text.strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+/// What the type inference knows beyond the file being looked at.
+///
+#[salsa::input]
+pub struct TypeEnvironment {
+    pub workspace: Workspace,
+    pub rbi_files: Option<RbiFiles>,
+
+    /// Return type annotations (in any syntax `Type::parse` reads), keyed by the method's fully
+    /// qualified name, ex. `String#upcase` → `String`. Declarations in the workspace take
+    /// precedence.
+    #[return_ref]
+    pub return_types: BTreeMap<String, String>,
+
+    /// The methods in `return_types` that are private, ex. `Kernel#puts`.
+    #[return_ref]
+    pub private_methods: BTreeSet<String>,
+}
+
+/// The declared return type annotation of every method the environment knows about, keyed by
+/// fully qualified name. In order of precedence: inline Sorbet `sig`s, YARD `@return`s (including
+/// those of `@!method` and `@!attribute` directives), `.rbi` files and `return_types`.
+///
+#[salsa::tracked]
+pub fn declared_return_types(
+    db: &dyn crate::db::Db,
+    environment: TypeEnvironment,
+) -> BTreeMap<String, String> {
+    let mut sorbet = BTreeMap::new();
+    let mut yard = BTreeMap::new();
+
+    for &file_source in environment.workspace(db).file_sources(db) {
+        let nodes = parse(db, file_source);
+        let by_id = index_by_id(&nodes);
+        let signatures = signatures(db, file_source);
+        let docs = yard_docs(db, file_source);
+
+        for node in nodes.iter() {
+            let Some(name) = method_name(&nodes, &by_id, node) else {
+                continue;
+            };
+
+            if let Some(returns) = signatures.get(&node.id()).map(Sig::returns) {
+                sorbet
+                    .entry(name.clone())
+                    .or_insert_with(|| returns.unwrap_or("void").to_string());
+            }
+
+            if let Some(returns) = docs.get(&node.id()).and_then(return_annotation) {
+                yard.entry(name).or_insert(returns);
+            }
+        }
+
+        for directive in yard_directives(db, file_source) {
+            let Some(returns) = return_annotation(&directive.doc) else {
+                continue;
+            };
+
+            let namespace: Vec<&str> = directive.namespace.iter().map(String::as_str).collect();
+            let name =
+                method_fully_qualified_name(&namespace, &directive.name, directive.singleton);
+
+            // An attribute's type is its reader's return type.
+            if matches!(
+                directive.kind,
+                DirectiveKind::Method | DirectiveKind::Attribute { reader: true, .. }
+            ) {
+                yard.entry(name).or_insert(returns);
+            }
+        }
+    }
+
+    let rbi = environment
+        .rbi_files(db)
+        .map(|rbi_files| rbi_signatures(db, rbi_files))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, sig)| (name, sig.returns().unwrap_or("void").to_string()));
+
+    let mut declared = environment.return_types(db).clone();
+    declared.extend(rbi);
+    declared.extend(yard);
+    declared.extend(sorbet);
+
+    declared
+}
+
+/// The fully qualified name of the method that `node` defines, if it's a `Def` or `Defs`.
+///
+fn method_name(nodes: &[Node], by_id: &HashMap<usize, &Node>, node: &Node) -> Option<String> {
+    let (name, singleton) = match node.properties() {
+        Properties::Def(def) => (&def.name, in_singleton_class(nodes, node)),
+        Properties::Defs(defs) => (&defs.name, true),
+        _ => return None,
+    };
+
+    let owner = method_owner(nodes, by_id, node);
+    let namespace: Vec<&str> = owner.iter().map(String::as_str).collect();
+
+    Some(method_fully_qualified_name(&namespace, name, singleton))
+}
+
+/// A YARD `@return`'s types as one annotation, ex. `String, nil`.
+///
+fn return_annotation(doc: &YardDoc) -> Option<String> {
+    let types = doc.return_types();
+
+    (!types.is_empty()).then(|| types.join(", "))
+}
+
+#[salsa::input]
+pub struct TypeQuery {
+    pub environment: TypeEnvironment,
+    pub file_source: FileSource,
+    pub offset: usize,
+}
+
+/// The type of the innermost expression at the query's `offset`.
+///
+#[salsa::tracked]
+pub fn type_at(db: &dyn crate::db::Db, query: TypeQuery) -> Option<Type> {
+    let file_source = query.file_source(db);
+    let offset = query.offset(db);
+    let nodes = parse(db, file_source);
+
+    let node = nodes
+        .iter()
+        .filter(|node| node.expression_l().contains(offset))
+        .min_by_key(|node| node.expression_l().end() - node.expression_l().begin())?;
+
+    Inference::new(db, query.environment(db), file_source, &nodes).infer(node)
+}
+
+/// Infers the types of a single file's nodes. Queries that need the types of several nodes (ex.
+/// completion) build one of these rather than going through `type_at` for each.
+///
+pub(crate) struct Inference<'a> {
+    nodes: &'a [Node],
+    by_id: HashMap<usize, &'a Node>,
+    locals: Locals,
+    signatures: BTreeMap<usize, Sig>,
+    docs: BTreeMap<usize, YardDoc>,
+    declared: BTreeMap<String, String>,
+    ancestors: BTreeMap<Vec<String>, Ancestors>,
+    defined: BTreeSet<Vec<String>>,
+}
+
+impl<'a> Inference<'a> {
+    pub(crate) fn new(
+        db: &dyn crate::db::Db,
+        environment: TypeEnvironment,
+        file_source: FileSource,
+        nodes: &'a [Node],
+    ) -> Self {
+        let workspace = environment.workspace(db);
+
+        Self {
+            nodes,
+            by_id: index_by_id(nodes),
+            locals: locals(db, file_source),
+            signatures: signatures(db, file_source),
+            docs: yard_docs(db, file_source),
+            declared: declared_return_types(db, environment),
+            ancestors: ancestor_chains(db, workspace),
+            defined: defined_constants(db, workspace),
+        }
+    }
+
+    pub(crate) fn infer(&self, node: &Node) -> Option<Type> {
+        self.infer_at_depth(node, 0)
+    }
+
+    /// The type of `self` where `node` is evaluated.
+    ///
+    pub(crate) fn self_type(&self, node: &Node) -> Type {
+        let path = enclosing_namespace(self.nodes, &self.by_id, node)
+            .unwrap_or_else(|| vec!["Object".to_string()]);
+
+        if in_singleton_context(self.nodes, node) {
+            Type::Singleton(path)
+        } else {
+            Type::Instance(path)
+        }
+    }
+
+    /// Where methods called on `type_` are looked up, nearest first.
+    ///
+    pub(crate) fn method_owners(&self, type_: &Type) -> Vec<Ancestor> {
+        let (path, singleton) = match type_ {
+            Type::Instance(path) => (path, false),
+            Type::Singleton(path) => (path, true),
+            Type::Union(_) => return Vec::new(),
+        };
+
+        if let Some(ancestors) = self.ancestors.get(path) {
+            return if singleton {
+                ancestors.singleton().to_vec()
+            } else {
+                ancestors.instance().to_vec()
+            };
+        }
+
+        // Not defined in the workspace (ex. core classes); assume a plain class.
+        let mut owners = vec![if singleton {
+            Ancestor::Singleton(path.clone())
+        } else {
+            Ancestor::Instance(path.clone())
+        }];
+
+        if singleton {
+            owners.extend(["Class", "Module"].map(|name| Ancestor::Instance(vec![name.into()])));
+        }
+
+        owners.extend(
+            ["Object", "Kernel", "BasicObject"].map(|name| Ancestor::Instance(vec![name.into()])),
+        );
+
+        owners
+    }
+
+    /// The type that calling `method_name` on `receiver` returns.
+    ///
+    pub(crate) fn method_return(&self, receiver: &Type, method_name: &str) -> Option<Type> {
+        let types: Option<Vec<Type>> = receiver
+            .members()
+            .iter()
+            .map(|member| self.member_method_return(member, method_name))
+            .collect();
+
+        Type::union(types?)
+    }
+
+    fn member_method_return(&self, receiver: &Type, method_name: &str) -> Option<Type> {
+        let declared = self.method_owners(receiver).iter().find_map(|owner| {
+            let (path, singleton) = match owner {
+                Ancestor::Instance(path) => (path, false),
+                Ancestor::Singleton(path) => (path, true),
+            };
+            let namespace: Vec<&str> = path.iter().map(String::as_str).collect();
+
+            self.declared.get(&method_fully_qualified_name(
+                &namespace,
+                method_name,
+                singleton,
+            ))
+        });
+
+        // Annotations we can't use (ex. `untyped`) fall through to the built-in fallbacks.
+        if let Some(type_) = declared.and_then(|annotation| Type::parse(annotation, receiver)) {
+            return Some(type_);
+        }
+
+        match (receiver, method_name) {
+            (Type::Singleton(path), "new") => Some(Type::Instance(path.clone())),
+            (Type::Instance(path), "class") => Some(Type::Singleton(path.clone())),
+            _ => None,
+        }
+    }
+
+    fn infer_at_depth(&self, node: &Node, depth: usize) -> Option<Type> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        let infer_id = |id: usize| self.infer_at_depth(self.by_id.get(&id)?, depth + 1);
+
+        match node.properties() {
+            Properties::Int(_) => Some(Type::instance("Integer")),
+            Properties::Float(_) => Some(Type::instance("Float")),
+            Properties::Rational(_) => Some(Type::instance("Rational")),
+            Properties::Complex(_) => Some(Type::instance("Complex")),
+            Properties::Str(_)
+            | Properties::Dstr(_)
+            | Properties::Heredoc(_)
+            | Properties::Xstr(_)
+            | Properties::XHeredoc(_) => Some(Type::instance("String")),
+            Properties::Sym(_) | Properties::Dsym(_) => Some(Type::instance("Symbol")),
+            Properties::Array(_) => Some(Type::instance("Array")),
+            Properties::Hash(_) => Some(Type::instance("Hash")),
+            Properties::Irange(_) | Properties::Erange(_) => Some(Type::instance("Range")),
+            Properties::Regexp(_) => Some(Type::instance("Regexp")),
+            Properties::Nil(_) => Some(Type::nil()),
+            Properties::True(_) => Some(Type::instance("TrueClass")),
+            Properties::False(_) => Some(Type::instance("FalseClass")),
+            Properties::Self_(_) => Some(self.self_type(node)),
+            Properties::Const(const_) => {
+                let path = ConstPath::new(&self.by_id, &const_.name, const_.scope_id)?.resolve(
+                    &lexical_namespace(self.nodes, &self.by_id, node),
+                    &self.defined,
+                );
+
+                Some(Type::Singleton(path))
+            }
+            Properties::Begin(begin) => infer_id(*begin.statement_ids.last()?),
+            Properties::KwBegin(begin) => infer_id(*begin.statement_ids.last()?),
+            Properties::Lvasgn(lvasgn) => infer_id(lvasgn.value_id?),
+            Properties::Ivasgn(ivasgn) => infer_id(ivasgn.value_id?),
+            Properties::If(if_) => Type::union([
+                if_.if_true_id.map_or(Some(Type::nil()), infer_id)?,
+                if_.if_false_id.map_or(Some(Type::nil()), infer_id)?,
+            ]),
+            Properties::IfTernary(if_) => {
+                Type::union([infer_id(if_.if_true_id)?, infer_id(if_.if_false_id)?])
+            }
+            Properties::Lvar(_) => self.infer_lvar(node, depth),
+            Properties::Ivar(ivar) => self.infer_ivar(node, &ivar.name, depth),
+            Properties::Send(send) => {
+                let receiver = match send.recv_id {
+                    Some(id) => infer_id(id)?,
+                    None => self.self_type(node),
+                };
+
+                self.method_return(&receiver, &send.method_name)
+            }
+            Properties::CSend(csend) => {
+                // `&.` skips the call when the receiver is `nil`.
+                let receiver = infer_id(csend.recv_id)?;
+                let receiver = Type::union(
+                    receiver
+                        .members()
+                        .iter()
+                        .filter(|member| **member != Type::nil())
+                        .cloned(),
+                )?;
+
+                Type::union([
+                    self.method_return(&receiver, &csend.method_name)?,
+                    Type::nil(),
+                ])
+            }
+            _ => None,
+        }
+    }
+
+    /// The union of the types of the definitions that reach this use of a local.
+    ///
+    fn infer_lvar(&self, node: &Node, depth: usize) -> Option<Type> {
+        let (variable, use_) = self.locals.variables().iter().find_map(|variable| {
+            variable
+                .uses()
+                .iter()
+                .find(|use_| use_.node_id() == node.id())
+                .map(|use_| (variable, use_))
+        })?;
+
+        if use_.reaching_definitions().is_empty() {
+            return Some(Type::nil());
+        }
+
+        let types: Option<Vec<Type>> = use_
+            .reaching_definitions()
+            .iter()
+            .map(|&index| {
+                let definition = variable.definitions().get(index)?;
+                let definition_node = self.by_id.get(&definition.node_id())?;
+
+                match definition.kind() {
+                    DefinitionKind::Parameter => {
+                        self.infer_parameter(definition_node, variable.name(), depth)
+                    }
+                    _ => self.infer_at_depth(definition_node, depth + 1),
+                }
+            })
+            .collect();
+
+        Type::union(types?)
+    }
+
+    /// A method parameter's type: from the method's `sig` or YARD `@param`, else from its default
+    /// value or kind (ex. `*rest` is an `Array`).
+    ///
+    fn infer_parameter(&self, parameter: &Node, name: &str, depth: usize) -> Option<Type> {
+        let method = self.nodes.iter().find(|node| {
+            let args_id = match node.properties() {
+                Properties::Def(def) => def.args_id,
+                Properties::Defs(defs) => defs.args_id,
+                _ => None,
+            };
+
+            args_id
+                .and_then(|id| self.by_id.get(&id))
+                .map_or(false, |args| match args.properties() {
+                    Properties::Args(args) => args.arg_ids.contains(&parameter.id()),
+                    _ => false,
+                })
+        });
+
+        if let Some(method) = method {
+            let receiver = self.self_type(parameter);
+
+            let sig_type = self
+                .signatures
+                .get(&method.id())
+                .and_then(|sig| sig.param(name))
+                .and_then(|annotation| Type::parse(annotation, &receiver));
+
+            let yard_type = self
+                .docs
+                .get(&method.id())
+                .map(|doc| doc.param_types(name).join(", "))
+                .filter(|annotation| !annotation.is_empty())
+                .and_then(|annotation| Type::parse(&annotation, &receiver));
+
+            if let Some(type_) = sig_type.or(yard_type) {
+                return Some(type_);
+            }
+        }
+
+        let infer_id = |id: usize| self.infer_at_depth(self.by_id.get(&id)?, depth + 1);
+
+        match parameter.properties() {
+            Properties::Optarg(optarg) => infer_id(optarg.default_id),
+            Properties::Kwoptarg(kwoptarg) => infer_id(kwoptarg.default_id),
+            Properties::Restarg(_) => Some(Type::instance("Array")),
+            Properties::Kwrestarg(_) => Some(Type::instance("Hash")),
+            Properties::Blockarg(_) => Some(Type::instance("Proc")),
+            _ => None,
+        }
+    }
+
+    /// The union of the types of every assignment to the instance variable in the same class (and
+    /// on the same side of it: instance or singleton).
+    ///
+    fn infer_ivar(&self, node: &Node, name: &str, depth: usize) -> Option<Type> {
+        let owner = self.self_type(node);
+
+        let types: Option<Vec<Type>> = self
+            .nodes
+            .iter()
+            .filter(|other| match other.properties() {
+                Properties::Ivasgn(ivasgn) => ivasgn.name == name,
+                _ => false,
+            })
+            .filter(|other| self.self_type(other) == owner)
+            .map(|other| self.infer_at_depth(other, depth + 1))
+            .collect();
+
+        Type::union(types?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use ropey::Rope;
+
+    use super::*;
+    use crate::db::Database;
+
+    const CODE: &str = r#"class Repository
+  extend T::Sig
+
+  sig { params(id: Integer).returns(T.nilable(User)) }
+  def find(id); end
+
+  # @return [Array<User>]
+  def all; end
+
+  def initialize
+    @cache = {}
+  end
+
+  def search(query, limit = 10, *rest)
+    users = all
+    user = find(limit)
+    name = user&.name
+    count = users.size
+    cached = @cache
+    repository = Repository.new
+    label = query ? "found" : nil
+    [users, user, name, count, cached, repository, label, rest, self]
+  end
+end
+"#;
+
+    fn types_of(code: &str, return_types: BTreeMap<String, String>) -> BTreeMap<String, String> {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(code));
+        let workspace = Workspace::new(&db, vec![file_source]);
+        let environment = TypeEnvironment::new(&db, workspace, None, return_types, BTreeSet::new());
+
+        // The type of each local (and `self`) in the last line's array.
+        let begin = code.rfind('[').unwrap() + 1;
+        let end = begin + code[begin..].find(']').unwrap();
+        let mut offset = begin;
+
+        code[begin..end]
+            .split(", ")
+            .map(|name| {
+                let query = TypeQuery::new(&db, environment, file_source, offset);
+                offset += name.len() + 2;
+
+                (
+                    name.to_string(),
+                    type_at(&db, query).map_or_else(|| "?".to_string(), |type_| type_.to_string()),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inference_test() {
+        let types = types_of(
+            CODE,
+            BTreeMap::from([
+                ("User#name".to_string(), "String".to_string()),
+                ("Array#size".to_string(), "Integer".to_string()),
+                // Too vague to use, so `Repository.new` still falls back to a `Repository`.
+                ("Class#new".to_string(), "untyped".to_string()),
+            ]),
+        );
+
+        let expected = [
+            ("users", "Array"),
+            ("user", "User | nil"),
+            ("name", "String | nil"),
+            ("count", "Integer"),
+            ("cached", "Hash"),
+            ("repository", "Repository"),
+            ("label", "String | nil"),
+            ("rest", "Array"),
+            ("self", "Repository"),
+        ];
+
+        assert_eq!(
+            expected
+                .iter()
+                .map(|(name, type_)| (name.to_string(), type_.to_string()))
+                .collect::<BTreeMap<_, _>>(),
+            types
+        );
+    }
+
+    #[test]
+    fn parse_test() {
+        let receiver = Type::Singleton(vec!["User".to_string()]);
+        let parse = |annotation: &str| Type::parse(annotation, &receiver).map(|t| t.to_string());
+
+        assert_eq!(Some("String | nil".to_string()), parse("T.nilable(String)"));
+        assert_eq!(Some("String | nil".to_string()), parse("String?"));
+        assert_eq!(Some("String | nil".to_string()), parse("String, nil"));
+        assert_eq!(
+            Some("Integer | String".to_string()),
+            parse("T.any(String, Integer)")
+        );
+        assert_eq!(Some("Array".to_string()), parse("T::Array[String]"));
+        assert_eq!(Some("Hash".to_string()), parse("Hash{Symbol => String}"));
+        assert_eq!(Some("FalseClass | TrueClass".to_string()), parse("bool"));
+        assert_eq!(Some("User".to_string()), parse("instance"));
+        assert_eq!(Some("singleton(User)".to_string()), parse("self"));
+        assert_eq!(
+            Some("singleton(Admin::Role)".to_string()),
+            parse("T.class_of(Admin::Role)")
+        );
+        assert_eq!(None, parse("void"));
+        assert_eq!(None, parse("T.untyped"));
+        assert_eq!(None, parse("String | untyped"));
+    }
+}
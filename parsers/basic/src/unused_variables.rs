@@ -0,0 +1,395 @@
+//! Warnings about local variables, based on `crate::locals`: unused arguments, assignments whose
+//! value is never read, block parameters that shadow an outer local and block-locals (`|a; b|`)
+//! that are never used. Names that start with `_` are meant to be unused, so they're skipped;
+//! where it's safe, the quick-fix is to add that `_`.
+//!
+use std::collections::{HashMap, HashSet};
+
+use lsp_types::{
+    CodeAction, CodeActionKind, Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString,
+    TextEdit, Url, WorkspaceEdit,
+};
+use ropey::Rope;
+
+use crate::{
+    locals::{locals, DefinitionKind, LocalScopeKind, LocalVariable, Locals},
+    node::{index_by_id, Contains, Loc},
+    parser::{parse, FileSource},
+    properties::Properties,
+    Node,
+};
+
+pub(crate) const SOURCE: &str = "ruby-analyzer";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VariableDiagnosticKind {
+    UnusedArgument,
+    UselessAssignment,
+    ShadowingBlockParameter,
+    UnusedShadowarg,
+}
+
+impl VariableDiagnosticKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnusedArgument => "unused_argument",
+            Self::UselessAssignment => "useless_assignment",
+            Self::ShadowingBlockParameter => "shadowing_block_parameter",
+            Self::UnusedShadowarg => "unused_shadowarg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VariableDiagnostic {
+    pub(crate) kind: VariableDiagnosticKind,
+    pub(crate) loc: Loc,
+    pub(crate) diagnostic: Diagnostic,
+
+    /// Prefixes the name with `_`. Not offered when that would change what the code does (ex.
+    /// renaming a keyword argument).
+    pub(crate) fix: Option<TextEdit>,
+}
+
+impl VariableDiagnostic {
+    pub fn kind(&self) -> VariableDiagnosticKind {
+        self.kind
+    }
+
+    /// Location of the variable's name.
+    ///
+    pub fn loc(&self) -> Loc {
+        self.loc
+    }
+
+    pub fn diagnostic(&self) -> &Diagnostic {
+        &self.diagnostic
+    }
+
+    pub fn fix(&self) -> Option<&TextEdit> {
+        self.fix.as_ref()
+    }
+
+    /// The quick-fix as an LSP code action for the file at `uri`.
+    ///
+    pub fn to_code_action(&self, uri: Url) -> Option<CodeAction> {
+        let fix = self.fix.clone()?;
+
+        Some(CodeAction {
+            title: "Prefix with `_`".to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![self.diagnostic.clone()]),
+            edit: Some(WorkspaceEdit {
+                changes: Some([(uri, vec![fix])].into_iter().collect()),
+                ..Default::default()
+            }),
+            is_preferred: Some(true),
+            ..Default::default()
+        })
+    }
+}
+
+#[salsa::tracked]
+pub fn variable_diagnostics(
+    db: &dyn crate::db::Db,
+    file_source: FileSource,
+) -> Vec<VariableDiagnostic> {
+    let code = file_source.code(db);
+    let nodes = parse(db, file_source);
+    let by_id = index_by_id(&nodes);
+    let locals = locals(db, file_source);
+
+    // Assignments that can't be renamed without changing what the code does: `_a += 1` reads a
+    // different (`nil`) variable, and `in {_a:}` matches a different key.
+    let unrenamable: HashSet<usize> = nodes
+        .iter()
+        .flat_map(|node| match node.properties() {
+            Properties::OpAsgn(op_asgn) => vec![op_asgn.recv_id],
+            Properties::OrAsgn(or_asgn) => vec![or_asgn.recv_id],
+            Properties::AndAsgn(and_asgn) => vec![and_asgn.recv_id],
+            Properties::HashPattern(hash_pattern) => hash_pattern.element_ids.clone(),
+            _ => Vec::new(),
+        })
+        .collect();
+
+    let mut diagnostics = Vec::new();
+
+    for variable in locals.variables() {
+        if variable.name().starts_with('_') {
+            continue;
+        }
+
+        let Some(declaration) = variable.declaration() else {
+            continue;
+        };
+
+        let declared_by = by_id
+            .get(&declaration.node_id())
+            .map(|node| node.properties());
+
+        if declaration.kind() == DefinitionKind::Parameter {
+            let scope = &locals.scopes()[variable.scope()];
+
+            if variable.uses().is_empty() {
+                match declared_by {
+                    Some(Properties::Shadowarg(_)) => diagnostics.push(warning(
+                        code,
+                        VariableDiagnosticKind::UnusedShadowarg,
+                        declaration.name_l(),
+                        format!("Unused block-local variable - `{}`.", variable.name()),
+                        true,
+                    )),
+                    // Keyword arguments are part of the method's interface, so can't be renamed.
+                    Some(Properties::Kwarg(_) | Properties::Kwoptarg(_)) => {
+                        if !ignores_arguments(&nodes, &by_id, scope.node_id()) {
+                            diagnostics.push(warning(
+                                code,
+                                VariableDiagnosticKind::UnusedArgument,
+                                declaration.name_l(),
+                                format!("Unused keyword argument - `{}`.", variable.name()),
+                                false,
+                            ));
+                        }
+                    }
+                    _ => {
+                        if !ignores_arguments(&nodes, &by_id, scope.node_id()) {
+                            let what = match scope.kind() {
+                                LocalScopeKind::Block => "block",
+                                _ => "method",
+                            };
+
+                            diagnostics.push(warning(
+                                code,
+                                VariableDiagnosticKind::UnusedArgument,
+                                declaration.name_l(),
+                                format!("Unused {what} argument - `{}`.", variable.name()),
+                                true,
+                            ));
+                        }
+                    }
+                }
+            }
+
+            if let Some(outer) = shadowed(&locals, variable) {
+                let message = format!("Shadowing outer local variable - `{}`.", outer.name());
+
+                diagnostics.push(warning(
+                    code,
+                    VariableDiagnosticKind::ShadowingBlockParameter,
+                    declaration.name_l(),
+                    message,
+                    false,
+                ));
+            }
+        }
+
+        // Assignments whose value is never read.
+        for (index, definition) in variable.definitions().iter().enumerate() {
+            let assignment = matches!(
+                definition.kind(),
+                DefinitionKind::Assignment
+                    | DefinitionKind::MultipleAssignment
+                    | DefinitionKind::PatternMatch
+            );
+
+            let read = variable
+                .uses()
+                .iter()
+                .any(|use_| use_.reaching_definitions().contains(&index));
+
+            if assignment && !read {
+                diagnostics.push(warning(
+                    code,
+                    VariableDiagnosticKind::UselessAssignment,
+                    definition.name_l(),
+                    format!("Useless assignment to variable - `{}`.", variable.name()),
+                    !unrenamable.contains(&definition.node_id()),
+                ));
+            }
+        }
+    }
+
+    diagnostics.sort_by_key(|diagnostic| {
+        (
+            diagnostic.diagnostic.range.start.line,
+            diagnostic.diagnostic.range.start.character,
+        )
+    });
+
+    diagnostics
+}
+
+fn warning(
+    code: &Rope,
+    kind: VariableDiagnosticKind,
+    name_l: Loc,
+    message: String,
+    fixable: bool,
+) -> VariableDiagnostic {
+    let range = name_l.to_lsp_range(code);
+
+    let tags = match kind {
+        VariableDiagnosticKind::ShadowingBlockParameter => None,
+        _ => Some(vec![DiagnosticTag::UNNECESSARY]),
+    };
+
+    VariableDiagnostic {
+        kind,
+        loc: name_l,
+        diagnostic: Diagnostic {
+            range,
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String(kind.code().to_string())),
+            source: Some(SOURCE.to_string()),
+            message,
+            tags,
+            ..Default::default()
+        },
+        fix: fixable.then(|| TextEdit {
+            range: lsp_types::Range::new(range.start, range.start),
+            new_text: "_".to_string(),
+        }),
+    }
+}
+
+/// Methods that are empty (ex. meant to be overridden) or that pass their arguments on with a
+/// bare `super` use all of them, as far as we're concerned.
+///
+fn ignores_arguments(
+    nodes: &[Node],
+    by_id: &HashMap<usize, &Node>,
+    scope_node_id: Option<usize>,
+) -> bool {
+    let Some(scope_node) = scope_node_id.and_then(|id| by_id.get(&id)) else {
+        return false;
+    };
+
+    let body_id = match scope_node.properties() {
+        Properties::Def(def) => def.body_id,
+        Properties::Defs(defs) => defs.body_id,
+        _ => return false,
+    };
+
+    body_id.is_none()
+        || nodes.iter().any(|node| {
+            matches!(node.properties(), Properties::ZSuper(_))
+                && scope_node.expression_l().contains(node.expression_l())
+        })
+}
+
+/// The outer local that the block parameter `variable` hides, if any.
+///
+fn shadowed<'a>(locals: &'a Locals, variable: &LocalVariable) -> Option<&'a LocalVariable> {
+    let scope = &locals.scopes()[variable.scope()];
+
+    if scope.kind() != LocalScopeKind::Block {
+        return None;
+    }
+
+    let offset = scope.loc().begin();
+
+    locals
+        .visible_at(offset)
+        .into_iter()
+        .find(|outer| outer.name() == variable.name() && outer.scope() != variable.scope())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::db::Database;
+
+    const CODE: &str = r#"def run(job, retries, _context, timeout:)
+  result = nil
+  result = job.call
+  items = [1, 2]
+
+  items.each do |job, index; tmp|
+    puts job
+  end
+
+  [result, items]
+end
+
+def placeholder(a, b); end
+"#;
+
+    #[test]
+    fn variable_diagnostics_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let diagnostics = variable_diagnostics(&db, file_source);
+
+        let summary: Vec<(VariableDiagnosticKind, &str, bool)> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let range = diagnostic.diagnostic().range;
+                let line = CODE.lines().nth(range.start.line as usize).unwrap();
+                let name = &line[range.start.character as usize..range.end.character as usize];
+
+                (diagnostic.kind(), name, diagnostic.fix().is_some())
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                (VariableDiagnosticKind::UnusedArgument, "retries", true),
+                (VariableDiagnosticKind::UnusedArgument, "timeout", false),
+                (VariableDiagnosticKind::UselessAssignment, "result", true),
+                (
+                    VariableDiagnosticKind::ShadowingBlockParameter,
+                    "job",
+                    false
+                ),
+                (VariableDiagnosticKind::UnusedArgument, "index", true),
+                (VariableDiagnosticKind::UnusedShadowarg, "tmp", true),
+            ],
+            summary
+        );
+
+        let fix = diagnostics[0].fix().unwrap();
+        assert_eq!("_", fix.new_text);
+        assert_eq!(fix.range.start, diagnostics[0].diagnostic().range.start);
+        assert_eq!(fix.range.start, fix.range.end);
+    }
+
+    #[test]
+    fn unrenamable_assignment_test() {
+        let db = Database::default();
+        let code = r#"def count(items)
+  total = 0
+  total += 1
+
+  case items
+  in {name:}
+    nil
+  end
+end
+"#;
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(code));
+
+        let summary: Vec<(VariableDiagnosticKind, u32, bool)> =
+            variable_diagnostics(&db, file_source)
+                .iter()
+                .map(|diagnostic| {
+                    (
+                        diagnostic.kind(),
+                        diagnostic.diagnostic().range.start.line,
+                        diagnostic.fix().is_some(),
+                    )
+                })
+                .collect();
+
+        // Neither `_total += 1` nor `in {_name:}` would do the same thing.
+        assert_eq!(
+            vec![
+                (VariableDiagnosticKind::UselessAssignment, 2, false),
+                (VariableDiagnosticKind::UselessAssignment, 5, false),
+            ],
+            summary
+        );
+    }
+}
@@ -0,0 +1,10 @@
+use crate::parser::FileSource;
+
+/// All of the source files that make up a project. Queries that need to look beyond a single file
+/// (ex. find-all-references) take this as their input.
+///
+#[salsa::input]
+pub struct Workspace {
+    #[return_ref]
+    pub file_sources: Vec<FileSource>,
+}
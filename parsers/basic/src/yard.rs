@@ -0,0 +1,729 @@
+//! YARD doc comments: the prose plus tags such as `@param [String] name The name.` or
+//! `@return [Integer]`, and the `@!method`/`@!attribute` directives that document methods which
+//! are defined by metaprogramming (so there's no `def` to find). `yard_docs` parses the doc comment
+//! of each node (see `crate::comments::doc_comments`); `yard_directives` finds the directives
+//! anywhere in a file, since they often aren't above anything.
+//!
+use std::collections::BTreeMap;
+
+use ropey::Rope;
+
+use crate::{
+    comments::{comments, doc_comments, last_line, on_own_line, Comment},
+    constants::definition_path,
+    node::{index_by_id, Contains, Loc},
+    parser::{parse, FileSource},
+    properties::Properties,
+};
+
+/// Tags whose type list is followed by a name, ex. `@param [String] name`.
+///
+const NAMED_TAGS: &[&str] = &["param", "yieldparam", "option"];
+
+/// The tags that `YardDoc::to_markdown` lists under a heading, in the order it lists them.
+///
+const LISTED_TAGS: &[(&str, &str)] = &[
+    ("param", "Parameters"),
+    ("option", "Options"),
+    ("yieldparam", "Yield parameters"),
+    ("yieldreturn", "Yield returns"),
+    ("return", "Returns"),
+    ("raise", "Raises"),
+];
+
+/// A single `@tag`, ex. `@param [String, nil] name The user's name.`
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tag {
+    pub(crate) name: String,
+
+    /// ex. `["String", "nil"]`; `Array<String>` and `Hash{Symbol => String}` are kept whole.
+    pub(crate) types: Vec<String>,
+
+    /// The parameter name of `@param`, `@yieldparam` and `@option`, or the title of `@example`.
+    pub(crate) key: Option<String>,
+
+    /// The description, with continuation lines joined; for `@example`, the (dedented) code.
+    pub(crate) text: String,
+}
+
+impl Tag {
+    fn parse(name: &str, rest: &str, body: &str) -> Self {
+        if name == "example" {
+            return Self {
+                name: name.to_string(),
+                types: Vec::new(),
+                key: Some(rest.trim().to_string()).filter(|title| !title.is_empty()),
+                text: body.to_string(),
+            };
+        }
+
+        let (mut types, mut rest) = parse_types(rest);
+        let mut key = None;
+
+        if NAMED_TAGS.contains(&name) {
+            let (word, after) = split_word(rest);
+            key = Some(word.to_string()).filter(|word| !word.is_empty());
+            rest = after;
+
+            // `@param name [String]` is allowed too.
+            if types.is_empty() {
+                (types, rest) = parse_types(rest);
+            }
+        }
+
+        let text = rest
+            .split_whitespace()
+            .chain(body.split_whitespace())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Self {
+            name: name.to_string(),
+            types,
+            key,
+            text,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn types(&self) -> &[String] {
+        &self.types
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut parts = Vec::new();
+
+        if let Some(key) = &self.key {
+            parts.push(format!("`{key}`"));
+        }
+
+        if !self.types.is_empty() {
+            let types: Vec<String> = self
+                .types
+                .iter()
+                .map(|type_| format!("`{type_}`"))
+                .collect();
+            parts.push(format!("({})", types.join(", ")));
+        }
+
+        if !self.text.is_empty() {
+            parts.push(format!("— {}", self.text));
+        }
+
+        format!("- {}", parts.join(" "))
+    }
+}
+
+/// A `@!name ...` directive, before `yard_directives` makes sense of it.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct RawDirective {
+    pub(crate) name: String,
+
+    /// The rest of the directive's line, ex. `find_by_name(name)` for `@!method`.
+    pub(crate) text: String,
+
+    /// The lines indented under the directive.
+    pub(crate) doc: YardDoc,
+}
+
+/// A parsed doc comment.
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct YardDoc {
+    /// Everything that isn't a tag or directive.
+    pub(crate) text: String,
+    pub(crate) tags: Vec<Tag>,
+    pub(crate) directives: Vec<RawDirective>,
+}
+
+impl YardDoc {
+    /// Parses the body of a doc comment (i.e. without the `#`s). A tag runs until the next line
+    /// that's indented no deeper than it is.
+    ///
+    pub fn parse(doc: &str) -> Self {
+        let lines: Vec<&str> = doc.lines().collect();
+
+        let mut text = Vec::new();
+        let mut tags = Vec::new();
+        let mut directives = Vec::new();
+
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let indent = indentation(line);
+
+            let Some(tag) = line.trim_start().strip_prefix('@') else {
+                text.push(line);
+                i += 1;
+                continue;
+            };
+
+            let mut end = i + 1;
+
+            while end < lines.len()
+                && (lines[end].trim().is_empty() || indentation(lines[end]) > indent)
+            {
+                end += 1;
+            }
+
+            while end > i + 1 && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+
+            let body = dedent(&lines[i + 1..end]);
+
+            match tag.strip_prefix('!') {
+                Some(directive) => {
+                    let (name, rest) = split_word(directive);
+
+                    directives.push(RawDirective {
+                        name: name.to_string(),
+                        text: rest.trim().to_string(),
+                        doc: Self::parse(&body),
+                    });
+                }
+                None => {
+                    let (name, rest) = split_word(tag);
+                    tags.push(Tag::parse(name, rest, &body));
+                }
+            }
+
+            i = end;
+        }
+
+        Self {
+            text: text.join("\n").trim().to_string(),
+            tags,
+            directives,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn tags(&self) -> &[Tag] {
+        &self.tags
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty() && self.tags.is_empty()
+    }
+
+    /// The types of the `@param` named `name`.
+    ///
+    pub fn param_types(&self, name: &str) -> &[String] {
+        self.tags
+            .iter()
+            .find(|tag| tag.name == "param" && tag.key.as_deref() == Some(name))
+            .map(|tag| tag.types.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The types of the (first) `@return`.
+    ///
+    pub fn return_types(&self) -> &[String] {
+        self.tags
+            .iter()
+            .find(|tag| tag.name == "return")
+            .map(|tag| tag.types.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// The prose, followed by the tags: parameters, returns and the like as lists, then any
+    /// others, then examples as code blocks.
+    ///
+    pub fn to_markdown(&self) -> String {
+        let mut sections = Vec::new();
+
+        if !self.text.is_empty() {
+            sections.push(self.text.clone());
+        }
+
+        for (name, heading) in LISTED_TAGS {
+            let lines: Vec<String> = self
+                .tags
+                .iter()
+                .filter(|tag| tag.name == *name)
+                .map(Tag::to_markdown)
+                .collect();
+
+            if !lines.is_empty() {
+                sections.push(format!("**{heading}:**\n\n{}", lines.join("\n")));
+            }
+        }
+
+        for tag in &self.tags {
+            if tag.name == "example" || LISTED_TAGS.iter().any(|(name, _)| *name == tag.name) {
+                continue;
+            }
+
+            sections.push(
+                format!("**@{}** {}", tag.name, tag.text)
+                    .trim_end()
+                    .to_string(),
+            );
+        }
+
+        for tag in self.tags.iter().filter(|tag| tag.name == "example") {
+            let title = tag
+                .key
+                .as_deref()
+                .map_or_else(String::new, |title| format!(" {title}"));
+
+            sections.push(format!("**Example:**{title}\n\n```ruby\n{}\n```", tag.text));
+        }
+
+        sections.join("\n\n")
+    }
+}
+
+/// `[String, Array<Integer>] rest` → `(["String", "Array<Integer>"], " rest")`. Returns no types
+/// (and `text` as is) if `text` doesn't start with a type list.
+///
+fn parse_types(text: &str) -> (Vec<String>, &str) {
+    let trimmed = text.trim_start();
+
+    if !trimmed.starts_with('[') {
+        return (Vec::new(), text);
+    }
+
+    let mut depth = 0;
+    let mut types = Vec::new();
+    let mut current = String::new();
+    let mut previous = None;
+
+    for (i, char) in trimmed.char_indices() {
+        match char {
+            '[' if depth == 0 => depth += 1,
+            // `Hash{Symbol => String}`'s `=>` doesn't close anything.
+            '>' if previous == Some('=') => current.push(char),
+            ']' if depth == 1 => {
+                if !current.trim().is_empty() {
+                    types.push(current.trim().to_string());
+                }
+
+                return (types, &trimmed[i + 1..]);
+            }
+            ',' if depth == 1 => {
+                types.push(current.trim().to_string());
+                current.clear();
+            }
+            '[' | '<' | '(' | '{' => {
+                depth += 1;
+                current.push(char);
+            }
+            ']' | '>' | ')' | '}' => {
+                depth -= 1;
+                current.push(char);
+            }
+            _ => current.push(char),
+        }
+
+        previous = Some(char);
+    }
+
+    // Unterminated; not a type list after all.
+    (Vec::new(), text)
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+
+    (&text[..end], &text[end..])
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indentation(line))
+        .min()
+        .unwrap_or_default();
+
+    lines
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The YARD docs of each node that has a doc comment, keyed by the node's ID.
+///
+#[salsa::tracked]
+pub fn yard_docs(db: &dyn crate::db::Db, file_source: FileSource) -> BTreeMap<usize, YardDoc> {
+    doc_comments(db, file_source)
+        .into_iter()
+        .map(|(id, doc)| (id, YardDoc::parse(&doc)))
+        .filter(|(_, doc)| !doc.is_empty())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DirectiveKind {
+    /// `@!method name(params)`
+    Method,
+
+    /// `@!attribute [rw] name`; `[r]` and `[w]` make it read- or write-only.
+    Attribute { reader: bool, writer: bool },
+}
+
+/// A method documented by `@!method` or `@!attribute`, as if it were defined where the directive
+/// is written.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Directive {
+    pub(crate) kind: DirectiveKind,
+    pub(crate) name: String,
+
+    /// ex. `(name, options = {})`; empty for attributes.
+    pub(crate) parameters: String,
+
+    /// The class or module whose body the directive is in.
+    pub(crate) namespace: Vec<String>,
+
+    /// From `@!method self.name` or a `@!scope class` under the directive.
+    pub(crate) singleton: bool,
+    pub(crate) doc: YardDoc,
+    pub(crate) name_l: Loc,
+}
+
+impl Directive {
+    pub fn kind(&self) -> DirectiveKind {
+        self.kind
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn parameters(&self) -> &str {
+        &self.parameters
+    }
+
+    pub fn namespace(&self) -> &[String] {
+        &self.namespace
+    }
+
+    pub fn singleton(&self) -> bool {
+        self.singleton
+    }
+
+    pub fn doc(&self) -> &YardDoc {
+        &self.doc
+    }
+
+    pub fn name_l(&self) -> Loc {
+        self.name_l
+    }
+
+    /// The methods the directive stands for, ex. `name` and `name=` for `@!attribute [rw] name`.
+    ///
+    pub fn method_names(&self) -> Vec<String> {
+        match self.kind {
+            DirectiveKind::Method => vec![self.name.clone()],
+            DirectiveKind::Attribute { reader, writer } => {
+                let mut names = Vec::new();
+
+                if reader {
+                    names.push(self.name.clone());
+                }
+
+                if writer {
+                    names.push(format!("{}=", self.name));
+                }
+
+                names
+            }
+        }
+    }
+}
+
+/// Every `@!method` and `@!attribute` directive in the file, in source order.
+///
+#[salsa::tracked]
+pub fn yard_directives(db: &dyn crate::db::Db, file_source: FileSource) -> Vec<Directive> {
+    let code = file_source.code(db);
+    let nodes = parse(db, file_source);
+    let by_id = index_by_id(&nodes);
+
+    let own_line: Vec<Comment> = comments(db, file_source)
+        .into_iter()
+        .filter(|comment| on_own_line(code, comment))
+        .collect();
+
+    comment_blocks(code, &own_line)
+        .into_iter()
+        .flat_map(|block| {
+            let begin = block.first().map_or(0, |comment| comment.loc.begin);
+            let end = block.last().map_or(0, |comment| comment.loc.end);
+            let body: Vec<String> = block.iter().map(|comment| comment.body()).collect();
+
+            // The innermost class or module that the comments are in.
+            let namespace = nodes
+                .iter()
+                .filter(|node| {
+                    matches!(
+                        node.properties(),
+                        Properties::Class(_) | Properties::Module(_)
+                    ) && node.expression_l().contains(begin)
+                })
+                .max_by_key(|node| node.expression_l().begin)
+                .and_then(|node| definition_path(&nodes, &by_id, node))
+                .unwrap_or_default();
+
+            let block_text = code.byte_slice(begin..end).to_string();
+            let mut search_from = 0;
+
+            YardDoc::parse(&body.join("\n"))
+                .directives
+                .into_iter()
+                .filter_map(|raw| {
+                    let tag = format!("@!{}", raw.name);
+                    let directive = directive(raw, &namespace)?;
+
+                    // Where the name is written, for go-to-definition and symbols.
+                    let tag_at = search_from + block_text[search_from..].find(&tag)?;
+                    let at = tag_at + block_text[tag_at..].find(&directive.name)?;
+                    search_from = at + directive.name.len();
+
+                    Some(Directive {
+                        name_l: Loc {
+                            begin: begin + at,
+                            end: begin + search_from,
+                        },
+                        ..directive
+                    })
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Groups comments on consecutive lines.
+///
+fn comment_blocks<'a>(code: &Rope, comments: &'a [Comment]) -> Vec<&'a [Comment]> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+
+    for i in 1..=comments.len() {
+        let continues = comments.get(i).map_or(false, |comment| {
+            last_line(code, &comments[i - 1]) + 1 == code.byte_to_line(comment.loc.begin)
+        });
+
+        if !continues {
+            blocks.push(&comments[start..i]);
+            start = i;
+        }
+    }
+
+    blocks.retain(|block| !block.is_empty());
+    blocks
+}
+
+fn directive(raw: RawDirective, namespace: &[String]) -> Option<Directive> {
+    let scope_class = raw
+        .doc
+        .directives
+        .iter()
+        .any(|nested| nested.name == "scope" && nested.text == "class");
+
+    let (kind, name, parameters, singleton) = match raw.name.as_str() {
+        "method" => {
+            let signature = raw.text.as_str();
+            let name_end = signature
+                .find(|char: char| char == '(' || char.is_whitespace())
+                .unwrap_or(signature.len());
+            let (name, parameters) = signature.split_at(name_end);
+
+            let (name, singleton) = match name.strip_prefix("self.") {
+                Some(name) => (name, true),
+                None => (name, scope_class),
+            };
+
+            (
+                DirectiveKind::Method,
+                name.to_string(),
+                parameters.trim().to_string(),
+                singleton,
+            )
+        }
+        "attribute" => {
+            let (access, rest) = match raw.text.strip_prefix('[') {
+                Some(rest) => rest.split_once(']')?,
+                None => ("rw", raw.text.as_str()),
+            };
+            let (name, _) = split_word(rest);
+
+            (
+                DirectiveKind::Attribute {
+                    reader: access.contains('r'),
+                    writer: access.contains('w'),
+                },
+                name.to_string(),
+                String::new(),
+                scope_class,
+            )
+        }
+        _ => return None,
+    };
+
+    if name.is_empty() {
+        return None;
+    }
+
+    Some(Directive {
+        kind,
+        name,
+        parameters,
+        namespace: namespace.to_vec(),
+        singleton,
+        doc: raw.doc,
+        name_l: Loc { begin: 0, end: 0 },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn parse_test() {
+        let doc = YardDoc::parse(
+            r#"Finds users by name.
+
+@param [String, nil] name The name to look for,
+  or nil for everyone.
+@param limit [Integer]
+@option options [Hash{Symbol => String}] :order
+@return [Array<User>] the matches
+@raise [ArgumentError]
+@deprecated Use {search} instead.
+@example Find admins
+  User.find_all("admin")"#,
+        );
+
+        assert_eq!("Finds users by name.", doc.text());
+        assert_eq!(["String", "nil"], doc.param_types("name"));
+        assert_eq!(["Integer"], doc.param_types("limit"));
+        assert_eq!(["Array<User>"], doc.return_types());
+        assert_eq!(["Hash{Symbol => String}"], doc.tags()[2].types());
+
+        assert_eq!(
+            r#"Finds users by name.
+
+**Parameters:**
+
+- `name` (`String`, `nil`) — The name to look for, or nil for everyone.
+- `limit` (`Integer`)
+
+**Options:**
+
+- `options` (`Hash{Symbol => String}`) — :order
+
+**Returns:**
+
+- (`Array<User>`) — the matches
+
+**Raises:**
+
+- (`ArgumentError`)
+
+**@deprecated** Use {search} instead.
+
+**Example:** Find admins
+
+```ruby
+User.find_all("admin")
+```"#,
+            doc.to_markdown()
+        );
+    }
+
+    #[test]
+    fn directives_test() {
+        let db = Database::default();
+        let code = r#"module Admin
+  class User
+    # Finds a user by name.
+    # @!method find_by_name(name)
+    #   @param [String] name
+    #   @return [User, nil]
+    #   @!scope class
+    define_finders :name
+
+    # @!attribute [r] email
+    #   @return [String]
+    store :settings, accessors: [:email]
+
+    # @!attribute role
+  end
+end
+"#;
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(code));
+
+        let directives = yard_directives(&db, file_source);
+        let summary: Vec<(Vec<String>, bool, &str)> = directives
+            .iter()
+            .map(|directive| {
+                (
+                    directive.method_names(),
+                    directive.singleton(),
+                    &code[directive.name_l().begin()..directive.name_l().end()],
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                (vec!["find_by_name".to_string()], true, "find_by_name"),
+                (vec!["email".to_string()], false, "email"),
+                (vec!["role".to_string(), "role=".to_string()], false, "role"),
+            ],
+            summary
+        );
+
+        assert_eq!(["Admin", "User"], directives[0].namespace());
+        assert_eq!("(name)", directives[0].parameters());
+        assert_eq!(["User", "nil"], directives[0].doc().return_types());
+
+        // The directives aren't part of `define_finders`'s own docs.
+        let nodes = parse(&db, file_source);
+        let define_finders = nodes
+            .iter()
+            .find(|node| match node.properties() {
+                Properties::Send(send) => send.method_name == "define_finders",
+                _ => false,
+            })
+            .unwrap();
+
+        assert_eq!(
+            Some("Finds a user by name."),
+            yard_docs(&db, file_source)
+                .get(&define_finders.id())
+                .map(YardDoc::text)
+        );
+    }
+}
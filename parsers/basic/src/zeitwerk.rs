@@ -0,0 +1,377 @@
+//! Zeitwerk-style autoloading: under each autoload root (ex. `app/models` or `lib`), a file's path
+//! says which constant it must define, ex. `app/models/admin/user.rb` → `Admin::User`. Code that
+//! relies on this never `require`s those files, so this is how we know where constants come from.
+//! Files that don't define the constant their path calls for are reported.
+//!
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+
+use crate::{
+    constants::definition_path,
+    node::{index_by_id, Loc},
+    parser::{parse, FileSource},
+    properties::Properties,
+    unused_variables::SOURCE,
+    workspace::Workspace,
+    Node,
+};
+
+#[salsa::input]
+pub struct AutoloadConfig {
+    /// Directories whose files are autoloaded, ex. `/project/app/models`. A file belongs to the
+    /// deepest root it's in, so nested roots (ex. `app/models` and `app/models/concerns`) work as
+    /// in Zeitwerk.
+    #[return_ref]
+    pub roots: Vec<PathBuf>,
+
+    /// Files and directories within the roots that aren't autoloaded.
+    #[return_ref]
+    pub ignored: Vec<PathBuf>,
+
+    #[return_ref]
+    pub inflector: Inflector,
+}
+
+/// Turns file and directory names into constant names, ex. `user_session` → `UserSession`.
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Inflector {
+    /// Keyed by lowercase word, ex. `"html"` → `"HTML"`.
+    acronyms: BTreeMap<String, String>,
+
+    /// Whole names that don't follow the rules, ex. `"oauth2"` → `"OAuth2"`.
+    overrides: BTreeMap<String, String>,
+}
+
+impl Inflector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Words that are all caps when camelized, ex. `HTML` makes `html_parser` → `HTMLParser`.
+    ///
+    pub fn with_acronym(mut self, acronym: &str) -> Self {
+        self.acronyms
+            .insert(acronym.to_lowercase(), acronym.to_string());
+        self
+    }
+
+    pub fn with_override(mut self, basename: &str, constant_name: &str) -> Self {
+        self.overrides
+            .insert(basename.to_string(), constant_name.to_string());
+        self
+    }
+
+    pub fn camelize(&self, basename: &str) -> String {
+        if let Some(name) = self.overrides.get(basename) {
+            return name.clone();
+        }
+
+        basename
+            .split('_')
+            .map(|word| match self.acronyms.get(word) {
+                Some(acronym) => acronym.clone(),
+                None => {
+                    let mut chars = word.chars();
+
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect())
+                        .unwrap_or_default()
+                }
+            })
+            .collect()
+    }
+}
+
+#[salsa::input]
+pub struct AutoloadQuery {
+    pub workspace: Workspace,
+    pub config: AutoloadConfig,
+}
+
+/// Which constant each autoloaded file is expected to define.
+///
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AutoloadMap {
+    pub(crate) files: Vec<(FileSource, Vec<String>)>,
+}
+
+impl AutoloadMap {
+    pub fn expected_constant(&self, file_source: FileSource) -> Option<&[String]> {
+        self.files
+            .iter()
+            .find(|(other, _)| *other == file_source)
+            .map(|(_, path)| path.as_slice())
+    }
+
+    /// The file that Zeitwerk would load for the constant `path`, ex. `["Admin", "User"]`.
+    ///
+    pub fn file_for(&self, path: &[&str]) -> Option<FileSource> {
+        self.files
+            .iter()
+            .find(|(_, expected)| expected.iter().eq(path.iter()))
+            .map(|(file_source, _)| *file_source)
+    }
+
+    pub fn files(&self) -> &[(FileSource, Vec<String>)] {
+        &self.files
+    }
+}
+
+#[salsa::tracked]
+pub fn autoload_map(db: &dyn crate::db::Db, query: AutoloadQuery) -> AutoloadMap {
+    let config = query.config(db);
+    let roots = config.roots(db);
+    let ignored = config.ignored(db);
+    let inflector = config.inflector(db);
+
+    let files = query
+        .workspace(db)
+        .file_sources(db)
+        .iter()
+        .filter_map(|&file_source| {
+            let path = file_source.file_uri(db);
+
+            if ignored.iter().any(|ignored| path.starts_with(ignored)) {
+                return None;
+            }
+
+            let expected = expected_constant(path, roots, inflector)?;
+
+            Some((file_source, expected))
+        })
+        .collect();
+
+    AutoloadMap { files }
+}
+
+/// The constant path for the file at `path`, relative to the deepest root that contains it.
+///
+fn expected_constant(path: &Path, roots: &[PathBuf], inflector: &Inflector) -> Option<Vec<String>> {
+    if path.extension().map_or(true, |extension| extension != "rb") {
+        return None;
+    }
+
+    let root = roots
+        .iter()
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())?;
+
+    let relative = path.strip_prefix(root).ok()?.with_extension("");
+
+    relative
+        .components()
+        .map(|component| {
+            component
+                .as_os_str()
+                .to_str()
+                .map(|name| inflector.camelize(name))
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoloadDiagnostic {
+    pub(crate) file_source: FileSource,
+    pub(crate) expected: Vec<String>,
+    pub(crate) diagnostic: Diagnostic,
+}
+
+impl AutoloadDiagnostic {
+    pub fn file_source(&self) -> FileSource {
+        self.file_source
+    }
+
+    pub fn expected(&self) -> &[String] {
+        &self.expected
+    }
+
+    pub fn diagnostic(&self) -> &Diagnostic {
+        &self.diagnostic
+    }
+}
+
+/// Autoloaded files that don't define the constant their path calls for. The diagnostic is put on
+/// the name of the file's outermost definition, if it has one.
+///
+#[salsa::tracked]
+pub fn autoload_diagnostics(
+    db: &dyn crate::db::Db,
+    query: AutoloadQuery,
+) -> Vec<AutoloadDiagnostic> {
+    let map = autoload_map(db, query);
+
+    map.files
+        .iter()
+        .filter_map(|(file_source, expected)| {
+            let nodes = parse(db, *file_source);
+            let by_id = index_by_id(&nodes);
+
+            // (path, name_l) of each definition, outermost first.
+            let mut definitions: Vec<(Vec<String>, Loc)> = nodes
+                .iter()
+                .filter_map(|node| {
+                    Some((
+                        definition_path(&nodes, &by_id, node)?,
+                        name_l(&by_id, node)?,
+                    ))
+                })
+                .collect();
+
+            if definitions.iter().any(|(path, _)| path == expected) {
+                return None;
+            }
+
+            definitions.sort_by_key(|(path, loc)| (path.len(), loc.begin));
+
+            let expected_name = expected.join("::");
+            let file_name = file_source
+                .file_uri(db)
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+
+            let (message, loc) = match definitions.first() {
+                Some((path, loc)) => (
+                    format!(
+                        "Expected `{file_name}` to define `{expected_name}`, but it defines `{}`.",
+                        path.join("::")
+                    ),
+                    *loc,
+                ),
+                None => (
+                    format!("Expected `{file_name}` to define `{expected_name}`."),
+                    Loc { begin: 0, end: 0 },
+                ),
+            };
+
+            Some(AutoloadDiagnostic {
+                file_source: *file_source,
+                expected: expected.clone(),
+                diagnostic: Diagnostic {
+                    range: loc.to_lsp_range(file_source.code(db)),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    code: Some(NumberOrString::String("autoload_mismatch".to_string())),
+                    source: Some(SOURCE.to_string()),
+                    message,
+                    ..Default::default()
+                },
+            })
+        })
+        .collect()
+}
+
+/// Location of the name of the constant that `node` defines.
+///
+fn name_l(by_id: &HashMap<usize, &Node>, node: &Node) -> Option<Loc> {
+    match node.properties() {
+        Properties::Class(class) => Some(*by_id.get(&class.name_id)?.expression_l()),
+        Properties::Module(module) => Some(*by_id.get(&module.name_id)?.expression_l()),
+        Properties::Casgn(casgn) => Some(casgn.name_l),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ropey::Rope;
+
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn camelize_test() {
+        let inflector = Inflector::new()
+            .with_acronym("HTML")
+            .with_override("oauth2_client", "OAuth2Client");
+
+        assert_eq!("UserSession", inflector.camelize("user_session"));
+        assert_eq!("HTMLParser", inflector.camelize("html_parser"));
+        assert_eq!("OAuth2Client", inflector.camelize("oauth2_client"));
+        assert_eq!("V2", inflector.camelize("v2"));
+    }
+
+    #[test]
+    fn autoload_test() {
+        let db = Database::default();
+
+        let file = |path: &str, code: &str| {
+            FileSource::new(&db, PathBuf::from(path), Rope::from_str(code))
+        };
+
+        let user = file(
+            "/project/app/models/admin/user.rb",
+            "module Admin\n  class User\n  end\nend\n",
+        );
+        let compact = file(
+            "/project/app/models/admin/html_report.rb",
+            "class Admin::HTMLReport\nend\n",
+        );
+        let wrong = file("/project/app/models/admin/role.rb", "class Role\nend\n");
+        let concern = file(
+            "/project/app/models/concerns/trackable.rb",
+            "module Trackable\nend\n",
+        );
+        let version = file("/project/lib/my_gem/version.rb", "VERSION = \"1.0\"\n");
+        let task = file("/project/lib/tasks/seed.rb", "puts :seed\n");
+        let script = file("/project/bin/setup.rb", "puts :setup\n");
+
+        let workspace = Workspace::new(
+            &db,
+            vec![user, compact, wrong, concern, version, task, script],
+        );
+        let config = AutoloadConfig::new(
+            &db,
+            vec![
+                PathBuf::from("/project/app/models"),
+                PathBuf::from("/project/app/models/concerns"),
+                PathBuf::from("/project/lib"),
+            ],
+            vec![PathBuf::from("/project/lib/tasks")],
+            Inflector::new().with_acronym("HTML"),
+        );
+        let query = AutoloadQuery::new(&db, workspace, config);
+
+        let map = autoload_map(&db, query);
+        assert_eq!(
+            Some(["Admin".to_string(), "User".to_string()].as_slice()),
+            map.expected_constant(user)
+        );
+        assert_eq!(
+            Some(["Trackable".to_string()].as_slice()),
+            map.expected_constant(concern)
+        );
+        assert_eq!(None, map.expected_constant(task));
+        assert_eq!(None, map.expected_constant(script));
+        assert_eq!(Some(compact), map.file_for(&["Admin", "HTMLReport"]));
+
+        let diagnostics = autoload_diagnostics(&db, query);
+        let summary: Vec<(FileSource, &str)> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                (
+                    diagnostic.file_source(),
+                    diagnostic.diagnostic().message.as_str(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                (
+                    wrong,
+                    "Expected `role.rb` to define `Admin::Role`, but it defines `Role`."
+                ),
+                (
+                    version,
+                    "Expected `version.rb` to define `MyGem::Version`, but it defines `VERSION`."
+                ),
+            ],
+            summary
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! What `crate::parser` reads out of an RBS file. Types are kept as (whitespace-normalized) source
+//! text, ex. `Array[String | Integer]`; that's all hover and completion need from them.
+//!
+use crate::location::Loc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum NamespaceKind {
+    Class,
+    Module,
+    Interface,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Declaration {
+    Namespace(NamespaceDecl),
+
+    /// `class Foo = Bar` or `module Foo = Bar`.
+    NamespaceAlias {
+        kind: NamespaceKind,
+        name: TypeName,
+        target: TypeName,
+        doc: Option<String>,
+        loc: Loc,
+    },
+
+    /// `type name[T] = ...`
+    TypeAlias {
+        name: String,
+        type_params: Vec<String>,
+        type_: String,
+        doc: Option<String>,
+        loc: Loc,
+    },
+
+    /// `Foo: Type` or `Foo::Bar: Type`.
+    Constant {
+        name: TypeName,
+        type_: String,
+        doc: Option<String>,
+        loc: Loc,
+    },
+
+    /// `$stdout: IO`
+    Global {
+        name: String,
+        type_: String,
+        doc: Option<String>,
+        loc: Loc,
+    },
+}
+
+/// A constant path as written, ex. `::Foo::Bar`.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TypeName {
+    pub(crate) absolute: bool,
+    pub(crate) segments: Vec<String>,
+}
+
+impl TypeName {
+    pub(crate) fn parse(text: &str) -> Self {
+        let absolute = text.starts_with("::");
+
+        Self {
+            absolute,
+            segments: text
+                .trim_start_matches("::")
+                .split("::")
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// A `class`, `module` or `interface`.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct NamespaceDecl {
+    pub(crate) kind: NamespaceKind,
+    pub(crate) name: TypeName,
+    pub(crate) type_params: Vec<String>,
+
+    /// `class Foo < Bar[String]`'s `Bar` and its type arguments.
+    pub(crate) superclass: Option<(TypeName, String)>,
+
+    /// `module Foo : _Each[String]`'s `_Each[String]`.
+    pub(crate) self_types: Vec<String>,
+    pub(crate) members: Vec<Member>,
+    pub(crate) doc: Option<String>,
+    pub(crate) name_l: Loc,
+    pub(crate) loc: Loc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub enum Visibility {
+    #[default]
+    Public,
+    Private,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) enum MethodKind {
+    /// `def foo`
+    Instance,
+
+    /// `def self.foo`
+    Singleton,
+
+    /// `def self?.foo`; a `module_function`: a public singleton method, plus a private instance
+    /// method.
+    SingletonInstance,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AttributeKind {
+    Reader,
+    Writer,
+    Accessor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MixinKind {
+    Include,
+    Extend,
+    Prepend,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum VariableKind {
+    /// `@foo: T`
+    Instance,
+
+    /// `self.@foo: T`
+    ClassInstance,
+
+    /// `@@foo: T`
+    Class,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Member {
+    Method {
+        name: String,
+        kind: MethodKind,
+
+        /// One per overload, ex. `(Integer) -> String`; `...` means "and whatever else is
+        /// declared elsewhere".
+        overloads: Vec<String>,
+
+        /// Set by a `private def ...` modifier.
+        visibility: Option<Visibility>,
+        doc: Option<String>,
+        name_l: Loc,
+        loc: Loc,
+    },
+    Attribute {
+        kind: AttributeKind,
+        name: String,
+        singleton: bool,
+        type_: String,
+        visibility: Option<Visibility>,
+        doc: Option<String>,
+        name_l: Loc,
+        loc: Loc,
+    },
+    Variable {
+        kind: VariableKind,
+        name: String,
+        type_: String,
+        loc: Loc,
+    },
+    Mixin {
+        kind: MixinKind,
+        name: TypeName,
+
+        /// `[String]` in `include Enumerable[String]`.
+        type_args: String,
+        loc: Loc,
+    },
+
+    /// `alias new_name old_name`, or `alias self.new_name self.old_name`.
+    Alias {
+        new_name: String,
+        old_name: String,
+        singleton: bool,
+        doc: Option<String>,
+        name_l: Loc,
+        loc: Loc,
+    },
+
+    /// A `public` or `private` on its own, which applies to the members that follow it.
+    Visibility(Visibility),
+
+    /// Nested classes, modules, interfaces, constants and type aliases.
+    Declaration(Declaration),
+}
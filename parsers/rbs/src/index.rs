@@ -0,0 +1,914 @@
+//! Declarations from every RBS file in an `RbsWorkspace`, by the `ScopeGate` they're declared in
+//! (as the Ruby parsers' indexes are) and by fully qualified name. Classes and modules that are
+//! declared in several files (as core classes often are) are merged.
+//!
+//! Interfaces have no `ScopeGate` node of their own, so they're treated as modules.
+//!
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
+
+use ruby_analyzer_basic_parser::scope_gate::{Node as ScopeGateNode, ScopeGate};
+
+use crate::{
+    ast::{
+        AttributeKind, Declaration, Member, MethodKind, MixinKind, NamespaceKind, TypeName,
+        VariableKind, Visibility,
+    },
+    location::Loc,
+    parser::{parse, RbsFile, SyntaxError},
+};
+
+/// All of the `.rbs` files to index, ex. RBS's `core/` and `stdlib/` plus the project's `sig/`.
+///
+#[salsa::input]
+pub struct RbsWorkspace {
+    #[return_ref]
+    pub files: Vec<RbsFile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    Method,
+    Attribute(AttributeKind),
+
+    /// `alias new_name old_name`; holds `old_name`.
+    Alias(String),
+    Constant,
+    TypeAlias,
+    Variable(VariableKind),
+    Global,
+}
+
+/// A single declaration within a namespace.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RbsEntry {
+    pub(crate) kind: EntryKind,
+    pub(crate) name: String,
+    pub(crate) singleton: bool,
+    pub(crate) visibility: Visibility,
+
+    /// For methods, one per overload, ex. `(Integer) -> String`; for everything else, the single
+    /// type (if any).
+    pub(crate) types: Vec<String>,
+    pub(crate) doc: Option<String>,
+    pub(crate) file: RbsFile,
+    pub(crate) name_l: Loc,
+}
+
+impl RbsEntry {
+    pub fn kind(&self) -> &EntryKind {
+        &self.kind
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    /// Does this belong to the class/module object itself (ex. `def self.foo`), as opposed to its
+    /// instances?
+    ///
+    pub fn singleton(&self) -> bool {
+        self.singleton
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    pub fn types(&self) -> &[String] {
+        &self.types
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn file(&self) -> RbsFile {
+        self.file
+    }
+
+    pub fn name_l(&self) -> Loc {
+        self.name_l
+    }
+
+    /// Is this the method `name` (or an attribute or alias that defines it)?
+    ///
+    pub fn defines_method(&self, name: &str) -> bool {
+        match &self.kind {
+            EntryKind::Method | EntryKind::Alias(_) => self.name == name,
+            EntryKind::Attribute(kind) => {
+                let reader = self.name == name;
+                let writer = name.strip_suffix('=') == Some(self.name.as_str());
+
+                match kind {
+                    AttributeKind::Reader => reader,
+                    AttributeKind::Writer => writer,
+                    AttributeKind::Accessor => reader || writer,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// The declaration as RBS, ex. `def self.parse: (String) -> Integer | (Symbol) -> Integer`.
+    ///
+    pub fn signature(&self) -> String {
+        let self_prefix = if self.singleton { "self." } else { "" };
+        let type_ = self.types.join(" | ");
+
+        match &self.kind {
+            EntryKind::Method => format!("def {self_prefix}{}: {type_}", self.name),
+            EntryKind::Attribute(kind) => {
+                let keyword = match kind {
+                    AttributeKind::Reader => "attr_reader",
+                    AttributeKind::Writer => "attr_writer",
+                    AttributeKind::Accessor => "attr_accessor",
+                };
+
+                format!("{keyword} {self_prefix}{}: {type_}", self.name)
+            }
+            EntryKind::Alias(old_name) => {
+                format!("alias {self_prefix}{} {self_prefix}{old_name}", self.name)
+            }
+            EntryKind::TypeAlias => format!("type {} = {type_}", self.name),
+            EntryKind::Variable(VariableKind::ClassInstance) => {
+                format!("self.{}: {type_}", self.name)
+            }
+            EntryKind::Constant | EntryKind::Variable(_) | EntryKind::Global => {
+                format!("{}: {type_}", self.name)
+            }
+        }
+    }
+}
+
+/// A class, module or interface, merged from all of its declarations.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RbsNamespace {
+    pub(crate) kind: NamespaceKind,
+    pub(crate) path: Vec<String>,
+    pub(crate) scope_gate: ScopeGate,
+    pub(crate) type_params: Vec<String>,
+
+    /// Fully qualified.
+    pub(crate) superclass: Option<Vec<String>>,
+    pub(crate) mixins: Vec<(MixinKind, Vec<String>)>,
+
+    /// For `class Foo = Bar`, `Bar`, fully qualified.
+    pub(crate) alias_of: Option<Vec<String>>,
+    pub(crate) doc: Option<String>,
+
+    /// Where it's declared; there may be several.
+    pub(crate) declarations: Vec<(RbsFile, Loc)>,
+}
+
+impl RbsNamespace {
+    pub fn kind(&self) -> NamespaceKind {
+        self.kind
+    }
+
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    pub fn scope_gate(&self) -> &ScopeGate {
+        &self.scope_gate
+    }
+
+    pub fn type_params(&self) -> &[String] {
+        &self.type_params
+    }
+
+    pub fn superclass(&self) -> Option<&[String]> {
+        self.superclass.as_deref()
+    }
+
+    pub fn mixins(&self) -> &[(MixinKind, Vec<String>)] {
+        &self.mixins
+    }
+
+    pub fn alias_of(&self) -> Option<&[String]> {
+        self.alias_of.as_deref()
+    }
+
+    pub fn doc(&self) -> Option<&str> {
+        self.doc.as_deref()
+    }
+
+    pub fn declarations(&self) -> &[(RbsFile, Loc)] {
+        &self.declarations
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RbsIndex {
+    pub(crate) namespaces: BTreeMap<Vec<String>, RbsNamespace>,
+    pub(crate) scoped: BTreeMap<ScopeGate, Vec<RbsEntry>>,
+    pub(crate) errors: Vec<(RbsFile, SyntaxError)>,
+}
+
+impl RbsIndex {
+    pub fn namespace(&self, path: &[&str]) -> Option<&RbsNamespace> {
+        let path: Vec<String> = path.iter().map(ToString::to_string).collect();
+
+        self.namespaces.get(&path)
+    }
+
+    pub fn namespaces(&self) -> impl Iterator<Item = &RbsNamespace> {
+        self.namespaces.values()
+    }
+
+    /// Everything declared directly in `scope_gate` (the top level being the empty one).
+    ///
+    pub fn entries(&self, scope_gate: &ScopeGate) -> &[RbsEntry] {
+        self.scoped
+            .get(scope_gate)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Everything declared directly in the class/module `path`.
+    ///
+    pub fn entries_in(&self, path: &[&str]) -> &[RbsEntry] {
+        match self.namespace(path) {
+            Some(namespace) => self.entries(&namespace.scope_gate),
+            None if path.is_empty() => self.entries(&ScopeGate::default()),
+            None => &[],
+        }
+    }
+
+    /// Syntax errors, by file.
+    ///
+    pub fn errors(&self) -> &[(RbsFile, SyntaxError)] {
+        &self.errors
+    }
+
+    /// The method resolution order of `path` (or of its singleton class), as `(path, singleton)`
+    /// pairs, ex. `String`, `Comparable`, `Object`, `Kernel`, `BasicObject` for `String`.
+    ///
+    pub fn ancestors(&self, path: &[&str], singleton: bool) -> Vec<(Vec<String>, bool)> {
+        let path: Vec<String> = path.iter().map(ToString::to_string).collect();
+        let mut ancestors = Vec::new();
+        let mut seen = BTreeSet::new();
+
+        self.collect_ancestors(path.clone(), singleton, &mut ancestors, &mut seen);
+
+        // A class's singleton class is (eventually) an instance of `Class`.
+        if singleton {
+            let class = match self.namespaces.get(&path).map(|namespace| namespace.kind) {
+                Some(NamespaceKind::Class) => "Class",
+                _ => "Module",
+            };
+
+            self.collect_ancestors(vec![class.to_string()], false, &mut ancestors, &mut seen);
+        }
+
+        ancestors
+    }
+
+    fn collect_ancestors(
+        &self,
+        path: Vec<String>,
+        singleton: bool,
+        ancestors: &mut Vec<(Vec<String>, bool)>,
+        seen: &mut BTreeSet<(Vec<String>, bool)>,
+    ) {
+        if !seen.insert((path.clone(), singleton)) {
+            return;
+        }
+
+        let Some(namespace) = self.namespaces.get(&path) else {
+            ancestors.push((path, singleton));
+            return;
+        };
+
+        if let Some(target) = &namespace.alias_of {
+            self.collect_ancestors(target.clone(), singleton, ancestors, seen);
+            return;
+        }
+
+        let mixins = move |kind: MixinKind| {
+            namespace
+                .mixins
+                .iter()
+                .rev()
+                .filter(move |(mixin_kind, _)| *mixin_kind == kind)
+                .map(|(_, mixin)| mixin.clone())
+        };
+
+        if singleton {
+            ancestors.push((path.clone(), true));
+
+            for mixin in mixins(MixinKind::Extend) {
+                self.collect_ancestors(mixin, false, ancestors, seen);
+            }
+        } else {
+            for mixin in mixins(MixinKind::Prepend) {
+                self.collect_ancestors(mixin, false, ancestors, seen);
+            }
+
+            ancestors.push((path.clone(), false));
+
+            for mixin in mixins(MixinKind::Include) {
+                self.collect_ancestors(mixin, false, ancestors, seen);
+            }
+        }
+
+        if namespace.kind != NamespaceKind::Class {
+            return;
+        }
+
+        let superclass = match &namespace.superclass {
+            Some(superclass) => Some(superclass.clone()),
+            None if path == ["BasicObject"] => None,
+            None => Some(vec!["Object".to_string()]),
+        };
+
+        if let Some(superclass) = superclass {
+            self.collect_ancestors(superclass, singleton, ancestors, seen);
+        }
+    }
+
+    /// The declaration of the method `name` that `path`'s instances (or `path` itself, if
+    /// `singleton`) respond to, looking through its ancestors.
+    ///
+    pub fn find_method(&self, path: &[&str], name: &str, singleton: bool) -> Option<&RbsEntry> {
+        self.ancestors(path, singleton)
+            .into_iter()
+            .find_map(|(ancestor, ancestor_singleton)| {
+                let namespace = self.namespaces.get(&ancestor)?;
+
+                self.entries(&namespace.scope_gate).iter().find(|entry| {
+                    entry.singleton == ancestor_singleton && entry.defines_method(name)
+                })
+            })
+    }
+
+    /// Every method that `path`'s instances (or `path` itself) respond to, nearest first; methods
+    /// that are overridden are left out.
+    ///
+    pub fn methods(&self, path: &[&str], singleton: bool) -> Vec<&RbsEntry> {
+        let mut names = BTreeSet::new();
+        let mut methods = Vec::new();
+
+        for (ancestor, ancestor_singleton) in self.ancestors(path, singleton) {
+            let Some(namespace) = self.namespaces.get(&ancestor) else {
+                continue;
+            };
+
+            for entry in self.entries(&namespace.scope_gate) {
+                let is_method = matches!(
+                    entry.kind,
+                    EntryKind::Method | EntryKind::Attribute(_) | EntryKind::Alias(_)
+                );
+
+                if is_method
+                    && entry.singleton == ancestor_singleton
+                    && names.insert(entry.name.clone())
+                {
+                    methods.push(entry);
+                }
+            }
+        }
+
+        methods
+    }
+
+    /// The return type of every method (of its first overload) and attribute reader, keyed by
+    /// fully qualified name, ex. `String#upcase` → `String` or `File.read` → `String`. This is
+    /// what the Ruby parsers' type inference takes as known return types. Type parameters come
+    /// out as `untyped`, ex. `Array#first` → `untyped?`.
+    ///
+    pub fn return_types(&self) -> BTreeMap<String, String> {
+        let mut return_types = BTreeMap::new();
+
+        for (path, namespace) in &self.namespaces {
+            for entry in self.entries(&namespace.scope_gate) {
+                let mut type_params: Vec<&str> =
+                    namespace.type_params.iter().map(String::as_str).collect();
+
+                let return_type = match &entry.kind {
+                    EntryKind::Method => entry.types.first().and_then(|overload| {
+                        type_params.extend(overload_type_params(overload));
+                        overload_return_type(overload)
+                    }),
+                    EntryKind::Attribute(AttributeKind::Reader | AttributeKind::Accessor) => {
+                        entry.types.first().map(String::as_str)
+                    }
+                    _ => None,
+                };
+
+                let Some(return_type) = return_type else {
+                    continue;
+                };
+
+                let separator = if entry.singleton { '.' } else { '#' };
+
+                return_types
+                    .entry(format!("{}{separator}{}", path.join("::"), entry.name))
+                    .or_insert_with(|| erase_type_params(return_type, &type_params));
+            }
+        }
+
+        return_types
+    }
+
+    /// The fully qualified names of every private method and attribute, ex. `Kernel#puts` (the
+    /// instance half of a `module_function`). These are the ones in `return_types` that can't be
+    /// called with an explicit receiver.
+    ///
+    pub fn private_methods(&self) -> BTreeSet<String> {
+        self.namespaces
+            .iter()
+            .flat_map(|(path, namespace)| {
+                self.entries(&namespace.scope_gate)
+                    .iter()
+                    .filter(|entry| {
+                        matches!(entry.kind, EntryKind::Method | EntryKind::Attribute(_))
+                            && entry.visibility == Visibility::Private
+                    })
+                    .map(move |entry| {
+                        let separator = if entry.singleton { '.' } else { '#' };
+
+                        format!("{}{separator}{}", path.join("::"), entry.name)
+                    })
+            })
+            .collect()
+    }
+}
+
+/// `(Integer) { (String) -> void } -> Array[String]` → `Array[String]`: what follows the first
+/// `->` after the parameters and block. Any later one is part of the return type, ex. in
+/// `() -> ^(Integer) -> String`.
+///
+fn overload_return_type(overload: &str) -> Option<&str> {
+    let mut depth = 0_i32;
+
+    for (i, char) in overload.char_indices() {
+        match char {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            '-' if depth == 0 && overload[i..].starts_with("->") => {
+                return Some(overload[i + 2..].trim());
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// `[T, U < Comparable] (T) -> U` → `["T", "U"]`: the overload's own type parameters.
+///
+fn overload_type_params(overload: &str) -> Vec<&str> {
+    let Some(rest) = overload.trim_start().strip_prefix('[') else {
+        return Vec::new();
+    };
+
+    let mut depth = 0_i32;
+    let end = rest
+        .char_indices()
+        .find(|&(_, char)| {
+            match char {
+                '[' => depth += 1,
+                ']' => depth -= 1,
+                _ => {}
+            }
+            depth < 0
+        })
+        .map_or(rest.len(), |(i, _)| i);
+
+    rest[..end]
+        .split(',')
+        .filter_map(|param| {
+            param
+                .split_whitespace()
+                .find(|word| !matches!(*word, "unchecked" | "in" | "out"))
+        })
+        .collect()
+}
+
+/// Replaces each of `type_params` in `type_` with `untyped`, ex. `Elem?` → `untyped?`, so that
+/// type variables aren't mistaken for constants.
+///
+fn erase_type_params(type_: &str, type_params: &[&str]) -> String {
+    if type_params.is_empty() {
+        return type_.to_string();
+    }
+
+    let mut erased = String::with_capacity(type_.len());
+    let mut rest = type_;
+
+    while let Some(begin) = rest.find(|char: char| char.is_alphanumeric() || char == '_') {
+        let end = rest[begin..]
+            .find(|char: char| !(char.is_alphanumeric() || char == '_'))
+            .map_or(rest.len(), |len| begin + len);
+        let word = &rest[begin..end];
+
+        // `Foo::Elem` is a constant, not the type parameter.
+        let qualified = rest[..begin].ends_with("::") || rest[end..].starts_with("::");
+
+        erased.push_str(&rest[..begin]);
+        erased.push_str(if !qualified && type_params.contains(&word) {
+            "untyped"
+        } else {
+            word
+        });
+        rest = &rest[end..];
+    }
+
+    erased.push_str(rest);
+    erased
+}
+
+#[salsa::tracked]
+pub fn rbs_index(db: &dyn crate::db::Db, workspace: RbsWorkspace) -> Arc<RbsIndex> {
+    let mut builder = Builder::default();
+
+    for &file in workspace.files(db) {
+        let syntax = parse(db, file);
+
+        builder
+            .errors
+            .extend(syntax.errors.iter().map(|error| (file, error.clone())));
+
+        for declaration in &syntax.declarations {
+            builder.declaration(file, &[], declaration);
+        }
+    }
+
+    Arc::new(builder.finish())
+}
+
+/// A name as written, plus the namespace it was written in, to be resolved once every namespace
+/// is known.
+///
+type Unresolved = (TypeName, Vec<String>);
+
+#[derive(Default)]
+struct Builder {
+    namespaces: BTreeMap<Vec<String>, RbsNamespace>,
+    superclasses: BTreeMap<Vec<String>, Unresolved>,
+    mixins: BTreeMap<Vec<String>, Vec<(MixinKind, Unresolved)>>,
+    aliases: BTreeMap<Vec<String>, Unresolved>,
+    entries: BTreeMap<Vec<String>, Vec<RbsEntry>>,
+    errors: Vec<(RbsFile, SyntaxError)>,
+}
+
+impl Builder {
+    fn declaration(&mut self, file: RbsFile, namespace: &[String], declaration: &Declaration) {
+        match declaration {
+            Declaration::Namespace(decl) => {
+                let path = definition(namespace, &decl.name);
+                let kind = decl.kind;
+
+                let entry = self.namespace(path.clone(), kind);
+                entry.declarations.push((file, decl.name_l));
+
+                if entry.type_params.is_empty() {
+                    entry.type_params = decl.type_params.clone();
+                }
+
+                if entry.doc.is_none() {
+                    entry.doc = decl.doc.clone();
+                }
+
+                if let Some((superclass, _)) = &decl.superclass {
+                    self.superclasses
+                        .entry(path.clone())
+                        .or_insert_with(|| (superclass.clone(), namespace.to_vec()));
+                }
+
+                let mut visibility = Visibility::Public;
+
+                for member in &decl.members {
+                    self.member(file, &path, member, &mut visibility);
+                }
+            }
+            Declaration::NamespaceAlias {
+                kind,
+                name,
+                target,
+                doc,
+                loc,
+            } => {
+                let path = definition(namespace, name);
+
+                let entry = self.namespace(path.clone(), *kind);
+                entry.declarations.push((file, *loc));
+                entry.doc = entry.doc.take().or_else(|| doc.clone());
+
+                self.aliases
+                    .insert(path, (target.clone(), namespace.to_vec()));
+            }
+            Declaration::TypeAlias {
+                name,
+                type_params,
+                type_,
+                doc,
+                loc,
+            } => {
+                let type_ = match type_params.as_slice() {
+                    [] => type_.clone(),
+                    params => format!("[{}] {type_}", params.join(", ")),
+                };
+
+                self.entry(
+                    namespace.to_vec(),
+                    RbsEntry {
+                        kind: EntryKind::TypeAlias,
+                        name: name.clone(),
+                        singleton: false,
+                        visibility: Visibility::Public,
+                        types: vec![type_],
+                        doc: doc.clone(),
+                        file,
+                        name_l: *loc,
+                    },
+                );
+            }
+            Declaration::Constant {
+                name,
+                type_,
+                doc,
+                loc,
+            } => {
+                let mut path = definition(namespace, name);
+                let Some(name) = path.pop() else {
+                    return;
+                };
+
+                self.entry(
+                    path,
+                    RbsEntry {
+                        kind: EntryKind::Constant,
+                        name,
+                        singleton: false,
+                        visibility: Visibility::Public,
+                        types: vec![type_.clone()],
+                        doc: doc.clone(),
+                        file,
+                        name_l: *loc,
+                    },
+                );
+            }
+            Declaration::Global {
+                name,
+                type_,
+                doc,
+                loc,
+            } => self.entry(
+                Vec::new(),
+                RbsEntry {
+                    kind: EntryKind::Global,
+                    name: name.clone(),
+                    singleton: false,
+                    visibility: Visibility::Public,
+                    types: vec![type_.clone()],
+                    doc: doc.clone(),
+                    file,
+                    name_l: *loc,
+                },
+            ),
+        }
+    }
+
+    fn member(
+        &mut self,
+        file: RbsFile,
+        path: &[String],
+        member: &Member,
+        visibility: &mut Visibility,
+    ) {
+        match member {
+            Member::Method {
+                name,
+                kind,
+                overloads,
+                visibility: modifier,
+                doc,
+                name_l,
+                ..
+            } => {
+                let entry = |singleton: bool, visibility: Visibility| RbsEntry {
+                    kind: EntryKind::Method,
+                    name: name.clone(),
+                    singleton,
+                    visibility,
+                    types: overloads.clone(),
+                    doc: doc.clone(),
+                    file,
+                    name_l: *name_l,
+                };
+
+                let visibility = modifier.unwrap_or(*visibility);
+
+                match kind {
+                    MethodKind::Instance => self.entry(path.to_vec(), entry(false, visibility)),
+                    MethodKind::Singleton => self.entry(path.to_vec(), entry(true, visibility)),
+                    MethodKind::SingletonInstance => {
+                        self.entry(path.to_vec(), entry(true, Visibility::Public));
+                        self.entry(path.to_vec(), entry(false, Visibility::Private));
+                    }
+                }
+            }
+            Member::Attribute {
+                kind,
+                name,
+                singleton,
+                type_,
+                visibility: modifier,
+                doc,
+                name_l,
+                ..
+            } => self.entry(
+                path.to_vec(),
+                RbsEntry {
+                    kind: EntryKind::Attribute(*kind),
+                    name: name.clone(),
+                    singleton: *singleton,
+                    visibility: modifier.unwrap_or(*visibility),
+                    types: vec![type_.clone()],
+                    doc: doc.clone(),
+                    file,
+                    name_l: *name_l,
+                },
+            ),
+            Member::Variable {
+                kind,
+                name,
+                type_,
+                loc,
+            } => self.entry(
+                path.to_vec(),
+                RbsEntry {
+                    kind: EntryKind::Variable(*kind),
+                    name: name.clone(),
+                    singleton: *kind == VariableKind::ClassInstance,
+                    visibility: Visibility::Private,
+                    types: vec![type_.clone()],
+                    doc: None,
+                    file,
+                    name_l: *loc,
+                },
+            ),
+            Member::Mixin { kind, name, .. } => {
+                self.mixins
+                    .entry(path.to_vec())
+                    .or_default()
+                    .push((*kind, (name.clone(), path.to_vec())));
+            }
+            Member::Alias {
+                new_name,
+                old_name,
+                singleton,
+                doc,
+                name_l,
+                ..
+            } => self.entry(
+                path.to_vec(),
+                RbsEntry {
+                    kind: EntryKind::Alias(old_name.clone()),
+                    name: new_name.clone(),
+                    singleton: *singleton,
+                    visibility: *visibility,
+                    types: Vec::new(),
+                    doc: doc.clone(),
+                    file,
+                    name_l: *name_l,
+                },
+            ),
+            Member::Visibility(new_visibility) => *visibility = *new_visibility,
+            Member::Declaration(declaration) => self.declaration(file, path, declaration),
+        }
+    }
+
+    fn namespace(&mut self, path: Vec<String>, kind: NamespaceKind) -> &mut RbsNamespace {
+        self.namespaces
+            .entry(path.clone())
+            .or_insert_with(|| RbsNamespace {
+                kind,
+                path,
+                scope_gate: ScopeGate::default(),
+                type_params: Vec::new(),
+                superclass: None,
+                mixins: Vec::new(),
+                alias_of: None,
+                doc: None,
+                declarations: Vec::new(),
+            })
+    }
+
+    fn entry(&mut self, path: Vec<String>, entry: RbsEntry) {
+        self.entries.entry(path).or_default().push(entry);
+    }
+
+    fn finish(mut self) -> RbsIndex {
+        let defined: BTreeSet<Vec<String>> = self.namespaces.keys().cloned().collect();
+
+        for (path, (name, namespace)) in std::mem::take(&mut self.superclasses) {
+            let superclass = resolve(&name, &namespace, &defined);
+
+            if let Some(entry) = self.namespaces.get_mut(&path) {
+                entry.superclass = Some(superclass);
+            }
+        }
+
+        for (path, mixins) in std::mem::take(&mut self.mixins) {
+            let mixins = mixins
+                .into_iter()
+                .map(|(kind, (name, namespace))| (kind, resolve(&name, &namespace, &defined)))
+                .collect();
+
+            if let Some(entry) = self.namespaces.get_mut(&path) {
+                entry.mixins = mixins;
+            }
+        }
+
+        for (path, (name, namespace)) in std::mem::take(&mut self.aliases) {
+            let target = resolve(&name, &namespace, &defined);
+
+            if let Some(entry) = self.namespaces.get_mut(&path) {
+                entry.alias_of = Some(target);
+            }
+        }
+
+        let scope_gates: BTreeMap<Vec<String>, ScopeGate> = self
+            .namespaces
+            .keys()
+            .map(|path| (path.clone(), self.scope_gate(path)))
+            .collect();
+
+        for (path, namespace) in self.namespaces.iter_mut() {
+            namespace.scope_gate = scope_gates[path].clone();
+        }
+
+        let scoped = std::mem::take(&mut self.entries)
+            .into_iter()
+            .map(|(path, entries)| {
+                let scope_gate = scope_gates
+                    .get(&path)
+                    .cloned()
+                    .unwrap_or_else(|| self.scope_gate(&path));
+
+                (scope_gate, entries)
+            })
+            .collect();
+
+        RbsIndex {
+            namespaces: self.namespaces,
+            scoped,
+            errors: self.errors,
+        }
+    }
+
+    /// ex. `[Module("Foo"), Class("Bar")]` for `Foo::Bar`. Namespaces that were never declared
+    /// are taken to be modules.
+    ///
+    fn scope_gate(&self, path: &[String]) -> ScopeGate {
+        ScopeGate::new(
+            (1..=path.len())
+                .map(|length| {
+                    let name = path[length - 1].clone();
+
+                    match self.namespaces.get(&path[..length]).map(|ns| ns.kind) {
+                        Some(NamespaceKind::Class) => ScopeGateNode::Class(name),
+                        _ => ScopeGateNode::Module(name),
+                    }
+                })
+                .collect(),
+        )
+    }
+}
+
+/// The fully qualified name of something declared as `name` within `namespace`.
+///
+fn definition(namespace: &[String], name: &TypeName) -> Vec<String> {
+    if name.absolute {
+        return name.segments.clone();
+    }
+
+    namespace
+        .iter()
+        .chain(name.segments.iter())
+        .cloned()
+        .collect()
+}
+
+/// Resolves `name`, as referred to from within `namespace`: the innermost enclosing namespace in
+/// which it's defined wins; if there's none, it's taken as top-level.
+///
+fn resolve(name: &TypeName, namespace: &[String], defined: &BTreeSet<Vec<String>>) -> Vec<String> {
+    if name.absolute {
+        return name.segments.clone();
+    }
+
+    (0..=namespace.len())
+        .rev()
+        .map(|depth| definition(&namespace[..depth], name))
+        .find(|candidate| defined.contains(candidate))
+        .unwrap_or_else(|| name.segments.clone())
+}
@@ -0,0 +1,23 @@
+//! Parses RBS signature files (`.rbs`) and indexes their declarations by the same `ScopeGate`s and
+//! fully qualified names that the Ruby parsers use, so a Ruby class and its signatures can be
+//! looked up together.
+//!
+pub(crate) mod ast;
+pub mod db;
+pub mod index;
+pub mod location;
+pub mod parser;
+
+pub use self::{
+    ast::{AttributeKind, MixinKind, NamespaceKind, VariableKind, Visibility},
+    db::{Database, Db},
+    index::{EntryKind, RbsEntry, RbsIndex, RbsNamespace},
+};
+
+#[salsa::jar(db = crate::db::Db)]
+pub struct Jar(
+    crate::parser::RbsFile,
+    crate::parser::parse,
+    crate::index::RbsWorkspace,
+    crate::index::rbs_index,
+);
@@ -0,0 +1,17 @@
+/// A span of an RBS file, as byte offsets.
+///
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Loc {
+    pub(crate) begin: usize,
+    pub(crate) end: usize,
+}
+
+impl Loc {
+    pub fn begin(&self) -> usize {
+        self.begin
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+}
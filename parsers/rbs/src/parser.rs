@@ -0,0 +1,939 @@
+//! A hand-written, error-tolerant parser for RBS. It reads declarations and members fully, but only
+//! finds the extent of types (by balancing brackets), keeping their text; see `crate::ast`.
+//! Anything it can't make sense of is reported in `RbsSyntax::errors` and skipped, up to the end of
+//! the line.
+//!
+use std::{path::PathBuf, sync::Arc};
+
+use tracing::debug;
+
+use crate::{
+    ast::{
+        AttributeKind, Declaration, Member, MethodKind, MixinKind, NamespaceDecl, NamespaceKind,
+        TypeName, VariableKind, Visibility,
+    },
+    location::Loc,
+};
+
+/// The path and contents of an `.rbs` file.
+///
+#[salsa::input]
+pub struct RbsFile {
+    #[id]
+    #[return_ref]
+    pub path: PathBuf,
+
+    #[return_ref]
+    pub text: String,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RbsSyntax {
+    pub(crate) declarations: Vec<Declaration>,
+    pub(crate) errors: Vec<SyntaxError>,
+}
+
+impl RbsSyntax {
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SyntaxError {
+    pub(crate) message: String,
+    pub(crate) loc: Loc,
+}
+
+impl SyntaxError {
+    pub fn message(&self) -> &str {
+        self.message.as_ref()
+    }
+
+    pub fn loc(&self) -> Loc {
+        self.loc
+    }
+}
+
+#[salsa::tracked]
+pub fn parse(db: &dyn crate::db::Db, file: RbsFile) -> Arc<RbsSyntax> {
+    debug!("Parsing {}", file.path(db).display());
+
+    Arc::new(Parser::new(file.text(db)).parse())
+}
+
+type Result<T> = std::result::Result<T, SyntaxError>;
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+
+    /// Comment lines right before the current position; the doc of the next declaration.
+    doc: Vec<&'a str>,
+    errors: Vec<SyntaxError>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(src: &'a str) -> Self {
+        Self {
+            src,
+            pos: 0,
+            doc: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn parse(mut self) -> RbsSyntax {
+        let mut declarations = Vec::new();
+
+        loop {
+            self.skip_trivia();
+
+            if self.at_end() {
+                break;
+            }
+
+            if self.eat_keyword("use") {
+                self.skip_line();
+                continue;
+            }
+
+            let doc = self.take_doc();
+
+            match self.declaration(doc) {
+                Ok(Some(declaration)) => declarations.push(declaration),
+                Ok(None) => {
+                    let error = self.error("Expected a declaration");
+                    self.recover(error);
+                }
+                Err(error) => self.recover(error),
+            }
+        }
+
+        RbsSyntax {
+            declarations,
+            errors: self.errors,
+        }
+    }
+
+    // ------------------------------------------------------------------------------------------
+    // Declarations and members
+
+    /// `Ok(None)` if what's next isn't a declaration.
+    ///
+    fn declaration(&mut self, doc: Option<String>) -> Result<Option<Declaration>> {
+        self.skip_annotations();
+        let begin = self.pos;
+
+        let kind = if self.eat_keyword("class") {
+            NamespaceKind::Class
+        } else if self.eat_keyword("module") {
+            NamespaceKind::Module
+        } else if self.eat_keyword("interface") {
+            NamespaceKind::Interface
+        } else if self.eat_keyword("type") {
+            return self.type_alias(begin, doc).map(Some);
+        } else if self.peek() == Some('$') {
+            let name = self.word();
+            self.expect(":")?;
+            let type_ = self.type_(false)?;
+
+            return Ok(Some(Declaration::Global {
+                name,
+                type_,
+                doc,
+                loc: self.loc_from(begin),
+            }));
+        } else if self.peek().map_or(false, |c| c.is_ascii_uppercase()) || self.peeks("::") {
+            let name = TypeName::parse(&self.const_path()?);
+            self.expect(":")?;
+            let type_ = self.type_(false)?;
+
+            return Ok(Some(Declaration::Constant {
+                name,
+                type_,
+                doc,
+                loc: self.loc_from(begin),
+            }));
+        } else {
+            self.pos = begin;
+            return Ok(None);
+        };
+
+        self.skip_trivia();
+        let name_begin = self.pos;
+        let name = TypeName::parse(&self.const_path()?);
+        let name_l = self.loc_from(name_begin);
+
+        // Whitespace is only skipped when something follows on the same declaration, so that
+        // comments before the first member stay its doc.
+        let checkpoint = self.pos;
+        self.skip_trivia();
+
+        if kind != NamespaceKind::Interface && self.eat("=") {
+            self.skip_trivia();
+            let target = TypeName::parse(&self.const_path()?);
+
+            return Ok(Some(Declaration::NamespaceAlias {
+                kind,
+                name,
+                target,
+                doc,
+                loc: self.loc_from(begin),
+            }));
+        }
+
+        self.pos = checkpoint;
+        let type_params = self.type_params()?;
+
+        let mut superclass = None;
+        let mut self_types = Vec::new();
+
+        let checkpoint = self.pos;
+        self.skip_trivia();
+
+        if kind == NamespaceKind::Class && self.eat("<") {
+            self.skip_trivia();
+            let name = TypeName::parse(&self.const_path()?);
+            let args = self.optional_group('[')?;
+            superclass = Some((name, args));
+        } else if kind == NamespaceKind::Module && self.peek() == Some(':') {
+            self.pos += 1;
+
+            loop {
+                self_types.push(self.type_(true)?);
+
+                let checkpoint = self.pos;
+                self.skip_trivia();
+
+                if !self.eat(",") {
+                    self.pos = checkpoint;
+                    break;
+                }
+            }
+        } else {
+            self.pos = checkpoint;
+        }
+
+        let members = self.members()?;
+
+        Ok(Some(Declaration::Namespace(NamespaceDecl {
+            kind,
+            name,
+            type_params,
+            superclass,
+            self_types,
+            members,
+            doc,
+            name_l,
+            loc: self.loc_from(begin),
+        })))
+    }
+
+    fn type_alias(&mut self, begin: usize, doc: Option<String>) -> Result<Declaration> {
+        self.skip_trivia();
+        let name = self.word();
+
+        if name.is_empty() {
+            return Err(self.error("Expected the name of the type alias"));
+        }
+
+        let type_params = self.type_params()?;
+        self.expect("=")?;
+        let type_ = self.type_(false)?;
+
+        Ok(Declaration::TypeAlias {
+            name,
+            type_params,
+            type_,
+            doc,
+            loc: self.loc_from(begin),
+        })
+    }
+
+    /// The members of a class, module or interface, up to and including its `end`.
+    ///
+    fn members(&mut self) -> Result<Vec<Member>> {
+        let mut members = Vec::new();
+
+        loop {
+            self.skip_trivia();
+
+            if self.at_end() {
+                return Err(self.error("Expected `end`"));
+            }
+
+            if self.eat_keyword("end") {
+                return Ok(members);
+            }
+
+            match self.member() {
+                Ok(member) => members.push(member),
+                Err(error) => self.recover(error),
+            }
+        }
+    }
+
+    fn member(&mut self) -> Result<Member> {
+        let begin = self.pos;
+        let doc = self.take_doc();
+        self.skip_annotations();
+
+        let visibility = if self.eat_keyword("public") {
+            Some(Visibility::Public)
+        } else if self.eat_keyword("private") {
+            Some(Visibility::Private)
+        } else {
+            None
+        };
+
+        if let Some(visibility) = visibility {
+            self.skip_spaces();
+
+            // On its own line, it applies to what follows.
+            if self.at_end() || self.peek() == Some('\n') || self.peek() == Some('#') {
+                return Ok(Member::Visibility(visibility));
+            }
+        }
+
+        if self.eat_keyword("def") {
+            return self.method(begin, visibility, doc);
+        }
+
+        for (keyword, kind) in [
+            ("attr_reader", AttributeKind::Reader),
+            ("attr_writer", AttributeKind::Writer),
+            ("attr_accessor", AttributeKind::Accessor),
+        ] {
+            if self.eat_keyword(keyword) {
+                return self.attribute(begin, kind, visibility, doc);
+            }
+        }
+
+        if visibility.is_some() {
+            return Err(self.error("Expected `def` or `attr_*` after the visibility modifier"));
+        }
+
+        for (keyword, kind) in [
+            ("include", MixinKind::Include),
+            ("extend", MixinKind::Extend),
+            ("prepend", MixinKind::Prepend),
+        ] {
+            if self.eat_keyword(keyword) {
+                self.skip_trivia();
+                let name = TypeName::parse(&self.const_path()?);
+                let type_args = self.optional_group('[')?;
+
+                return Ok(Member::Mixin {
+                    kind,
+                    name,
+                    type_args,
+                    loc: self.loc_from(begin),
+                });
+            }
+        }
+
+        if self.eat_keyword("alias") {
+            self.skip_trivia();
+            let singleton = self.eat("self.");
+            let name_begin = self.pos;
+            let new_name = self.method_name();
+            let name_l = self.loc_from(name_begin);
+
+            self.skip_trivia();
+            self.eat("self.");
+            let old_name = self.method_name();
+
+            if new_name.is_empty() || old_name.is_empty() {
+                return Err(self.error("Expected `alias new_name old_name`"));
+            }
+
+            return Ok(Member::Alias {
+                new_name,
+                old_name,
+                singleton,
+                doc,
+                name_l,
+                loc: self.loc_from(begin),
+            });
+        }
+
+        let variable_kind = if self.eat("self.@") {
+            self.pos -= 1;
+            Some(VariableKind::ClassInstance)
+        } else if self.peeks("@@") {
+            Some(VariableKind::Class)
+        } else if self.peeks("@") {
+            Some(VariableKind::Instance)
+        } else {
+            None
+        };
+
+        if let Some(kind) = variable_kind {
+            let name = self.word();
+            self.expect(":")?;
+            let type_ = self.type_(false)?;
+
+            return Ok(Member::Variable {
+                kind,
+                name,
+                type_,
+                loc: self.loc_from(begin),
+            });
+        }
+
+        // Nested classes, modules, constants, etc.
+        match self.declaration(doc)? {
+            Some(declaration) => Ok(Member::Declaration(declaration)),
+            None => Err(self.error("Expected a member")),
+        }
+    }
+
+    fn method(
+        &mut self,
+        begin: usize,
+        visibility: Option<Visibility>,
+        doc: Option<String>,
+    ) -> Result<Member> {
+        self.skip_trivia();
+
+        let kind = if self.eat("self?.") {
+            MethodKind::SingletonInstance
+        } else if self.eat("self.") {
+            MethodKind::Singleton
+        } else {
+            MethodKind::Instance
+        };
+
+        let name_begin = self.pos;
+        let name = self.method_name();
+        let name_l = self.loc_from(name_begin);
+
+        if name.is_empty() {
+            return Err(self.error("Expected a method name"));
+        }
+
+        self.expect(":")?;
+
+        let mut overloads = Vec::new();
+
+        loop {
+            self.skip_trivia();
+            self.skip_annotations();
+
+            if self.eat("...") {
+                overloads.push("...".to_string());
+            } else {
+                overloads.push(self.method_type()?);
+            }
+
+            let checkpoint = self.pos;
+            self.skip_trivia();
+
+            if !self.eat("|") {
+                self.pos = checkpoint;
+                break;
+            }
+        }
+
+        Ok(Member::Method {
+            name,
+            kind,
+            overloads,
+            visibility,
+            doc,
+            name_l,
+            loc: self.loc_from(begin),
+        })
+    }
+
+    fn attribute(
+        &mut self,
+        begin: usize,
+        kind: AttributeKind,
+        visibility: Option<Visibility>,
+        doc: Option<String>,
+    ) -> Result<Member> {
+        self.skip_trivia();
+        let singleton = self.eat("self.");
+
+        let name_begin = self.pos;
+        let name = self.word();
+        let name_l = self.loc_from(name_begin);
+
+        if name.is_empty() {
+            return Err(self.error("Expected the name of the attribute"));
+        }
+
+        // The instance variable it's stored in, ex. `(@raw_name)`, or `()` for none.
+        self.optional_group('(')?;
+
+        self.expect(":")?;
+        let type_ = self.type_(false)?;
+
+        Ok(Member::Attribute {
+            kind,
+            name,
+            singleton,
+            type_,
+            visibility,
+            doc,
+            name_l,
+            loc: self.loc_from(begin),
+        })
+    }
+
+    // ------------------------------------------------------------------------------------------
+    // Types
+
+    /// `[T, out U < Bound]` → `["T", "U"]`; nothing if there's no `[`.
+    ///
+    fn type_params(&mut self) -> Result<Vec<String>> {
+        let group = self.optional_group('[')?;
+
+        let Some(inner) = group.strip_prefix('[').and_then(|g| g.strip_suffix(']')) else {
+            return Ok(Vec::new());
+        };
+
+        Ok(split_top_level(inner, ',')
+            .into_iter()
+            .filter_map(|param| {
+                param
+                    .split_whitespace()
+                    .find(|word| !matches!(*word, "unchecked" | "in" | "out"))
+                    .map(ToString::to_string)
+            })
+            .collect())
+    }
+
+    /// `[T] (params) ?{ block } -> ReturnType`
+    ///
+    fn method_type(&mut self) -> Result<String> {
+        let begin = self.pos;
+
+        self.skip_trivia();
+        self.optional_group('[')?;
+        self.skip_trivia();
+        self.optional_group('(')?;
+        self.skip_trivia();
+
+        if self.eat("?") {
+            self.skip_trivia();
+        }
+
+        self.optional_group('{')?;
+        self.expect("->")?;
+
+        // Return types can't be unions without parentheses; `|` starts the next overload.
+        self.type_(true)?;
+
+        Ok(normalize(&self.src[begin..self.pos]))
+    }
+
+    /// Finds the extent of a type, returning its text.
+    ///
+    fn type_(&mut self, return_type: bool) -> Result<String> {
+        self.skip_trivia();
+        let begin = self.pos;
+
+        loop {
+            self.primary_type()?;
+
+            // `String?`
+            self.eat("?");
+
+            let end = self.pos;
+            self.skip_trivia();
+
+            if (!return_type && self.eat("|")) || self.eat("&") {
+                continue;
+            }
+
+            self.pos = end;
+            break;
+        }
+
+        Ok(normalize(&self.src[begin..self.pos]))
+    }
+
+    fn primary_type(&mut self) -> Result<()> {
+        self.skip_trivia();
+
+        match self.peek() {
+            Some('(' | '[' | '{') => {
+                self.group()?;
+            }
+            Some('^') => {
+                self.pos += 1;
+                self.skip_trivia();
+                self.optional_group('(')?;
+                self.skip_trivia();
+
+                if self.eat("?") {
+                    self.skip_trivia();
+                }
+
+                self.optional_group('{')?;
+                self.expect("->")?;
+                self.type_(true)?;
+            }
+            Some('"' | '\'') => self.string()?,
+            Some(':') if !self.peeks("::") => {
+                self.pos += 1;
+
+                if matches!(self.peek(), Some('"' | '\'')) {
+                    self.string()?;
+                } else {
+                    self.method_name();
+                }
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' => {
+                self.pos += 1;
+                self.take_while(|c| c.is_ascii_digit() || c == '_');
+            }
+            _ => {
+                let name = self.const_path_or_word();
+
+                if name.is_empty() {
+                    return Err(self.error("Expected a type"));
+                }
+
+                // Type arguments (ex. `Array[String]`) and `singleton(Foo)` follow the name
+                // directly.
+                match self.peek() {
+                    Some('[') => {
+                        self.group()?;
+                    }
+                    Some('(') if name == "singleton" => {
+                        self.group()?;
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // ------------------------------------------------------------------------------------------
+    // Lexing
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.src.len()
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn peeks(&self, text: &str) -> bool {
+        self.rest().starts_with(text)
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if self.peeks(text) {
+            self.pos += text.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<()> {
+        self.skip_trivia();
+
+        if self.eat(text) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected `{text}`")))
+        }
+    }
+
+    /// Eats `keyword` if it's next, as a whole word.
+    ///
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if !self.peeks(keyword) {
+            return false;
+        }
+
+        let follows = self.rest()[keyword.len()..].chars().next();
+
+        if !follows.map_or(false, is_word_char) {
+            self.pos += keyword.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let begin = self.pos;
+        let length: usize = self
+            .rest()
+            .chars()
+            .take_while(|c| predicate(*c))
+            .map(char::len_utf8)
+            .sum();
+
+        self.pos += length;
+        &self.src[begin..self.pos]
+    }
+
+    /// Identifiers, including sigils (`@`, `@@`, `$`) and a trailing `?`/`!`.
+    ///
+    fn word(&mut self) -> String {
+        let begin = self.pos;
+        self.take_while(|c| c == '@' || c == '$');
+        self.take_while(is_word_char);
+
+        if matches!(self.peek(), Some('?' | '!')) && self.pos > begin {
+            self.pos += 1;
+        }
+
+        self.src[begin..self.pos].to_string()
+    }
+
+    /// A method name, which may be an operator (ex. `<=>` or `[]=`) or backquoted.
+    ///
+    fn method_name(&mut self) -> String {
+        if self.eat("`") {
+            let name = self.take_while(|c| c != '`').to_string();
+            self.eat("`");
+            return name;
+        }
+
+        match self.peek() {
+            Some(c) if is_word_char(c) => {
+                let mut name = self.word();
+
+                // Setters, ex. `name=`, but not `name: ...` or `==`.
+                if self.peeks("=") && !self.peeks("==") && !self.peeks("=>") {
+                    self.pos += 1;
+                    name.push('=');
+                }
+
+                name
+            }
+            _ => self
+                .take_while(|c| !c.is_whitespace() && c != ':' && !is_word_char(c))
+                .to_string(),
+        }
+    }
+
+    /// `Foo::Bar`, `::Foo` or `_Interface`.
+    ///
+    fn const_path(&mut self) -> Result<String> {
+        let path = self.const_path_or_word();
+
+        if path.is_empty() {
+            Err(self.error("Expected a constant"))
+        } else {
+            Ok(path)
+        }
+    }
+
+    fn const_path_or_word(&mut self) -> String {
+        let begin = self.pos;
+
+        loop {
+            self.eat("::");
+
+            if self.take_while(is_word_char).is_empty() {
+                break;
+            }
+
+            if !(self.peeks("::") && self.rest()[2..].starts_with(is_word_char)) {
+                break;
+            }
+        }
+
+        self.src[begin..self.pos].to_string()
+    }
+
+    fn string(&mut self) -> Result<()> {
+        let Some(quote) = self.peek() else {
+            return Err(self.error("Expected a string"));
+        };
+
+        self.pos += 1;
+
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string")),
+                Some('\\') => {
+                    self.pos += 1 + self.rest()[1..].chars().next().map_or(0, char::len_utf8)
+                }
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(c) => self.pos += c.len_utf8(),
+            }
+        }
+    }
+
+    /// A bracketed group, ex. `(Integer, ?String) `, with its brackets balanced.
+    ///
+    fn group(&mut self) -> Result<&'a str> {
+        let begin = self.pos;
+        let mut stack: Vec<char> = Vec::new();
+
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unbalanced brackets")),
+                Some(open @ ('(' | '[' | '{')) => {
+                    stack.push(closing(open));
+                    self.pos += 1;
+                }
+                Some(close @ (')' | ']' | '}')) => {
+                    if stack.pop() != Some(close) {
+                        return Err(self.error("Unbalanced brackets"));
+                    }
+
+                    self.pos += 1;
+
+                    if stack.is_empty() {
+                        return Ok(&self.src[begin..self.pos]);
+                    }
+                }
+                Some('"' | '\'') => self.string()?,
+                Some('#') => self.skip_line(),
+                Some(c) => self.pos += c.len_utf8(),
+            }
+        }
+    }
+
+    /// A group that starts with `open`, if that's next; empty otherwise.
+    ///
+    fn optional_group(&mut self, open: char) -> Result<String> {
+        if self.peek() == Some(open) {
+            Ok(normalize(self.group()?))
+        } else {
+            Ok(String::new())
+        }
+    }
+
+    /// `%a{...}` (or `%a(...)`, etc.) annotations, which we don't use.
+    ///
+    fn skip_annotations(&mut self) {
+        while self.peeks("%a") {
+            self.pos += 2;
+
+            if self.group().is_err() {
+                self.skip_line();
+            }
+
+            self.skip_trivia();
+        }
+    }
+
+    fn skip_spaces(&mut self) {
+        self.take_while(|c| c == ' ' || c == '\t');
+    }
+
+    /// Whitespace and comments. Comment lines are kept as the doc of whatever follows them, unless
+    /// a blank line separates the two.
+    ///
+    fn skip_trivia(&mut self) {
+        self.doc.clear();
+
+        loop {
+            let newlines = self.take_while(char::is_whitespace).matches('\n').count();
+
+            if newlines > 1 {
+                self.doc.clear();
+            }
+
+            if self.peek() != Some('#') {
+                break;
+            }
+
+            let comment = self.take_while(|c| c != '\n');
+            let body = comment.trim_start_matches('#');
+            self.doc.push(body.strip_prefix(' ').unwrap_or(body));
+        }
+    }
+
+    fn take_doc(&mut self) -> Option<String> {
+        let doc = std::mem::take(&mut self.doc);
+
+        (!doc.is_empty()).then(|| doc.join("\n"))
+    }
+
+    fn skip_line(&mut self) {
+        self.take_while(|c| c != '\n');
+    }
+
+    fn loc_from(&self, begin: usize) -> Loc {
+        Loc {
+            begin,
+            end: self.pos,
+        }
+    }
+
+    fn error(&self, message: &str) -> SyntaxError {
+        let end = self.pos + self.peek().map_or(0, char::len_utf8);
+
+        SyntaxError {
+            message: message.to_string(),
+            loc: Loc {
+                begin: self.pos,
+                end,
+            },
+        }
+    }
+
+    /// Records `error` and moves on to the next line.
+    ///
+    fn recover(&mut self, error: SyntaxError) {
+        debug!("RBS syntax error: {error:?}");
+        self.errors.push(error);
+
+        self.skip_line();
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn closing(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}',
+    }
+}
+
+/// Collapses runs of whitespace (including line breaks) into single spaces.
+///
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Splits `text` on `separator`, except where it's inside brackets.
+///
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut begin = 0;
+
+    for (index, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&text[begin..index]);
+                begin = index + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+
+    parts.push(&text[begin..]);
+    parts
+}
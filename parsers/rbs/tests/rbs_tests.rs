@@ -0,0 +1,291 @@
+use std::path::PathBuf;
+
+use ruby_analyzer_basic_parser::scope_gate::{Node as ScopeGateNode, ScopeGate};
+use ruby_analyzer_rbs_parser::{
+    index::{rbs_index, RbsWorkspace},
+    parser::RbsFile,
+    AttributeKind, Database, EntryKind, NamespaceKind, Visibility,
+};
+
+const CORE: &str = include_str!("fixtures/core.rbs");
+
+fn index(db: &Database) -> std::sync::Arc<ruby_analyzer_rbs_parser::RbsIndex> {
+    let file = RbsFile::new(db, PathBuf::from("/rbs/core.rbs"), CORE.to_string());
+
+    rbs_index(db, RbsWorkspace::new(db, vec![file]))
+}
+
+#[test]
+fn namespaces_test() {
+    let db = Database::default();
+    let index = index(&db);
+
+    let string = index.namespace(&["String"]).unwrap();
+    assert_eq!(NamespaceKind::Class, string.kind());
+    assert_eq!(
+        Some("A sequence of bytes.\n\nStrings are mutable unless frozen."),
+        string.doc()
+    );
+    assert_eq!(
+        &ScopeGate::new(vec![ScopeGateNode::Class("String".to_string())]),
+        string.scope_gate()
+    );
+
+    let success = index.namespace(&["Net", "HTTPSuccess"]).unwrap();
+    assert_eq!(
+        Some(
+            [
+                "Net".to_string(),
+                "HTTP".to_string(),
+                "Response".to_string()
+            ]
+            .as_slice()
+        ),
+        success.superclass()
+    );
+    assert_eq!(
+        &ScopeGate::new(vec![
+            ScopeGateNode::Module("Net".to_string()),
+            ScopeGateNode::Class("HTTPSuccess".to_string()),
+        ]),
+        success.scope_gate()
+    );
+
+    let each = index.namespace(&["_Each"]).unwrap();
+    assert_eq!(NamespaceKind::Interface, each.kind());
+    assert_eq!(&["T".to_string()], each.type_params());
+}
+
+#[test]
+fn entries_test() {
+    let db = Database::default();
+    let index = index(&db);
+
+    let signatures: Vec<String> = index
+        .entries_in(&["String"])
+        .iter()
+        .map(|entry| entry.signature())
+        .collect();
+
+    assert_eq!(
+        vec![
+            "type encoding = Encoding | String",
+            "def capitalize: () -> String | (:ascii | :lithuanian | :turkic) -> String",
+            "def []: (Integer start, ?Integer length) -> String? | (Range[Integer?]) -> String?",
+            "def []=: (Integer, String) -> String",
+            "def <=>: (untyped other) -> Integer?",
+            "def each_line: (?String separator) { (String line) -> void } -> self \
+             | (?String separator) -> Enumerator[String, self]",
+            "def self.try_convert: (untyped obj) -> String?",
+            "alias to_str to_s",
+            "def to_s: () -> String",
+            "attr_reader size: Integer",
+        ],
+        signatures
+    );
+
+    let capitalize = &index.entries_in(&["String"])[1];
+    assert_eq!(
+        Some("Returns a copy with the first character upcased."),
+        capitalize.doc()
+    );
+    assert_eq!(
+        "capitalize",
+        &CORE[capitalize.name_l().begin()..capitalize.name_l().end()]
+    );
+
+    let http: Vec<(String, &EntryKind, bool)> = index
+        .entries(&ScopeGate::new(vec![
+            ScopeGateNode::Module("Net".to_string()),
+            ScopeGateNode::Class("HTTP".to_string()),
+        ]))
+        .iter()
+        .map(|entry| (entry.signature(), entry.kind(), entry.singleton()))
+        .collect();
+
+    assert_eq!(
+        vec![
+            ("VERSION: String".to_string(), &EntryKind::Constant, false),
+            (
+                "attr_accessor self.proxy_address: String?".to_string(),
+                &EntryKind::Attribute(AttributeKind::Accessor),
+                true
+            ),
+            (
+                "attr_accessor read_timeout: Float | Integer | nil".to_string(),
+                &EntryKind::Attribute(AttributeKind::Accessor),
+                false
+            ),
+            (
+                "@started: bool".to_string(),
+                &EntryKind::Variable(ruby_analyzer_rbs_parser::VariableKind::Instance),
+                false
+            ),
+        ],
+        http
+    );
+
+    let globals: Vec<String> = index
+        .entries(&ScopeGate::default())
+        .iter()
+        .map(|entry| entry.signature())
+        .collect();
+    assert_eq!(vec!["$stdout: IO"], globals);
+
+    // Kernel's methods are private; `self?.` ones are also public singleton methods.
+    let kernel: Vec<(&str, bool, Visibility)> = index
+        .entries_in(&["Kernel"])
+        .iter()
+        .map(|entry| (entry.name(), entry.singleton(), entry.visibility()))
+        .collect();
+    assert_eq!(
+        vec![
+            ("puts", false, Visibility::Private),
+            ("format", true, Visibility::Public),
+            ("format", false, Visibility::Private),
+        ],
+        kernel
+    );
+}
+
+#[test]
+fn method_lookup_test() {
+    let db = Database::default();
+    let index = index(&db);
+
+    let ancestors: Vec<String> = index
+        .ancestors(&["String"], false)
+        .into_iter()
+        .map(|(path, _)| path.join("::"))
+        .collect();
+    assert_eq!(
+        vec!["String", "Comparable", "Object", "Kernel", "BasicObject"],
+        ancestors
+    );
+
+    let lookup = |path: &[&str], name: &str, singleton: bool| {
+        index
+            .find_method(path, name, singleton)
+            .map(|entry| entry.signature())
+    };
+
+    // Overridden by `String`.
+    assert_eq!(
+        Some("def <=>: (untyped other) -> Integer?".to_string()),
+        lookup(&["String"], "<=>", false)
+    );
+    assert_eq!(
+        Some("def between?: (untyped min, untyped max) -> bool".to_string()),
+        lookup(&["String"], "between?", false)
+    );
+    assert_eq!(
+        Some("def frozen?: () -> bool".to_string()),
+        lookup(&["String"], "frozen?", false)
+    );
+    assert_eq!(
+        Some("def self.try_convert: (untyped obj) -> String?".to_string()),
+        lookup(&["String"], "try_convert", true)
+    );
+    assert_eq!(None, lookup(&["String"], "try_convert", false));
+
+    // Through `Class`.
+    assert!(lookup(&["String"], "new", true).is_some());
+    assert_eq!(
+        Some("def name: () -> String?".to_string()),
+        lookup(&["String"], "name", true)
+    );
+
+    assert_eq!(
+        Some("attr_accessor read_timeout: Float | Integer | nil".to_string()),
+        lookup(&["Net", "HTTP"], "read_timeout=", false)
+    );
+
+    let names: Vec<&str> = index
+        .methods(&["String"], false)
+        .into_iter()
+        .map(|entry| entry.name())
+        .collect();
+    assert_eq!(1, names.iter().filter(|name| **name == "<=>").count());
+    assert!(names.contains(&"puts"));
+}
+
+#[test]
+fn return_types_test() {
+    let db = Database::default();
+    let return_types = index(&db).return_types();
+
+    let return_type = |name: &str| return_types.get(name).map(String::as_str);
+
+    assert_eq!(Some("String"), return_type("String#capitalize"));
+    assert_eq!(Some("String?"), return_type("String.try_convert"));
+    assert_eq!(Some("self"), return_type("String#each_line"));
+    assert_eq!(Some("Integer"), return_type("String#size"));
+    assert_eq!(Some("String"), return_type("Kernel.format"));
+    assert_eq!(
+        Some("Float | Integer | nil"),
+        return_type("Net::HTTP#read_timeout")
+    );
+    assert_eq!(None, return_type("String#to_str"));
+
+    // The return type is itself a proc type.
+    let file = RbsFile::new(
+        &db,
+        PathBuf::from("/rbs/callbacks.rbs"),
+        "class Callbacks\n  def handler: () -> ^(Integer) -> String\nend\n".to_string(),
+    );
+    // Type parameters, the class's and the method's own, aren't constants.
+    let generic = RbsFile::new(
+        &db,
+        PathBuf::from("/rbs/box.rbs"),
+        r#"class Box[Elem]
+  def first: () -> Elem?
+  def map: [U] () { (Elem) -> U } -> Box[U]
+  def label: () -> Box::Elem
+end
+"#
+        .to_string(),
+    );
+    let return_types = rbs_index(&db, RbsWorkspace::new(&db, vec![file, generic])).return_types();
+    let return_type = |name: &str| return_types.get(name).map(String::as_str);
+
+    assert_eq!(
+        Some("^(Integer) -> String"),
+        return_type("Callbacks#handler")
+    );
+    assert_eq!(Some("untyped?"), return_type("Box#first"));
+    assert_eq!(Some("Box[untyped]"), return_type("Box#map"));
+    assert_eq!(Some("Box::Elem"), return_type("Box#label"));
+}
+
+#[test]
+fn private_methods_test() {
+    let db = Database::default();
+    let private_methods = index(&db).private_methods();
+
+    assert!(private_methods.contains("Kernel#puts"));
+
+    // `self?.` makes a public singleton method and a private instance one.
+    assert!(private_methods.contains("Kernel#format"));
+    assert!(!private_methods.contains("Kernel.format"));
+
+    assert!(!private_methods.contains("Object#frozen?"));
+}
+
+#[test]
+fn syntax_errors_test() {
+    let db = Database::default();
+    let index = index(&db);
+
+    assert_eq!(1, index.errors().len());
+    assert_eq!("Expected a method name", index.errors()[0].1.message());
+
+    // Parsing carries on after the error.
+    assert_eq!(
+        vec!["fine"],
+        index
+            .entries_in(&["Broken"])
+            .iter()
+            .map(|entry| entry.name())
+            .collect::<Vec<_>>()
+    );
+}
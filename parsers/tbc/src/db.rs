@@ -2,16 +2,32 @@ use std::sync::{Arc, Mutex};
 
 use salsa::DebugWithDb;
 
+use crate::dsl::{DslRegistry, Expanders};
+
 pub trait Db: salsa::DbWithJar<crate::Jar> {}
 impl<DB> Db for DB where DB: ?Sized + salsa::DbWithJar<crate::Jar> {}
 
-#[derive(Default)]
 #[salsa::db(crate::Jar)]
 pub struct Database {
     storage: salsa::Storage<Self>,
     logs: Option<Arc<Mutex<Vec<String>>>>,
 }
 
+impl Default for Database {
+    /// A database whose `DslRegistry` holds the builtin expanders.
+    ///
+    fn default() -> Self {
+        let database = Self {
+            storage: salsa::Storage::default(),
+            logs: None,
+        };
+
+        DslRegistry::new(&database, Expanders::with_builtins());
+
+        database
+    }
+}
+
 impl salsa::Database for Database {
     fn salsa_event(&self, event: salsa::Event) {
         // Log interesting events, if logging is enabled
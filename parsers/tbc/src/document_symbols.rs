@@ -0,0 +1,174 @@
+use lsp_types::{DocumentSymbol, SymbolKind};
+use ropey::Rope;
+
+use crate::{
+    location::{Contains, LocNode, NodeType},
+    parser::{parse, FileSource},
+    ScopeGate,
+};
+
+/// Builds the outline of a file (classes, modules, methods, aliases, constants and `attr_*`s) as a
+/// tree of `DocumentSymbol`s, nested the same way their scope gates are.
+///
+#[salsa::tracked]
+pub fn document_symbols(db: &dyn crate::db::Db, file_source: FileSource) -> Vec<DocumentSymbol> {
+    let (loc_nodes, _) = parse(db, file_source);
+    let code = file_source.code(db);
+
+    let outline = Outline {
+        symbol_nodes: loc_nodes
+            .iter()
+            .filter(|node| symbol_kind(node.node()).is_some())
+            .collect(),
+        singleton_classes: loc_nodes
+            .iter()
+            .filter(|node| node.node() == NodeType::SClass)
+            .collect(),
+        code,
+    };
+
+    outline.children_of(&ScopeGate::default(), None)
+}
+
+struct Outline<'a> {
+    symbol_nodes: Vec<&'a LocNode>,
+    singleton_classes: Vec<&'a LocNode>,
+    code: &'a Rope,
+}
+
+impl<'a> Outline<'a> {
+    // A class can be reopened in the same file, in which case both `LocNode`s open the same scope
+    // gate; using the parent's location keeps each one's children separate.
+    //
+    fn children_of(&self, scope_gate: &ScopeGate, parent: Option<&LocNode>) -> Vec<DocumentSymbol> {
+        let mut children: Vec<&LocNode> = self
+            .symbol_nodes
+            .iter()
+            .copied()
+            .filter(|node| node.scope_gate() == scope_gate)
+            .filter(|node| parent.map_or(true, |p| p.expression_l().contains(&node.expression_l())))
+            .collect();
+
+        children.sort_by_key(|node| node.name_l().begin());
+
+        children
+            .into_iter()
+            .map(|node| self.to_document_symbol(node))
+            .collect()
+    }
+
+    #[allow(deprecated)]
+    fn to_document_symbol(&self, node: &LocNode) -> DocumentSymbol {
+        let name = if self.is_singleton_method(node) {
+            format!("self.{}", node.name())
+        } else {
+            node.name().to_string()
+        };
+
+        let children = node
+            .inner_scope_gate()
+            .map(|scope_gate| self.children_of(&scope_gate, Some(node)))
+            .filter(|children| !children.is_empty());
+
+        DocumentSymbol {
+            name,
+            detail: None,
+            kind: symbol_kind(node.node()).unwrap(),
+            tags: None,
+            deprecated: None,
+            range: node.expression_l().to_lsp_range(self.code),
+            selection_range: node.name_l().to_lsp_range(self.code),
+            children,
+        }
+    }
+
+    fn is_singleton_method(&self, node: &LocNode) -> bool {
+        match node.node() {
+            NodeType::Defs => true,
+            NodeType::Def => self
+                .singleton_classes
+                .iter()
+                .any(|sclass| sclass.expression_l().contains(&node.expression_l())),
+            _ => false,
+        }
+    }
+}
+
+fn symbol_kind(node_type: NodeType) -> Option<SymbolKind> {
+    match node_type {
+        NodeType::Class => Some(SymbolKind::CLASS),
+        NodeType::Module => Some(SymbolKind::MODULE),
+        // `Alias` `LocNode`s are for the methods that `alias` and `alias_method` define.
+        NodeType::Def | NodeType::Defs | NodeType::Alias => Some(SymbolKind::METHOD),
+        NodeType::Casgn => Some(SymbolKind::CONSTANT),
+        // Only `attr_*` calls get `Send` `LocNode`s.
+        NodeType::Send => Some(SymbolKind::PROPERTY),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::db::Database;
+
+    const CODE: &str = r#"class Foo
+  BAR = 1
+  attr_accessor :baz
+
+  def qux; end
+  alias grault qux
+
+  class << self
+    def quux; end
+  end
+end
+
+module Corge; end"#;
+
+    #[test]
+    fn nested_symbols_test() {
+        let db = Database::default();
+        let file_source = FileSource::new(&db, PathBuf::from("/tmp/test.rb"), Rope::from_str(CODE));
+
+        let symbols = document_symbols(&db, file_source);
+
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(vec!["Foo", "Corge"], names);
+
+        let foo = &symbols[0];
+        assert_eq!(SymbolKind::CLASS, foo.kind);
+        assert_eq!(
+            lsp_types::Range::new(
+                lsp_types::Position::new(0, 6),
+                lsp_types::Position::new(0, 9)
+            ),
+            foo.selection_range
+        );
+        assert_eq!(lsp_types::Position::new(0, 0), foo.range.start);
+
+        let children: Vec<(&str, SymbolKind)> = foo
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|s| (s.name.as_str(), s.kind))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("BAR", SymbolKind::CONSTANT),
+                ("baz", SymbolKind::PROPERTY),
+                ("baz=", SymbolKind::PROPERTY),
+                ("qux", SymbolKind::METHOD),
+                ("grault", SymbolKind::METHOD),
+                ("self.quux", SymbolKind::METHOD),
+            ],
+            children
+        );
+
+        assert!(symbols[1].children.is_none());
+    }
+}
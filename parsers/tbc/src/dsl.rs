@@ -0,0 +1,272 @@
+//! DSL expanders: things that recognize calls to class-level macros (ex. Rails' `has_many :posts`)
+//! and say which methods and constants those calls define. The transformer runs every expander
+//! over each receiverless `Send` it visits and adds the results to the `ScopedIndex` (as
+//! `NodeProperties::SyntheticMethod` and `NodeProperties::SyntheticConstant`) and `LocNode`s.
+//!
+//! Other crates can add their own expanders by implementing `DslExpander` and registering it in
+//! the `DslRegistry`:
+//!
+//! ```ignore
+//! DslRegistry::get(&db)
+//!     .set_expanders(&mut db)
+//!     .to(Expanders::with_builtins().with(MyExpander));
+//! ```
+//!
+//! Since the registry is a salsa input, parse results depend on it; replacing the expanders
+//! reparses files the next time they're asked for.
+//!
+pub mod rails;
+
+use std::{fmt, sync::Arc};
+
+use lib_ruby_parser::{nodes as lrp_nodes, Node};
+
+use crate::{location::Loc, ScopeGate};
+
+pub use self::rails::Rails;
+
+/// The expanders to use when parsing. The `Database` creates it with the builtin ones (see
+/// `Expanders::with_builtins`); other databases that include this crate's `Jar` need to create it
+/// themselves before parsing.
+///
+#[salsa::input(singleton)]
+pub struct DslRegistry {
+    #[return_ref]
+    pub expanders: Expanders,
+}
+
+/// The expanders in use, as of the current revision.
+///
+pub(crate) fn current_expanders(db: &dyn crate::db::Db) -> Expanders {
+    DslRegistry::get(db).expanders(db).clone()
+}
+
+/// An ordered list of expanders; each one is run on every call. Two lists are equal when they
+/// hold the very same expanders.
+///
+#[derive(Clone, Default)]
+pub struct Expanders {
+    inner: Vec<Arc<dyn DslExpander>>,
+}
+
+impl Expanders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The expanders this crate comes with: `Rails`.
+    ///
+    pub fn with_builtins() -> Self {
+        Self::new().with(Rails)
+    }
+
+    pub fn with<T: DslExpander + 'static>(mut self, expander: T) -> Self {
+        self.inner.push(Arc::new(expander));
+        self
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn DslExpander> {
+        self.inner.iter().map(|expander| expander.as_ref())
+    }
+}
+
+impl fmt::Debug for Expanders {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.inner.iter().map(|expander| expander.name()))
+            .finish()
+    }
+}
+
+impl PartialEq for Expanders {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner.len() == other.inner.len()
+            && self
+                .inner
+                .iter()
+                .zip(&other.inner)
+                .all(|(a, b)| Arc::as_ptr(a).cast::<()>() == Arc::as_ptr(b).cast::<()>())
+    }
+}
+
+impl Eq for Expanders {}
+
+/// Expands a DSL call into the definitions it generates. Implementations should be pure: the
+/// output must only depend on the `call` and `scope_gate`, since it's memoized.
+///
+pub trait DslExpander: Send + Sync {
+    /// Name of the DSL, ex. `"rails"`.
+    fn name(&self) -> &str;
+
+    /// The methods and constants that `call`, made in `scope_gate`, defines. Calls that the
+    /// expander doesn't know about should just return an empty `Vec`.
+    fn expand(&self, call: &Call, scope_gate: &ScopeGate) -> Vec<SyntheticDefinition>;
+}
+
+/// A definition generated by a DSL call.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SyntheticDefinition {
+    Method {
+        name: String,
+
+        /// Is this a class method (ex. a Rails `scope`)?
+        singleton: bool,
+
+        /// Location of whatever names the method (typically a symbol argument).
+        name_l: Loc,
+    },
+    Constant {
+        name: String,
+        name_l: Loc,
+    },
+}
+
+/// A receiverless method call, ex. `has_many :posts, dependent: :destroy`, in terms that
+/// expanders can work with without knowing about `lib_ruby_parser`.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Call {
+    pub(crate) method_name: String,
+    pub(crate) args: Vec<Arg>,
+    pub(crate) selector_l: Loc,
+    pub(crate) expression_l: Loc,
+}
+
+impl Call {
+    /// `None` if the call has an explicit receiver.
+    ///
+    pub(crate) fn from_send(node: &lrp_nodes::Send) -> Option<Self> {
+        if node.recv.is_some() {
+            return None;
+        }
+
+        let expression_l: Loc = node.expression_l.into();
+
+        Some(Self {
+            method_name: node.method_name.clone(),
+            args: node.args.iter().map(Arg::new).collect(),
+            selector_l: node.selector_l.map_or(expression_l, Loc::from),
+            expression_l,
+        })
+    }
+
+    pub fn method_name(&self) -> &str {
+        self.method_name.as_ref()
+    }
+
+    pub fn args(&self) -> &[Arg] {
+        &self.args
+    }
+
+    pub fn selector_l(&self) -> Loc {
+        self.selector_l
+    }
+
+    pub fn expression_l(&self) -> Loc {
+        self.expression_l
+    }
+
+    /// The leading symbol/string arguments, ex. `:a` and `:b` in `delegate :a, :b, to: :c`.
+    ///
+    pub fn names(&self) -> impl Iterator<Item = (&str, Loc)> {
+        self.args.iter().map_while(Arg::name)
+    }
+
+    /// The value of option `key` in the trailing hash (or keyword arguments), ex. `:c` for `to` in
+    /// `delegate :a, :b, to: :c`.
+    ///
+    pub fn option(&self, key: &str) -> Option<&Arg> {
+        match self.args.last()? {
+            Arg::Hash(pairs) => Arg::lookup(pairs, key),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Arg {
+    Symbol {
+        name: String,
+        loc: Loc,
+    },
+    String {
+        value: String,
+        loc: Loc,
+    },
+    Bool(bool),
+
+    /// A hash literal or keyword arguments, as `(key, value)` pairs.
+    Hash(Vec<(Arg, Arg)>),
+    Array(Vec<Arg>),
+
+    /// `-> { ... }` or `lambda { ... }`.
+    Lambda,
+
+    /// Anything else; expanders can't know its value.
+    Other {
+        loc: Loc,
+    },
+}
+
+impl Arg {
+    fn new(node: &Node) -> Self {
+        match node {
+            Node::Sym(sym) => Self::Symbol {
+                name: sym.name.to_string_lossy(),
+                loc: sym.expression_l.into(),
+            },
+            Node::Str(str_) => Self::String {
+                value: str_.value.to_string_lossy(),
+                loc: str_.expression_l.into(),
+            },
+            Node::True(_) => Self::Bool(true),
+            Node::False(_) => Self::Bool(false),
+            Node::Hash(hash) => Self::Hash(Self::pairs(&hash.pairs)),
+            Node::Kwargs(kwargs) => Self::Hash(Self::pairs(&kwargs.pairs)),
+            Node::Array(array) => Self::Array(array.elements.iter().map(Self::new).collect()),
+            Node::Lambda(_) => Self::Lambda,
+            Node::Block(block) => match &*block.call {
+                Node::Lambda(_) => Self::Lambda,
+                Node::Send(send) if send.recv.is_none() && send.method_name == "lambda" => {
+                    Self::Lambda
+                }
+                _ => Self::Other {
+                    loc: block.expression_l.into(),
+                },
+            },
+            _ => Self::Other {
+                loc: (*node.expression()).into(),
+            },
+        }
+    }
+
+    fn pairs(pairs: &[Node]) -> Vec<(Self, Self)> {
+        pairs
+            .iter()
+            .filter_map(|pair| match pair {
+                Node::Pair(pair) => Some((Self::new(&pair.key), Self::new(&pair.value))),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The value for `key` in `pairs`, where the key is a symbol or string.
+    ///
+    pub fn lookup<'a>(pairs: &'a [(Arg, Arg)], key: &str) -> Option<&'a Arg> {
+        pairs
+            .iter()
+            .find(|(k, _)| k.name().map_or(false, |(name, _)| name == key))
+            .map(|(_, value)| value)
+    }
+
+    /// The name given by a symbol or string, ex. `foo` for `:foo`.
+    ///
+    pub fn name(&self) -> Option<(&str, Loc)> {
+        match self {
+            Self::Symbol { name, loc } => Some((name, *loc)),
+            Self::String { value, loc } => Some((value, *loc)),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! Methods generated by Rails' class-level macros: ActiveRecord associations, `scope`, `enum`,
+//! `has_secure_password` and ActiveSupport's `delegate`.
+//!
+//! Validations (`validates`, `validate`, ...) and callbacks (`before_save`, `after_commit`, ...)
+//! only register behavior; they don't define any methods, so they expand to nothing.
+//!
+use super::{Arg, Call, DslExpander, SyntheticDefinition};
+use crate::{location::Loc, ScopeGate, ScopeGateNode};
+
+/// Options to `enum` that aren't the name of an enum attribute (in the Rails < 7 syntax, ex.
+/// `enum status: [:active, :archived], _prefix: true`).
+///
+const ENUM_OPTIONS: &[&str] = &[
+    "_prefix",
+    "_suffix",
+    "_scopes",
+    "_default",
+    "_instance_methods",
+    "prefix",
+    "suffix",
+    "scopes",
+    "default",
+    "instance_methods",
+    "validate",
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rails;
+
+impl DslExpander for Rails {
+    fn name(&self) -> &str {
+        "rails"
+    }
+
+    fn expand(&self, call: &Call, scope_gate: &ScopeGate) -> Vec<SyntheticDefinition> {
+        if !matches!(scope_gate.last(), Some(ScopeGateNode::Class(_))) {
+            return Vec::new();
+        }
+
+        match call.method_name() {
+            "has_many" | "has_and_belongs_to_many" => collection_association(call),
+            "has_one" | "belongs_to" => singular_association(call),
+            "scope" => call
+                .names()
+                .take(1)
+                .map(|(name, name_l)| singleton_method(name.to_string(), name_l))
+                .collect(),
+            "delegate" => delegate(call),
+            "enum" => enum_(call),
+            "has_secure_password" => secure_password(call),
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn method(name: String, name_l: Loc) -> SyntheticDefinition {
+    SyntheticDefinition::Method {
+        name,
+        singleton: false,
+        name_l,
+    }
+}
+
+fn singleton_method(name: String, name_l: Loc) -> SyntheticDefinition {
+    SyntheticDefinition::Method {
+        name,
+        singleton: true,
+        name_l,
+    }
+}
+
+/// `has_many :posts` defines `posts`, `posts=`, `post_ids` and `post_ids=`.
+///
+fn collection_association(call: &Call) -> Vec<SyntheticDefinition> {
+    let Some((name, name_l)) = call.names().next() else {
+        return Vec::new();
+    };
+
+    let singular = singularize(name);
+
+    vec![
+        method(name.to_string(), name_l),
+        method(format!("{name}="), name_l),
+        method(format!("{singular}_ids"), name_l),
+        method(format!("{singular}_ids="), name_l),
+    ]
+}
+
+/// `belongs_to :author` defines `author`, `author=`, `build_author`, `create_author`,
+/// `create_author!` and `reload_author`.
+///
+fn singular_association(call: &Call) -> Vec<SyntheticDefinition> {
+    let Some((name, name_l)) = call.names().next() else {
+        return Vec::new();
+    };
+
+    vec![
+        method(name.to_string(), name_l),
+        method(format!("{name}="), name_l),
+        method(format!("build_{name}"), name_l),
+        method(format!("create_{name}"), name_l),
+        method(format!("create_{name}!"), name_l),
+        method(format!("reload_{name}"), name_l),
+    ]
+}
+
+/// `delegate :name, :email, to: :user, prefix: true` defines `user_name` and `user_email`.
+/// Without `prefix`, the methods keep their names; with `prefix: :owner`, they're `owner_name`,
+/// etc.
+///
+fn delegate(call: &Call) -> Vec<SyntheticDefinition> {
+    let Some((target, _)) = call.option("to").and_then(Arg::name) else {
+        return Vec::new();
+    };
+
+    let prefix = match call.option("prefix") {
+        Some(Arg::Bool(true)) => Some(target),
+        Some(arg) => arg.name().map(|(prefix, _)| prefix),
+        None => None,
+    };
+
+    call.names()
+        .map(|(name, name_l)| match prefix {
+            Some(prefix) => method(format!("{prefix}_{name}"), name_l),
+            None => method(name.to_string(), name_l),
+        })
+        .collect()
+}
+
+/// For each value of the enum, `enum :status, [:active, :archived]` defines the predicate
+/// `active?`, the bang method `active!` and the scopes `active` and `not_active`. It also defines
+/// the class method `statuses`, which maps values to what's stored in the database.
+///
+/// Both the Rails 7 syntax (`enum :status, ...`) and the older one (`enum status: ...`) are
+/// handled, as are `prefix`/`suffix` and `scopes: false` (with or without leading underscores).
+///
+fn enum_(call: &Call) -> Vec<SyntheticDefinition> {
+    let options: &[(Arg, Arg)] = match call.args().last() {
+        Some(Arg::Hash(pairs)) => pairs,
+        _ => &[],
+    };
+
+    let option =
+        |key: &str| Arg::lookup(options, key).or_else(|| Arg::lookup(options, &format!("_{key}")));
+
+    // (attribute, attribute_l, values)
+    let mut enums: Vec<(&str, Loc, &Arg)> = Vec::new();
+
+    match call.args() {
+        [Arg::Symbol { name, loc } | Arg::String { value: name, loc }, values, ..] => {
+            enums.push((name.as_str(), *loc, values));
+        }
+        [Arg::Hash(pairs)] => {
+            for (key, values) in pairs {
+                if let Some((name, loc)) = key.name() {
+                    if !ENUM_OPTIONS.contains(&name) {
+                        enums.push((name, loc, values));
+                    }
+                }
+            }
+        }
+        _ => (),
+    }
+
+    let affix = |option: Option<&Arg>, attribute: &str| match option {
+        Some(Arg::Bool(true)) => Some(attribute.to_string()),
+        Some(arg) => arg.name().map(|(affix, _)| affix.to_string()),
+        None => None,
+    };
+
+    let scopes = !matches!(option("scopes"), Some(Arg::Bool(false)));
+
+    let mut definitions = Vec::new();
+
+    for (attribute, attribute_l, values) in enums {
+        definitions.push(singleton_method(pluralize(attribute), attribute_l));
+
+        let prefix = affix(option("prefix"), attribute);
+        let suffix = affix(option("suffix"), attribute);
+
+        let value_names: Vec<(&str, Loc)> = match values {
+            Arg::Array(values) => values.iter().filter_map(Arg::name).collect(),
+            Arg::Hash(pairs) => pairs
+                .iter()
+                .filter_map(|(key, _)| key.name())
+                .filter(|(value, _)| !ENUM_OPTIONS.contains(value))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        for (value, value_l) in value_names {
+            let name = match (&prefix, &suffix) {
+                (Some(prefix), Some(suffix)) => format!("{prefix}_{value}_{suffix}"),
+                (Some(prefix), None) => format!("{prefix}_{value}"),
+                (None, Some(suffix)) => format!("{value}_{suffix}"),
+                (None, None) => value.to_string(),
+            };
+
+            definitions.push(method(format!("{name}?"), value_l));
+            definitions.push(method(format!("{name}!"), value_l));
+
+            if scopes {
+                definitions.push(singleton_method(name.clone(), value_l));
+                definitions.push(singleton_method(format!("not_{name}"), value_l));
+            }
+        }
+    }
+
+    definitions
+}
+
+/// `has_secure_password` defines `password=`, `password_confirmation=`, `authenticate` and
+/// `authenticate_password`; `has_secure_password :recovery_password` defines
+/// `recovery_password=`, `recovery_password_confirmation=` and `authenticate_recovery_password`.
+///
+fn secure_password(call: &Call) -> Vec<SyntheticDefinition> {
+    let (attribute, name_l) = call
+        .names()
+        .next()
+        .unwrap_or(("password", call.selector_l()));
+
+    let mut definitions = vec![
+        method(format!("{attribute}="), name_l),
+        method(format!("{attribute}_confirmation="), name_l),
+        method(format!("authenticate_{attribute}"), name_l),
+    ];
+
+    if attribute == "password" {
+        definitions.push(method("authenticate".to_string(), name_l));
+    }
+
+    definitions
+}
+
+/// A naive version of ActiveSupport's `String#singularize`; good enough for association names.
+///
+pub(crate) fn singularize(word: &str) -> String {
+    if let Some(stem) = word.strip_suffix("ies") {
+        format!("{stem}y")
+    } else if ["sses", "xes", "zes", "ches", "shes"]
+        .iter()
+        .any(|suffix| word.ends_with(suffix))
+        // Other `-ses` only when the singular ends in `s` (as ActiveSupport does), since `cases`
+        // and `responses` just drop the `s`.
+        || ["aliases", "buses", "statuses"]
+            .iter()
+            .any(|plural| word.ends_with(plural))
+    {
+        word[..word.len() - 2].to_string()
+    } else if word.ends_with('s') && !word.ends_with("ss") && !word.ends_with("us") {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+/// A naive version of ActiveSupport's `String#pluralize`.
+///
+pub(crate) fn pluralize(word: &str) -> String {
+    let consonant_y = word.ends_with('y')
+        && !word
+            .chars()
+            .rev()
+            .nth(1)
+            .map_or(false, |c| "aeiou".contains(c));
+
+    if consonant_y {
+        format!("{}ies", &word[..word.len() - 1])
+    } else if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|suffix| word.ends_with(suffix))
+    {
+        format!("{word}es")
+    } else {
+        format!("{word}s")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn singularize_test() {
+        assert_eq!("post", singularize("posts"));
+        assert_eq!("category", singularize("categories"));
+        assert_eq!("address", singularize("addresses"));
+        assert_eq!("box", singularize("boxes"));
+        assert_eq!("status", singularize("status"));
+        assert_eq!("status", singularize("statuses"));
+        assert_eq!("bus", singularize("buses"));
+        assert_eq!("case", singularize("cases"));
+    }
+
+    #[test]
+    fn pluralize_test() {
+        assert_eq!("statuses", pluralize("status"));
+        assert_eq!("categories", pluralize("category"));
+        assert_eq!("days", pluralize("day"));
+        assert_eq!("roles", pluralize("role"));
+    }
+}
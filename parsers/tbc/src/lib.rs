@@ -1,6 +1,9 @@
 pub mod db;
+pub mod document_symbols;
+pub mod dsl;
 pub mod location;
 pub(crate) mod lrp_extensions;
+pub(crate) mod metaprogramming;
 pub mod parser;
 pub mod queries;
 pub mod scope_gate;
@@ -22,4 +25,6 @@ pub struct Jar(
     crate::parser::NodeSource,
     crate::queries::ClosestNodeQuery,
     crate::queries::find_scope_gate,
+    crate::document_symbols::document_symbols,
+    crate::dsl::DslRegistry,
 );
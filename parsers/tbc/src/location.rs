@@ -2,7 +2,9 @@ mod node;
 
 use std::ops::Range;
 
-use crate::ScopeGate;
+use ropey::Rope;
+
+use crate::{ScopeGate, ScopeGateNode};
 
 pub(crate) use self::node::NodeType;
 
@@ -12,6 +14,7 @@ pub struct LocNode {
     pub(crate) name: String,
     pub(crate) scope_gate: ScopeGate,
     pub(crate) expression_l: Loc,
+    pub(crate) name_l: Loc,
 }
 
 impl LocNode {
@@ -30,6 +33,27 @@ impl LocNode {
     pub fn name(&self) -> &str {
         self.name.as_ref()
     }
+
+    /// Location of just the name (ex. the `Foo` in `class Foo`), as opposed to the whole
+    /// expression.
+    ///
+    pub fn name_l(&self) -> Loc {
+        self.name_l
+    }
+
+    /// The scope gate that this node opens, if it's a class, module or method definition.
+    ///
+    pub fn inner_scope_gate(&self) -> Option<ScopeGate> {
+        let scope_gate_node = match self.node {
+            NodeType::Class => ScopeGateNode::Class(self.name.clone()),
+            NodeType::Module => ScopeGateNode::Module(self.name.clone()),
+            NodeType::Def => ScopeGateNode::Def(self.name.clone()),
+            NodeType::Defs => ScopeGateNode::Defs(self.name.clone()),
+            _ => return None,
+        };
+
+        Some(self.scope_gate.join(scope_gate_node))
+    }
 }
 
 pub trait Contains<T> {
@@ -72,6 +96,45 @@ impl Loc {
     pub fn as_range(&self) -> Range<usize> {
         self.begin..self.end
     }
+
+    /// Converts this byte-offset-based `Loc` to an LSP `Range`, which is line/UTF-16-based.
+    ///
+    pub fn to_lsp_range(&self, code: &Rope) -> lsp_types::Range {
+        lsp_types::Range::new(
+            offset_to_position(self.begin, code),
+            offset_to_position(self.end, code),
+        )
+    }
+}
+
+/// Converts a byte offset in `code` to an LSP `Position`.
+///
+pub fn offset_to_position(offset: usize, code: &Rope) -> lsp_types::Position {
+    let line = code.byte_to_line(offset);
+    let line_start = code.char_to_utf16_cu(code.line_to_char(line));
+    let character = code.char_to_utf16_cu(code.byte_to_char(offset)) - line_start;
+
+    lsp_types::Position::new(line as u32, character as u32)
+}
+
+/// Converts an LSP `Position` to a byte offset in `code`. Positions past the end of a line (or of
+/// the file) are clamped to it, as LSP asks.
+///
+pub fn position_to_offset(position: lsp_types::Position, code: &Rope) -> usize {
+    let line_idx = (position.line as usize).min(code.len_lines() - 1);
+    let line = code.line(line_idx);
+
+    // The line's length, not counting its line ending.
+    let mut line_chars = line.len_chars();
+    while line_chars > 0 && matches!(line.char(line_chars - 1), '\n' | '\r') {
+        line_chars -= 1;
+    }
+    let character = (position.character as usize).min(line.char_to_utf16_cu(line_chars));
+
+    let line_start = code.char_to_utf16_cu(code.line_to_char(line_idx));
+    let char_idx = code.utf16_cu_to_char(line_start + character);
+
+    code.char_to_byte(char_idx)
 }
 
 impl From<lib_ruby_parser::Loc> for Loc {
@@ -24,6 +24,16 @@ impl NameFromNode for nodes::Module {
     }
 }
 
+impl NameFromNode for nodes::SClass {
+    fn name_from_node(&self) -> String {
+        match &*self.expr {
+            Node::Self_(_) => "self".to_string(),
+            Node::Const(nodes::Const { name, .. }) => name.clone(),
+            _ => "{{expression}}".to_string(),
+        }
+    }
+}
+
 pub(super) trait OptionNameFromNode {
     fn option_name_from_node(&self) -> Option<String>;
 }
@@ -36,3 +46,15 @@ impl OptionNameFromNode for nodes::MatchRest {
         }
     }
 }
+
+impl OptionNameFromNode for Node {
+    /// The name given by a symbol or string literal, ex. `:foo` or `"foo"`.
+    ///
+    fn option_name_from_node(&self) -> Option<String> {
+        match self {
+            Node::Sym(sym) => Some(sym.name.to_string_lossy()),
+            Node::Str(str_) => Some(str_.value.to_string_lossy()),
+            _ => None,
+        }
+    }
+}
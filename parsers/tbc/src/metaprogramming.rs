@@ -0,0 +1,198 @@
+//! Methods that aren't defined with `def`, but by calling something that defines them for us, ex.
+//! `attr_accessor :name` or `alias_method :new_name, :name`. The transformer adds these to the
+//! `ScopedIndex` (as `NodeProperties::SyntheticMethod`) and `LocNode`s, so they can be treated
+//! like any other method.
+//!
+//! The same goes for classes that are created by a constructor call instead of `class`, ex.
+//! `Point = Struct.new(:x, :y)`; see `ClassConstructorCall`.
+//!
+use lib_ruby_parser::{nodes as lrp_nodes, Node};
+
+use crate::{
+    location::{Loc, NodeType},
+    lrp_extensions::OptionNameFromNode,
+    scoped_index::nodes::{ClassConstructorKind, SyntheticMethodKind},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SynthesizedMethod {
+    pub(crate) name: String,
+    pub(crate) kind: SyntheticMethodKind,
+
+    /// Location of the symbol (or string) that names the method.
+    pub(crate) name_l: Loc,
+
+    pub(crate) singleton: bool,
+}
+
+impl SynthesizedMethod {
+    pub(crate) fn node_type(&self) -> NodeType {
+        match self.kind {
+            SyntheticMethodKind::AttrReader
+            | SyntheticMethodKind::AttrWriter
+            | SyntheticMethodKind::Dsl { .. } => NodeType::Send,
+            SyntheticMethodKind::Alias { .. } => NodeType::Alias,
+        }
+    }
+}
+
+/// Methods defined by a receiverless `attr_reader`, `attr_writer`, `attr_accessor` or
+/// `alias_method` call.
+///
+pub(crate) fn from_send(node: &lrp_nodes::Send) -> Vec<SynthesizedMethod> {
+    if node.recv.is_some() {
+        return Vec::new();
+    }
+
+    let (reader, writer) = match node.method_name.as_str() {
+        "attr_reader" => (true, false),
+        "attr_writer" => (false, true),
+        "attr_accessor" => (true, true),
+        "alias_method" => {
+            return match node.args.as_slice() {
+                [new_name, original] => alias(new_name, original).into_iter().collect(),
+                _ => Vec::new(),
+            };
+        }
+        _ => return Vec::new(),
+    };
+
+    let mut methods = Vec::new();
+
+    for arg in &node.args {
+        let Some(attr_name) = arg.option_name_from_node() else {
+            continue;
+        };
+
+        let name_l: Loc = (*arg.expression()).into();
+
+        if reader {
+            methods.push(SynthesizedMethod {
+                name: attr_name.clone(),
+                kind: SyntheticMethodKind::AttrReader,
+                name_l,
+                singleton: false,
+            });
+        }
+
+        if writer {
+            methods.push(SynthesizedMethod {
+                name: format!("{attr_name}="),
+                kind: SyntheticMethodKind::AttrWriter,
+                name_l,
+                singleton: false,
+            });
+        }
+    }
+
+    methods
+}
+
+/// The method defined by `alias new_name original`. `alias $new $original` (for globals) doesn't
+/// define a method.
+///
+pub(crate) fn from_alias(node: &lrp_nodes::Alias) -> Option<SynthesizedMethod> {
+    alias(&node.to, &node.from)
+}
+
+fn alias(new_name: &Node, original: &Node) -> Option<SynthesizedMethod> {
+    Some(SynthesizedMethod {
+        name: new_name.option_name_from_node()?,
+        kind: SyntheticMethodKind::Alias {
+            original: original.option_name_from_node()?,
+        },
+        name_l: (*new_name.expression()).into(),
+        singleton: false,
+    })
+}
+
+/// A constant assigned the result of `Struct.new`, `Data.define`, `Class.new` or `Module.new`,
+/// optionally with a block (which then acts as the class body).
+///
+pub(crate) struct ClassConstructorCall<'a> {
+    pub(crate) kind: ClassConstructorKind,
+
+    /// The `Struct`, `Data`, `Class` or `Module` constant.
+    pub(crate) recv: &'a Node,
+    pub(crate) args: &'a [Node],
+    pub(crate) body: Option<&'a Node>,
+}
+
+impl<'a> ClassConstructorCall<'a> {
+    pub(crate) fn from_casgn(node: &'a lrp_nodes::Casgn) -> Option<Self> {
+        let (send, body) = match node.value.as_deref()? {
+            Node::Send(send) => (send, None),
+            Node::Block(block) => match &*block.call {
+                Node::Send(send) => (send, block.body.as_deref()),
+                _ => return None,
+            },
+            _ => return None,
+        };
+
+        let recv = send.recv.as_deref()?;
+
+        // Only the top-level constants, ex. `Struct` or `::Struct`, but not `Foo::Struct`.
+        let Node::Const(const_) = recv else {
+            return None;
+        };
+
+        if !matches!(const_.scope.as_deref(), None | Some(Node::Cbase(_))) {
+            return None;
+        }
+
+        let kind = match (const_.name.as_str(), send.method_name.as_str()) {
+            ("Struct", "new") => ClassConstructorKind::Struct,
+            ("Data", "define") => ClassConstructorKind::Data,
+            ("Class", "new") => ClassConstructorKind::Class,
+            ("Module", "new") => ClassConstructorKind::Module,
+            _ => return None,
+        };
+
+        Some(Self {
+            kind,
+            recv,
+            args: &send.args,
+            body,
+        })
+    }
+
+    /// The accessors for the members of a `Struct` (readers and writers) or `Data` (readers only).
+    /// String arguments aren't members; `Struct.new("Name")` names the class `Struct::Name`.
+    ///
+    pub(crate) fn members(&self) -> Vec<SynthesizedMethod> {
+        let writers = match self.kind {
+            ClassConstructorKind::Struct => true,
+            ClassConstructorKind::Data => false,
+            ClassConstructorKind::Class | ClassConstructorKind::Module => return Vec::new(),
+        };
+
+        let mut methods = Vec::new();
+
+        for arg in self.args {
+            let Node::Sym(sym) = arg else {
+                continue;
+            };
+
+            let name = sym.name.to_string_lossy();
+            let name_l: Loc = sym.expression_l.into();
+
+            methods.push(SynthesizedMethod {
+                name: name.clone(),
+                kind: SyntheticMethodKind::AttrReader,
+                name_l,
+                singleton: false,
+            });
+
+            if writers {
+                methods.push(SynthesizedMethod {
+                    name: format!("{name}="),
+                    kind: SyntheticMethodKind::AttrWriter,
+                    name_l,
+                    singleton: false,
+                });
+            }
+        }
+
+        methods
+    }
+}
@@ -3,7 +3,7 @@ use std::path::{Path, PathBuf};
 use lib_ruby_parser::traverse::visitor::Visitor;
 use ropey::Rope;
 
-use crate::{location::LocNode, transformer, ScopedIndex};
+use crate::{dsl, location::LocNode, transformer, ScopedIndex};
 
 /// The path and contents of a source file. Typically, this is what we parse.
 ///
@@ -68,7 +68,8 @@ pub(crate) struct NodeSource {
 }
 
 /// Uses a `Transformer` to take the AST result of a `lib_ruby_parser::ParserResult` and converts
-/// those `Node`s to our `Node`s.
+/// those `Node`s to our `Node`s. DSL calls are expanded with the expanders in the
+/// `crate::dsl::DslRegistry`.
 ///
 #[salsa::tracked]
 pub(crate) fn inner_transform(
@@ -77,7 +78,7 @@ pub(crate) fn inner_transform(
 ) -> (Vec<LocNode>, ScopedIndex) {
     let root_node = node_source.root_node(db);
 
-    let mut transformer = transformer::Transformer::default();
+    let mut transformer = transformer::Transformer::with_expanders(dsl::current_expanders(db));
     transformer.visit(root_node);
 
     transformer.finish()
@@ -56,4 +56,8 @@ impl Node {
     pub fn id(&self) -> usize {
         self.id
     }
+
+    pub fn properties(&self) -> &NodeProperties {
+        &self.properties
+    }
 }
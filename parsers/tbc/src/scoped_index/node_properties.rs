@@ -21,6 +21,7 @@ pub enum NodeProperties {
     Casgn(Casgn),
     Cbase,
     Class(Class),
+    ClassConstructor(ClassConstructor), // ex. `Point = Struct.new(:x, :y)`
     Complex(Complex),
     Const(Const),
     ConstPattern(ConstPattern),
@@ -115,6 +116,8 @@ pub enum NodeProperties {
     Str(Str),
     Super(Super),
     Sym(Sym),
+    SyntheticConstant(SyntheticConstant), // ex. from a DSL call
+    SyntheticMethod(SyntheticMethod),     // ex. `attr_reader :foo`
     True,
     Undef(Undef),
     UnlessGuard(UnlessGuard),
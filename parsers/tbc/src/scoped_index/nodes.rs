@@ -102,6 +102,58 @@ pub struct Class {
     pub(crate) body_id: Option<usize>,
 }
 
+/// A class or module created by calling a constructor and assigning the result to a constant,
+/// ex. `Point = Struct.new(:x, :y) do ... end`. Its block (if any) is the body of the class.
+///
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct ClassConstructor {
+    pub(crate) name: String,
+    pub(crate) kind: ClassConstructorKind,
+
+    /// ID of the scope of the constant, ex. `Foo` in `Foo::Point = Struct.new(:x)`.
+    pub(crate) scope_id: Option<usize>,
+
+    /// ID of the superclass: `Struct` or `Data` for those, the argument to `Class.new(Base)`.
+    pub(crate) superclass_id: Option<usize>,
+    pub(crate) arg_ids: Vec<usize>,
+    pub(crate) body_id: Option<usize>,
+}
+
+impl ClassConstructor {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn kind(&self) -> ClassConstructorKind {
+        self.kind
+    }
+
+    pub fn superclass_id(&self) -> Option<usize> {
+        self.superclass_id
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ClassConstructorKind {
+    /// `Struct.new(:a, :b)`
+    Struct,
+
+    /// `Data.define(:a, :b)`
+    Data,
+
+    /// `Class.new` or `Class.new(Base)`
+    Class,
+
+    /// `Module.new`
+    Module,
+}
+
+impl ClassConstructorKind {
+    pub fn is_module(&self) -> bool {
+        matches!(self, Self::Module)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Complex {
     pub(crate) value: String,
@@ -142,6 +194,17 @@ pub struct Def {
     pub(crate) name: String,
     pub(crate) args_id: Option<usize>,
     pub(crate) body_id: Option<usize>,
+    pub(crate) visibility: Visibility,
+}
+
+impl Def {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
@@ -155,6 +218,17 @@ pub struct Defs {
     pub(crate) definee_id: usize,
     pub(crate) args_id: Option<usize>,
     pub(crate) body_id: Option<usize>,
+    pub(crate) visibility: Visibility,
+}
+
+impl Defs {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
@@ -554,6 +628,79 @@ pub struct Sym {
     pub(crate) name: String,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct SyntheticMethod {
+    pub(crate) name: String,
+    pub(crate) kind: SyntheticMethodKind,
+
+    /// ID of the `Send` (ex. `attr_reader :foo`) or `Alias` that defines the method.
+    pub(crate) origin_id: usize,
+
+    pub(crate) visibility: Visibility,
+
+    /// Is this a class method (ex. a Rails `scope`)?
+    pub(crate) singleton: bool,
+}
+
+impl SyntheticMethod {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn kind(&self) -> &SyntheticMethodKind {
+        &self.kind
+    }
+
+    pub fn origin_id(&self) -> usize {
+        self.origin_id
+    }
+
+    pub fn visibility(&self) -> Visibility {
+        self.visibility
+    }
+
+    pub fn singleton(&self) -> bool {
+        self.singleton
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub enum SyntheticMethodKind {
+    AttrReader,
+    AttrWriter,
+
+    /// `alias` or `alias_method`.
+    Alias {
+        original: String,
+    },
+
+    /// Generated by a DSL call, ex. `has_many :posts`. See `crate::dsl`.
+    Dsl {
+        expander: String,
+        macro_name: String,
+    },
+}
+
+/// A constant generated by a DSL call. See `crate::dsl`.
+///
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct SyntheticConstant {
+    pub(crate) name: String,
+
+    /// ID of the `Send` that defines the constant.
+    pub(crate) origin_id: usize,
+}
+
+impl SyntheticConstant {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+
+    pub fn origin_id(&self) -> usize {
+        self.origin_id
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct Undef {
     pub(crate) name_ids: Vec<usize>,
@@ -576,6 +723,38 @@ pub struct UntilPost {
     pub(crate) body_id: usize,
 }
 
+/// A method's visibility, as set by `public`, `protected`, `private` or `module_function`.
+///
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Visibility {
+    #[default]
+    Public,
+    Protected,
+    Private,
+
+    /// A private instance method, plus a public singleton method copy of it.
+    ModuleFunction,
+}
+
+impl Visibility {
+    pub(crate) fn from_method_name(method_name: &str) -> Option<Self> {
+        match method_name {
+            "public" => Some(Self::Public),
+            "protected" => Some(Self::Protected),
+            "private" => Some(Self::Private),
+            "module_function" => Some(Self::ModuleFunction),
+            _ => None,
+        }
+    }
+
+    /// Can the method be called with an explicit receiver (ex. `foo.bar`)? `protected` methods
+    /// can, as long as it's from within the same class hierarchy.
+    ///
+    pub fn callable_with_explicit_receiver(&self) -> bool {
+        matches!(self, Self::Public | Self::Protected)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct When {
     pub(crate) pattern_ids: Vec<usize>,
@@ -1,25 +1,62 @@
-use std::collections::btree_map::Entry;
+use std::collections::{btree_map::Entry, HashMap};
 
 use lib_ruby_parser::{nodes as lrp_nodes, traverse::visitor::Visitor};
 use tracing::trace;
 
 use crate::{
+    dsl::{self, Expanders, SyntheticDefinition},
     location::{Loc, LocNode, NodeType},
     lrp_extensions::{NameFromNode, OptionNameFromNode},
+    metaprogramming::{self, ClassConstructorCall, SynthesizedMethod},
     scoped_index::{nodes::*, Node, NodeProperties, ScopedIndex},
     ScopeGate, ScopeGateNode,
 };
 
+/// Methods that Ruby always makes private, regardless of where they're defined.
+///
+const ALWAYS_PRIVATE: &[&str] = &[
+    "initialize",
+    "initialize_copy",
+    "initialize_clone",
+    "initialize_dup",
+    "respond_to_missing?",
+];
+
 #[derive(Default)]
 pub(crate) struct Transformer {
     current_id: usize,
     current_scope_gate: ScopeGate,
+    visibility_frames: Vec<VisibilityFrame>,
+    expanders: Expanders,
+
+    /// How many `class << self` bodies we're in. `class << self` doesn't add to the scope gate, so
+    /// this (as of each `Def`, by ID, in `def_singleton_class_depths`) is what tells its `def`s
+    /// apart from instance methods.
+    singleton_class_depth: usize,
+    def_singleton_class_depths: HashMap<usize, usize>,
 
     locs: Vec<LocNode>,
     scoped_index: ScopedIndex,
 }
 
+/// The default visibility of `def`s in a class, module, `class << self` or method body, which a
+/// bare `private` (or `public`, etc.) changes for the rest of that body.
+///
+struct VisibilityFrame {
+    default: Visibility,
+
+    /// Every node in the body has a greater ID than this.
+    first_id: usize,
+}
+
 impl Transformer {
+    pub(crate) fn with_expanders(expanders: Expanders) -> Self {
+        Self {
+            expanders,
+            ..Default::default()
+        }
+    }
+
     pub(crate) fn finish(self) -> (Vec<LocNode>, ScopedIndex) {
         (self.locs, self.scoped_index)
     }
@@ -34,14 +71,133 @@ impl Transformer {
         F: Fn(&mut Self) -> T,
     {
         self.current_scope_gate.push_owned(scope_gate_node);
+        self.push_visibility_frame();
 
         let result = func(self);
 
+        self.pop_visibility_frame();
         self.current_scope_gate.pop();
 
         result
     }
 
+    fn push_visibility_frame(&mut self) {
+        self.visibility_frames.push(VisibilityFrame {
+            default: Visibility::Public,
+            first_id: self.current_id,
+        });
+    }
+
+    fn pop_visibility_frame(&mut self) {
+        self.visibility_frames.pop();
+    }
+
+    // Top-level methods end up as private methods on `Object`.
+    //
+    fn def_visibility(&self, method_name: &str) -> Visibility {
+        if ALWAYS_PRIVATE.contains(&method_name) {
+            return Visibility::Private;
+        }
+
+        self.visibility_frames
+            .last()
+            .map_or(Visibility::Private, |frame| frame.default)
+    }
+
+    // Handles `private` (and friends) when called without arguments, which changes the default for
+    // the rest of the body, and with arguments, ex. `private :foo` or `private def foo`, which
+    // changes the visibility of the named methods that have already been defined in this body.
+    //
+    fn apply_visibility_call(&mut self, node: &lrp_nodes::Send, arg_ids: &[usize]) {
+        if node.recv.is_some() {
+            return;
+        }
+
+        let (visibility, singleton) = match node.method_name.as_str() {
+            "private_class_method" => (Visibility::Private, true),
+            "public_class_method" => (Visibility::Public, true),
+            method_name => match Visibility::from_method_name(method_name) {
+                Some(visibility) => (visibility, false),
+                None => return,
+            },
+        };
+
+        if arg_ids.is_empty() {
+            if let (false, Some(frame)) = (singleton, self.visibility_frames.last_mut()) {
+                frame.default = visibility;
+            }
+
+            return;
+        }
+
+        let first_id = self
+            .visibility_frames
+            .last()
+            .map_or(0, |frame| frame.first_id);
+
+        // `private_class_method :foo` is about a `def foo` in `class << self`; `private :foo`, one
+        // that's not.
+        let def_depth = self.singleton_class_depth + usize::from(singleton);
+        let def_depths = &self.def_singleton_class_depths;
+
+        let Some(nodes) = self
+            .scoped_index
+            .inner_mut()
+            .get_mut(&self.current_scope_gate)
+        else {
+            return;
+        };
+
+        let method_names: Vec<String> = arg_ids
+            .iter()
+            .filter_map(|arg_id| nodes.iter().rev().find(|n| n.id == *arg_id))
+            .filter_map(|arg| match &arg.properties {
+                NodeProperties::Sym(sym) => Some(sym.name.clone()),
+                NodeProperties::Def(def) => Some(def.name.clone()),
+                NodeProperties::Defs(defs) => Some(defs.name.clone()),
+                _ => None,
+            })
+            .collect();
+
+        // ex. `private attr_reader :foo`
+        if !singleton {
+            for node in nodes.iter_mut() {
+                if let NodeProperties::SyntheticMethod(method) = &mut node.properties {
+                    if !method.singleton && arg_ids.contains(&method.origin_id) {
+                        method.visibility = visibility;
+                    }
+                }
+            }
+        }
+
+        for method_name in method_names {
+            let target = nodes
+                .iter_mut()
+                .rev()
+                .filter(|n| n.id > first_id)
+                .find_map(|n| match &mut n.properties {
+                    NodeProperties::Def(def)
+                        if def.name == method_name && def_depths.get(&n.id) == Some(&def_depth) =>
+                    {
+                        Some(&mut def.visibility)
+                    }
+                    NodeProperties::SyntheticMethod(method)
+                        if method.singleton == singleton && method.name == method_name =>
+                    {
+                        Some(&mut method.visibility)
+                    }
+                    NodeProperties::Defs(defs) if singleton && defs.name == method_name => {
+                        Some(&mut defs.visibility)
+                    }
+                    _ => None,
+                });
+
+            if let Some(target) = target {
+                *target = visibility;
+            }
+        }
+    }
+
     fn insert_scope_node(&mut self, node: Node) {
         match self
             .scoped_index
@@ -91,11 +247,194 @@ impl Transformer {
             .unwrap()
     }
 
+    // Adds the methods that a `Send` or `Alias` defines (ex. `attr_reader :foo`) to both the
+    // `LocNode`s and the scoped index. These need to be inserted before the `Send`/`Alias` node
+    // itself, since its parent expects it to be the last node in the scope.
+    //
+    fn insert_synthesized_methods(
+        &mut self,
+        methods: Vec<SynthesizedMethod>,
+        origin_id: usize,
+        expression_l: Loc,
+    ) {
+        for method in methods {
+            self.locs.push(LocNode {
+                node: method.node_type(),
+                name: method.name.clone(),
+                expression_l,
+                name_l: method.name_l,
+                scope_gate: self.current_scope_gate.clone(),
+            });
+
+            // An alias is a copy of the original method, visibility included. DSLs define their
+            // methods elsewhere (ex. in a generated module), so a bare `private` doesn't apply.
+            let visibility = match &method.kind {
+                SyntheticMethodKind::Alias { original } => {
+                    self.method_visibility(original).unwrap_or_default()
+                }
+                SyntheticMethodKind::Dsl { .. } => Visibility::Public,
+                _ => self.def_visibility(&method.name),
+            };
+
+            let id = self.new_id();
+
+            self.insert_scope_node(Node {
+                id,
+                properties: NodeProperties::SyntheticMethod(SyntheticMethod {
+                    name: method.name,
+                    kind: method.kind,
+                    origin_id,
+                    visibility,
+                    singleton: method.singleton,
+                }),
+            });
+        }
+    }
+
+    // Runs the DSL expanders over a receiverless `Send` and adds the methods and constants they
+    // generate. Like `insert_synthesized_methods`, this needs to happen before the `Send` node is
+    // inserted.
+    //
+    fn insert_dsl_definitions(&mut self, node: &lrp_nodes::Send, origin_id: usize) {
+        let Some(call) = dsl::Call::from_send(node) else {
+            return;
+        };
+
+        let mut methods = Vec::new();
+        let mut constants = Vec::new();
+
+        for expander in self.expanders.iter() {
+            for definition in expander.expand(&call, &self.current_scope_gate) {
+                match definition {
+                    SyntheticDefinition::Method {
+                        name,
+                        singleton,
+                        name_l,
+                    } => methods.push(SynthesizedMethod {
+                        name,
+                        kind: SyntheticMethodKind::Dsl {
+                            expander: expander.name().to_string(),
+                            macro_name: call.method_name.clone(),
+                        },
+                        name_l,
+                        singleton,
+                    }),
+                    SyntheticDefinition::Constant { name, name_l } => {
+                        constants.push((name, name_l));
+                    }
+                }
+            }
+        }
+
+        self.insert_synthesized_methods(methods, origin_id, call.expression_l);
+
+        for (name, name_l) in constants {
+            self.locs.push(LocNode {
+                node: NodeType::Casgn,
+                name: name.clone(),
+                expression_l: call.expression_l,
+                name_l,
+                scope_gate: self.current_scope_gate.clone(),
+            });
+
+            let id = self.new_id();
+
+            self.insert_scope_node(Node {
+                id,
+                properties: NodeProperties::SyntheticConstant(SyntheticConstant {
+                    name,
+                    origin_id,
+                }),
+            });
+        }
+    }
+
+    // Visibility of the most recently defined instance method named `method_name` in the current
+    // scope.
+    //
+    fn method_visibility(&self, method_name: &str) -> Option<Visibility> {
+        self.scoped_index
+            .get(&self.current_scope_gate)?
+            .iter()
+            .rev()
+            .find_map(|node| match &node.properties {
+                NodeProperties::Def(def) if def.name == method_name => Some(def.visibility),
+                NodeProperties::SyntheticMethod(method)
+                    if !method.singleton && method.name == method_name =>
+                {
+                    Some(method.visibility)
+                }
+                _ => None,
+            })
+    }
+
+    // Like `on_class` (or `on_module`), but for ex. `Point = Struct.new(:x, :y) do ... end`: the
+    // constant opens a scope gate for its block, which also holds the accessors for the members.
+    //
+    fn on_class_constructor(
+        &mut self,
+        node: &lrp_nodes::Casgn,
+        constructor: ClassConstructorCall<'_>,
+    ) {
+        let is_module = constructor.kind.is_module();
+        let expression_l: Loc = node.expression_l.into();
+
+        self.locs.push(LocNode {
+            node: if is_module {
+                NodeType::Module
+            } else {
+                NodeType::Class
+            },
+            name: node.name.clone(),
+            expression_l,
+            name_l: node.name_l.into(),
+            scope_gate: self.current_scope_gate.clone(),
+        });
+
+        let id = self.new_id();
+        let scope_id = self.visit_optional_child(&node.scope);
+        let recv_id = self.visit_child(constructor.recv);
+        let arg_ids = self.visit_children(constructor.args);
+
+        let superclass_id = match constructor.kind {
+            ClassConstructorKind::Struct | ClassConstructorKind::Data => Some(recv_id),
+            ClassConstructorKind::Class => arg_ids.first().copied(),
+            ClassConstructorKind::Module => None,
+        };
+
+        let scope_gate_node = if is_module {
+            ScopeGateNode::Module(node.name.clone())
+        } else {
+            ScopeGateNode::Class(node.name.clone())
+        };
+
+        let members = constructor.members();
+
+        let body_id = self.do_in_scope(scope_gate_node, |me| {
+            me.insert_synthesized_methods(members.clone(), id, expression_l);
+
+            constructor.body.map(|body| me.visit_child(body))
+        });
+
+        self.insert_scope_node(Node {
+            id,
+            properties: NodeProperties::ClassConstructor(ClassConstructor {
+                name: node.name.clone(),
+                kind: constructor.kind,
+                scope_id,
+                superclass_id,
+                arg_ids,
+                body_id,
+            }),
+        });
+    }
+
     fn make_empty_body(&mut self, begin: usize, end: usize) {
         self.locs.push(LocNode {
             node: NodeType::EmptyBody,
             name: String::new(),
             expression_l: Loc { begin, end },
+            name_l: Loc { begin, end },
             scope_gate: self.current_scope_gate.clone(),
         });
 
@@ -114,6 +453,12 @@ impl Visitor for Transformer {
         let to_id = self.visit_child(&node.to);
         let from_id = self.visit_child(&node.from);
 
+        self.insert_synthesized_methods(
+            metaprogramming::from_alias(node).into_iter().collect(),
+            id,
+            node.expression_l.into(),
+        );
+
         self.insert_scope_node(Node {
             id,
             properties: NodeProperties::Alias(Alias { to_id, from_id }),
@@ -294,6 +639,19 @@ impl Visitor for Transformer {
     }
 
     fn on_casgn(&mut self, node: &lrp_nodes::Casgn) {
+        if let Some(constructor) = ClassConstructorCall::from_casgn(node) {
+            self.on_class_constructor(node, constructor);
+            return;
+        }
+
+        self.locs.push(LocNode {
+            node: NodeType::Casgn,
+            name: node.name.clone(),
+            expression_l: node.expression_l.into(),
+            name_l: node.name_l.into(),
+            scope_gate: self.current_scope_gate.clone(),
+        });
+
         let id = self.new_id();
         let scope_id = self.visit_optional_child(&node.scope);
         let value_id = self.visit_optional_child(&node.value);
@@ -324,6 +682,7 @@ impl Visitor for Transformer {
             node: NodeType::Class,
             name: name.clone(),
             expression_l: node.expression_l.into(),
+            name_l: (*node.name.expression()).into(),
             scope_gate: self.current_scope_gate.clone(),
         });
 
@@ -441,12 +800,15 @@ impl Visitor for Transformer {
             node: NodeType::Def,
             name: node.name.clone(),
             expression_l: node.expression_l.into(),
+            name_l: node.name_l.into(),
             scope_gate: self.current_scope_gate.clone(),
         });
 
         // Not sure it matters in practice, but let's just keep the class's ID a lower number than
         // its children that were about to visit.
         let id = self.new_id();
+        self.def_singleton_class_depths
+            .insert(id, self.singleton_class_depth);
 
         let args_id = self.visit_optional_child(&node.args);
 
@@ -474,6 +836,7 @@ impl Visitor for Transformer {
                 name: node.name.clone(),
                 args_id,
                 body_id,
+                visibility: self.def_visibility(&node.name),
             }),
         });
     }
@@ -493,6 +856,7 @@ impl Visitor for Transformer {
             node: NodeType::Defs,
             name: node.name.clone(),
             expression_l: node.expression_l.into(),
+            name_l: node.name_l.into(),
             scope_gate: self.current_scope_gate.clone(),
         });
 
@@ -528,6 +892,7 @@ impl Visitor for Transformer {
                 name: node.name.clone(),
                 args_id,
                 body_id,
+                visibility: Visibility::Public,
             }),
         });
     }
@@ -1137,6 +1502,7 @@ impl Visitor for Transformer {
             node: NodeType::Module,
             name: name.clone(),
             expression_l: node.expression_l.into(),
+            name_l: (*node.name.expression()).into(),
             scope_gate: self.current_scope_gate.clone(),
         });
         trace!("self.locs is now {:#?}", &self.locs);
@@ -1423,9 +1789,21 @@ impl Visitor for Transformer {
     }
 
     fn on_s_class(&mut self, node: &lrp_nodes::SClass) {
+        self.locs.push(LocNode {
+            node: NodeType::SClass,
+            name: node.name_from_node(),
+            expression_l: node.expression_l.into(),
+            name_l: (*node.expr.expression()).into(),
+            scope_gate: self.current_scope_gate.clone(),
+        });
+
         let id = self.new_id();
         let expr_id = self.visit_child(&node.expr);
+        self.push_visibility_frame();
+        self.singleton_class_depth += 1;
         let body_id = self.visit_optional_child(&node.body);
+        self.singleton_class_depth -= 1;
+        self.pop_visibility_frame();
 
         self.insert_scope_node(Node {
             id,
@@ -1447,6 +1825,15 @@ impl Visitor for Transformer {
         let recv_id = self.visit_optional_child(&node.recv);
         let arg_ids = self.visit_children(&node.args);
 
+        self.insert_synthesized_methods(
+            metaprogramming::from_send(node),
+            id,
+            node.expression_l.into(),
+        );
+        self.insert_dsl_definitions(node, id);
+
+        self.apply_visibility_call(node, &arg_ids);
+
         self.insert_scope_node(Node {
             id,
             properties: NodeProperties::Send(Send {
@@ -0,0 +1,169 @@
+use std::path::PathBuf;
+
+use ropey::Rope;
+use ruby_analyzer_tbc_parser::{
+    dsl::{Arg, Call, DslExpander, DslRegistry, Expanders, SyntheticDefinition},
+    parser::{parse, FileSource},
+    scoped_index::NodeProperties,
+    Database, ScopeGate, ScopeGateNode,
+};
+
+/// `class_attribute :settings` defines `settings` and `settings=`, on both the class and its
+/// instances.
+///
+struct ClassAttribute;
+
+impl DslExpander for ClassAttribute {
+    fn name(&self) -> &str {
+        "class_attribute"
+    }
+
+    fn expand(&self, call: &Call, _scope_gate: &ScopeGate) -> Vec<SyntheticDefinition> {
+        if call.method_name() != "class_attribute" {
+            return Vec::new();
+        }
+
+        call.names()
+            .flat_map(|(name, name_l)| {
+                [false, true].into_iter().flat_map(move |singleton| {
+                    [name.to_string(), format!("{name}=")].map(|name| SyntheticDefinition::Method {
+                        name,
+                        singleton,
+                        name_l,
+                    })
+                })
+            })
+            .collect()
+    }
+}
+
+/// `define_method(:foo) { ... }` and `const_set(:FOO, 1)`.
+///
+struct Reflection;
+
+impl DslExpander for Reflection {
+    fn name(&self) -> &str {
+        "reflection"
+    }
+
+    fn expand(&self, call: &Call, _scope_gate: &ScopeGate) -> Vec<SyntheticDefinition> {
+        match (call.method_name(), call.args().first().and_then(Arg::name)) {
+            ("define_method", Some((name, name_l))) => vec![SyntheticDefinition::Method {
+                name: name.to_string(),
+                singleton: false,
+                name_l,
+            }],
+            ("const_set", Some((name, name_l))) => vec![SyntheticDefinition::Constant {
+                name: name.to_string(),
+                name_l,
+            }],
+            _ => Vec::new(),
+        }
+    }
+}
+
+const CODE: &str = r#"class Widget
+  class_attribute :settings
+  define_method(:render) { settings }
+  const_set(:SIZES, [1, 2])
+  has_many :parts
+end"#;
+
+fn synthetic_names(database: &Database, file_source: FileSource) -> Vec<String> {
+    let (_loc_nodes, index) = parse(database, file_source);
+    let scope_gate = ScopeGate::new(vec![ScopeGateNode::Class("Widget".to_string())]);
+
+    index
+        .get(&scope_gate)
+        .into_iter()
+        .flatten()
+        .filter_map(|node| match node.properties() {
+            NodeProperties::SyntheticMethod(method) if method.singleton() => {
+                Some(format!("self.{}", method.name()))
+            }
+            NodeProperties::SyntheticMethod(method) => Some(method.name().to_string()),
+            NodeProperties::SyntheticConstant(constant) => Some(constant.name().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn builtins_by_default_test() {
+    let database = Database::default();
+    let file_source = FileSource::new(&database, PathBuf::new(), Rope::from_str(CODE));
+
+    assert_eq!(
+        vec!["parts", "parts=", "part_ids", "part_ids="],
+        synthetic_names(&database, file_source)
+    );
+}
+
+#[test]
+fn registered_expanders_test() {
+    let mut database = Database::default();
+    let file_source = FileSource::new(&database, PathBuf::new(), Rope::from_str(CODE));
+    let registry = DslRegistry::get(&database);
+
+    registry
+        .set_expanders(&mut database)
+        .to(Expanders::new().with(ClassAttribute).with(Reflection));
+
+    assert_eq!(
+        vec![
+            "settings",
+            "settings=",
+            "self.settings",
+            "self.settings=",
+            "render",
+            "SIZES"
+        ],
+        synthetic_names(&database, file_source)
+    );
+
+    // Changing the registry invalidates the parse results.
+    registry
+        .set_expanders(&mut database)
+        .to(Expanders::with_builtins().with(Reflection));
+
+    assert_eq!(
+        vec![
+            "render",
+            "SIZES",
+            "parts",
+            "parts=",
+            "part_ids",
+            "part_ids="
+        ],
+        synthetic_names(&database, file_source)
+    );
+}
+
+#[test]
+fn register_after_parsing_test() {
+    let mut database = Database::default();
+    let file_source = FileSource::new(&database, PathBuf::new(), Rope::from_str(CODE));
+
+    // Parsed with just the builtins...
+    assert_eq!(
+        vec!["parts", "parts=", "part_ids", "part_ids="],
+        synthetic_names(&database, file_source)
+    );
+
+    // ...then reparsed once a plugin is registered.
+    DslRegistry::get(&database)
+        .set_expanders(&mut database)
+        .to(Expanders::with_builtins().with(Reflection));
+
+    assert_eq!(
+        vec![
+            "render",
+            "SIZES",
+            "parts",
+            "parts=",
+            "part_ids",
+            "part_ids="
+        ],
+        synthetic_names(&database, file_source)
+    );
+}
@@ -0,0 +1,15 @@
+class Post < ApplicationRecord
+  belongs_to :user
+  has_and_belongs_to_many :tags
+
+  enum status: { draft: 0, published: 1 }, _suffix: :post, _scopes: false
+
+  delegate :email, to: :user
+
+  after_commit :notify_followers, on: :create
+
+  module Publishing
+    # Not in a class, so not expanded.
+    scope :recent, -> { order(created_at: :desc) }
+  end
+end
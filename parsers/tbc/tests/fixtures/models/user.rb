@@ -0,0 +1,22 @@
+class User < ApplicationRecord
+  has_secure_password
+
+  has_many :posts, dependent: :destroy
+  has_many :categories, through: :posts
+  has_one :profile
+  belongs_to :organization, optional: true
+
+  scope :active, -> { where(active: true) }
+  scope :admins, lambda { where(role: :admin) }
+
+  enum :role, [:member, :admin], prefix: true
+
+  delegate :name, :plan, to: :organization, prefix: true
+
+  validates :email, presence: true
+  before_save :normalize_email
+
+  private
+
+  def normalize_email; end
+end
@@ -130,3 +130,304 @@ end"#;
     //     assert!(begin.next_sibling().is_none());
     // }
 }
+
+mod method_visibility {
+    use ruby_analyzer_tbc_parser::scoped_index::{nodes::Visibility, NodeProperties};
+
+    use super::*;
+
+    const CODE: &str = r#"class Foo
+  def a; end
+
+  private
+
+  def b; end
+  def c; end
+  public :c
+
+  protected def d; end
+
+  def self.e; end
+  private_class_method :e
+
+  public
+
+  class << self
+    private
+
+    def f; end
+  end
+
+  def g; end
+end"#;
+
+    #[test]
+    fn test_visibilities() {
+        let database = Database::default();
+        let file_source = FileSource::new(&database, PathBuf::new(), Rope::from_str(CODE));
+
+        let (_loc_nodes, index) = parse(&database, file_source);
+
+        let mut visibilities: Vec<(&str, Visibility)> = index
+            .values()
+            .flatten()
+            .filter_map(|node| match node.properties() {
+                NodeProperties::Def(def) => Some((def.name(), def.visibility())),
+                NodeProperties::Defs(defs) => Some((defs.name(), defs.visibility())),
+                _ => None,
+            })
+            .collect();
+        visibilities.sort_by_key(|(name, _)| *name);
+
+        assert_eq!(
+            vec![
+                ("a", Visibility::Public),
+                ("b", Visibility::Private),
+                ("c", Visibility::Public),
+                ("d", Visibility::Protected),
+                ("e", Visibility::Private),
+                ("f", Visibility::Private),
+                // `private` in `class << self` doesn't leak out of it.
+                ("g", Visibility::Public),
+            ],
+            visibilities
+        );
+    }
+
+    #[test]
+    fn test_singleton_class_visibilities() {
+        let database = Database::default();
+        let code = r#"class Foo
+  def call; end
+
+  class << self
+    def build; end
+    def call; end
+  end
+
+  private_class_method :build
+  private :call
+end"#;
+        let file_source = FileSource::new(&database, PathBuf::new(), Rope::from_str(code));
+
+        let (_loc_nodes, index) = parse(&database, file_source);
+
+        let visibilities: Vec<(&str, Visibility)> = index
+            .values()
+            .flatten()
+            .filter_map(|node| match node.properties() {
+                NodeProperties::Def(def) => Some((def.name(), def.visibility())),
+                _ => None,
+            })
+            .collect();
+
+        // `private :call` is about the instance method, not the one in `class << self`.
+        assert_eq!(
+            vec![
+                ("call", Visibility::Private),
+                ("build", Visibility::Private),
+                ("call", Visibility::Public),
+            ],
+            visibilities
+        );
+    }
+}
+
+mod synthesized_methods {
+    use ruby_analyzer_tbc_parser::scoped_index::{
+        nodes::{SyntheticMethodKind, Visibility},
+        NodeProperties,
+    };
+
+    use super::*;
+
+    const CODE: &str = r#"class Foo
+  attr_accessor :name
+  private attr_reader :secret
+
+  def greet; end
+  alias hello greet
+
+  private
+
+  def helper; end
+  alias_method :assist, :helper
+end"#;
+
+    #[test]
+    fn test_synthetic_methods() {
+        let database = Database::default();
+        let file_source = FileSource::new(&database, PathBuf::new(), Rope::from_str(CODE));
+
+        let (loc_nodes, index) = parse(&database, file_source);
+
+        let methods: Vec<(&str, &SyntheticMethodKind, Visibility)> = index
+            .values()
+            .flatten()
+            .filter_map(|node| match node.properties() {
+                NodeProperties::SyntheticMethod(method) => {
+                    Some((method.name(), method.kind(), method.visibility()))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("name", &SyntheticMethodKind::AttrReader, Visibility::Public),
+                (
+                    "name=",
+                    &SyntheticMethodKind::AttrWriter,
+                    Visibility::Public
+                ),
+                (
+                    "secret",
+                    &SyntheticMethodKind::AttrReader,
+                    Visibility::Private
+                ),
+                (
+                    "hello",
+                    &SyntheticMethodKind::Alias {
+                        original: "greet".to_string()
+                    },
+                    Visibility::Public
+                ),
+                (
+                    "assist",
+                    &SyntheticMethodKind::Alias {
+                        original: "helper".to_string()
+                    },
+                    Visibility::Private
+                ),
+            ],
+            methods
+        );
+
+        // Each points at its symbol.
+        let assist = loc_nodes.iter().find(|n| n.name() == "assist").unwrap();
+        assert_eq!(":assist", &CODE[assist.name_l().as_range()]);
+
+        let hello = loc_nodes.iter().find(|n| n.name() == "hello").unwrap();
+        assert_eq!("hello", &CODE[hello.name_l().as_range()]);
+    }
+}
+
+mod class_constructors {
+    use ruby_analyzer_tbc_parser::{
+        scoped_index::{nodes::ClassConstructorKind, NodeProperties},
+        ScopeGate, ScopeGateNode,
+    };
+
+    use super::*;
+
+    const CODE: &str = r#"module Geometry
+  Point = Struct.new(:x, :y) do
+    def distance; end
+  end
+
+  Size = Data.define(:width, :height)
+  Error = Class.new(StandardError)
+  Helpers = Module.new do
+    def help; end
+  end
+  Origin = Point.new(0, 0)
+end"#;
+
+    #[test]
+    fn test_class_constructors() {
+        let database = Database::default();
+        let file_source = FileSource::new(&database, PathBuf::new(), Rope::from_str(CODE));
+
+        let (loc_nodes, index) = parse(&database, file_source);
+
+        let geometry = ScopeGate::new(vec![ScopeGateNode::Module("Geometry".to_string())]);
+
+        let constructors: Vec<(&str, ClassConstructorKind, bool)> = index
+            .get(&geometry)
+            .unwrap()
+            .iter()
+            .filter_map(|node| match node.properties() {
+                NodeProperties::ClassConstructor(constructor) => Some((
+                    constructor.name(),
+                    constructor.kind(),
+                    constructor.superclass_id().is_some(),
+                )),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            vec!["Point", "Size", "Error", "Helpers"],
+            constructors
+                .iter()
+                .map(|(name, _, _)| *name)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![
+                ClassConstructorKind::Struct,
+                ClassConstructorKind::Data,
+                ClassConstructorKind::Class,
+                ClassConstructorKind::Module
+            ],
+            constructors
+                .iter()
+                .map(|(_, kind, _)| *kind)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![true, true, true, false],
+            constructors
+                .iter()
+                .map(|(_, _, has_superclass)| *has_superclass)
+                .collect::<Vec<_>>()
+        );
+
+        // Each gets its own scope gate, with the members' accessors in it.
+        let names_in = |scope_gate: ScopeGate| -> Vec<String> {
+            loc_nodes
+                .iter()
+                .filter(|loc_node| loc_node.scope_gate() == &scope_gate)
+                .map(|loc_node| loc_node.name().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            vec!["x", "x=", "y", "y=", "distance"],
+            names_in(geometry.join(ScopeGateNode::Class("Point".to_string())))
+        );
+        assert_eq!(
+            vec!["width", "height"],
+            names_in(geometry.join(ScopeGateNode::Class("Size".to_string())))
+        );
+        assert_eq!(
+            vec!["help"],
+            names_in(geometry.join(ScopeGateNode::Module("Helpers".to_string())))
+        );
+
+        // Not a constructor: stays a constant.
+        assert!(names_in(geometry.clone()).contains(&"Origin".to_string()));
+    }
+}
+
+mod positions {
+    use lsp_types::Position;
+    use ruby_analyzer_tbc_parser::location::position_to_offset;
+
+    use super::*;
+
+    #[test]
+    fn test_position_to_offset() {
+        let code = Rope::from_str("héllo\r\nworld");
+
+        assert_eq!(3, position_to_offset(Position::new(0, 2), &code));
+        assert_eq!(9, position_to_offset(Position::new(1, 1), &code));
+
+        // Past the end of the line, before its line ending.
+        assert_eq!(6, position_to_offset(Position::new(0, 40), &code));
+
+        // Past the end of the file.
+        assert_eq!(13, position_to_offset(Position::new(1, 40), &code));
+        assert_eq!(13, position_to_offset(Position::new(7, 40), &code));
+    }
+}
@@ -0,0 +1,166 @@
+use std::path::PathBuf;
+
+use ropey::Rope;
+use ruby_analyzer_tbc_parser::{
+    parser::{parse, FileSource},
+    scoped_index::{nodes::SyntheticMethodKind, NodeProperties},
+    Database, ScopeGate, ScopeGateNode,
+};
+
+const USER: &str = include_str!("fixtures/models/user.rb");
+const POST: &str = include_str!("fixtures/models/post.rb");
+
+/// `(name, singleton, macro_name)` of the DSL methods in `scope_gate`.
+///
+fn dsl_methods(code: &str, scope_gate: &ScopeGate) -> Vec<(String, bool, String)> {
+    let database = Database::default();
+    let file_source = FileSource::new(&database, PathBuf::new(), Rope::from_str(code));
+
+    let (_loc_nodes, index) = parse(&database, file_source);
+
+    index
+        .get(scope_gate)
+        .into_iter()
+        .flatten()
+        .filter_map(|node| match node.properties() {
+            NodeProperties::SyntheticMethod(method) => match method.kind() {
+                SyntheticMethodKind::Dsl { macro_name, .. } => Some((
+                    method.name().to_string(),
+                    method.singleton(),
+                    macro_name.clone(),
+                )),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+fn names(methods: &[(String, bool, String)], macro_name: &str) -> Vec<String> {
+    methods
+        .iter()
+        .filter(|(_, _, m)| m == macro_name)
+        .map(|(name, singleton, _)| match singleton {
+            true => format!("self.{name}"),
+            false => name.clone(),
+        })
+        .collect()
+}
+
+fn class(name: &str) -> ScopeGate {
+    ScopeGate::new(vec![ScopeGateNode::Class(name.to_string())])
+}
+
+#[test]
+fn associations_test() {
+    let user = dsl_methods(USER, &class("User"));
+
+    assert_eq!(
+        vec![
+            "posts",
+            "posts=",
+            "post_ids",
+            "post_ids=",
+            "categories",
+            "categories=",
+            "category_ids",
+            "category_ids="
+        ],
+        names(&user, "has_many")
+    );
+    assert_eq!(
+        vec![
+            "profile",
+            "profile=",
+            "build_profile",
+            "create_profile",
+            "create_profile!",
+            "reload_profile"
+        ],
+        names(&user, "has_one")
+    );
+    assert_eq!(6, names(&user, "belongs_to").len());
+
+    let post = dsl_methods(POST, &class("Post"));
+    assert_eq!(
+        vec!["tags", "tags=", "tag_ids", "tag_ids="],
+        names(&post, "has_and_belongs_to_many")
+    );
+}
+
+#[test]
+fn scopes_test() {
+    let user = dsl_methods(USER, &class("User"));
+    assert_eq!(vec!["self.active", "self.admins"], names(&user, "scope"));
+
+    // Only classes get expanded.
+    let publishing = ScopeGate::new(vec![
+        ScopeGateNode::Class("Post".to_string()),
+        ScopeGateNode::Module("Publishing".to_string()),
+    ]);
+    assert!(dsl_methods(POST, &publishing).is_empty());
+}
+
+#[test]
+fn enum_test() {
+    let user = dsl_methods(USER, &class("User"));
+    assert_eq!(
+        vec![
+            "self.roles",
+            "role_member?",
+            "role_member!",
+            "self.role_member",
+            "self.not_role_member",
+            "role_admin?",
+            "role_admin!",
+            "self.role_admin",
+            "self.not_role_admin",
+        ],
+        names(&user, "enum")
+    );
+
+    let post = dsl_methods(POST, &class("Post"));
+    assert_eq!(
+        vec![
+            "self.statuses",
+            "draft_post?",
+            "draft_post!",
+            "published_post?",
+            "published_post!",
+        ],
+        names(&post, "enum")
+    );
+}
+
+#[test]
+fn delegate_test() {
+    let user = dsl_methods(USER, &class("User"));
+    assert_eq!(
+        vec!["organization_name", "organization_plan"],
+        names(&user, "delegate")
+    );
+
+    let post = dsl_methods(POST, &class("Post"));
+    assert_eq!(vec!["email"], names(&post, "delegate"));
+}
+
+#[test]
+fn secure_password_and_callbacks_test() {
+    let user = dsl_methods(USER, &class("User"));
+    assert_eq!(
+        vec![
+            "password=",
+            "password_confirmation=",
+            "authenticate_password",
+            "authenticate"
+        ],
+        names(&user, "has_secure_password")
+    );
+
+    // Validations and callbacks don't define methods.
+    assert!(names(&user, "validates").is_empty());
+    assert!(names(&user, "before_save").is_empty());
+
+    let post = dsl_methods(POST, &class("Post"));
+    assert!(names(&post, "after_commit").is_empty());
+}